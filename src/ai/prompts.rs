@@ -1,5 +1,15 @@
+use crate::engine::registry::{self, usage_line};
+
 /// Build the system prompt for AI-assisted JSON querying.
+///
+/// The transform reference is generated from the transform registry, so it
+/// includes transforms registered by embedding applications.
 pub fn build_system_prompt(schema_summary: &str) -> String {
+    let transforms = registry::snapshot()
+        .iter()
+        .map(|t| format!("  {} — {}", usage_line(t.as_ref()), t.help()))
+        .collect::<Vec<_>>()
+        .join("\n");
     format!(
         r##"You are a helpful data assistant for jdx, a JSON data explorer. You have access to the actual JSON data and its schema. Your job is to ANSWER the user's question directly in plain English.
 
@@ -17,14 +27,13 @@ pub fn build_system_prompt(schema_summary: &str) -> String {
 
 Path navigation: .field, .field.sub, .arr[0], .arr[-1], .arr[0:3], .arr[*]
 Filter predicates: .arr[field == value], .arr[field < 10], .arr[field != "x"]
+Date comparisons: .arr[created_at > date("2024-01-01")]
 Compound filters: .arr[price > 5 && price < 20], .arr[role == "admin" || role == "mod"]
 Transform commands:
-  :keys, :values, :count, :flatten, :pick f1,f2, :omit f1, :sort field,
-  :uniq, :group_by field, :filter field op value,
-  :sum field, :avg field, :min field, :max field,
-  :reverse, :upper, :lower, :split delimiter, :join separator
+{transforms}
 
 Transforms chain: .books :filter price < 10 :pick title,price :sort price
+Quote arguments with spaces or commas: :join ", ", :pick "first name",age
 Operators: ==, !=, <, >, <=, >=
 Logical operators in filters: && (AND), || (OR)
 
@@ -66,6 +75,25 @@ Question: "{question}"
     )
 }
 
+/// Placeholder for values withheld from the AI.
+pub const REDACTED: &str = "[redacted]";
+
+/// Replace every value in `data` with [`REDACTED`], keeping keys, nesting
+/// and array lengths, for inputs whose values are secrets.
+pub fn redact_values(data: &serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+    match data {
+        Value::Null => Value::Null,
+        Value::Array(items) => Value::Array(items.iter().map(redact_values).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), redact_values(value)))
+                .collect(),
+        ),
+        _ => Value::String(REDACTED.into()),
+    }
+}
+
 /// Truncate JSON data for inclusion in AI prompt.
 /// Returns a compact string representation, truncated if too large.
 pub fn truncate_data_for_prompt(data: &serde_json::Value, max_chars: usize) -> String {
@@ -104,6 +132,15 @@ mod tests {
         assert!(prompt.contains("NEVER use JSONPath"));
     }
 
+    #[test]
+    fn test_system_prompt_lists_every_registered_transform() {
+        let prompt = build_system_prompt("{}");
+        for name in registry::snapshot().names() {
+            assert!(prompt.contains(&format!("  {name}")), "missing {name}");
+        }
+        assert!(prompt.contains(":sort field [asc|desc]"));
+    }
+
     #[test]
     fn test_user_prompt_contains_question_and_data() {
         let prompt = build_user_prompt("find all users", "{\"users\": []}");
@@ -111,6 +148,21 @@ mod tests {
         assert!(prompt.contains("\"users\""));
     }
 
+    #[test]
+    fn test_redact_values_keeps_structure() {
+        let data = serde_json::json!({
+            "DB_PASSWORD": "hunter2",
+            "nested": {"port": 5432, "on": true, "list": ["a", null]}
+        });
+        assert_eq!(
+            redact_values(&data),
+            serde_json::json!({
+                "DB_PASSWORD": REDACTED,
+                "nested": {"port": REDACTED, "on": REDACTED, "list": [REDACTED, null]}
+            })
+        );
+    }
+
     #[test]
     fn test_truncate_small_data() {
         let data = serde_json::json!({"a": 1});
@@ -4,6 +4,7 @@ use crossterm::event::{self, KeyCode, KeyModifiers};
 
 use crate::ai::ollama::OllamaProvider;
 use crate::ai::openai::OpenAiProvider;
+use crate::ai::prompts;
 use crate::ai::service::{AiQuery, AiService};
 use crate::engine::schema::{format_schema, infer_schema};
 use crate::keys::{map_key_event, Action};
@@ -86,15 +87,13 @@ impl App {
                         self.ai.suggested_query = None;
                         self.ai.error = None;
                     }
-                    Action::DeleteWordBackward => {
-                        if self.ai.cursor > 0 {
-                            let mut target = self.ai.cursor - 1;
-                            while target > 0 && self.ai.input.as_bytes()[target] != b' ' {
-                                target -= 1;
-                            }
-                            self.ai.input.drain(target..self.ai.cursor);
-                            self.ai.cursor = target;
+                    Action::DeleteWordBackward if self.ai.cursor > 0 => {
+                        let mut target = self.ai.cursor - 1;
+                        while target > 0 && self.ai.input.as_bytes()[target] != b' ' {
+                            target -= 1;
                         }
+                        self.ai.input.drain(target..self.ai.cursor);
+                        self.ai.cursor = target;
                     }
                     Action::ScrollDown => {
                         self.ai.scroll = self.ai.scroll.saturating_add(1);
@@ -120,12 +119,29 @@ impl App {
             return;
         }
 
+        // Secret inputs (.env) are described by their keys only: values are
+        // redacted before anything, including schema samples, is built
+        let redacted;
+        let data = if self.redact_ai {
+            redacted = prompts::redact_values(&self.data);
+            &redacted
+        } else {
+            &self.data
+        };
+
         // Build schema summary for context
-        let schema = infer_schema(&self.data, self.config.display.schema_max_samples);
+        let schema = infer_schema(data, self.config.display.schema_max_samples);
         let schema_summary = format_schema(&schema, 0);
 
         // Build data context (actual values, truncated if large)
-        let data_context = crate::ai::prompts::truncate_data_for_prompt(&self.data, 4000);
+        let mut data_context = prompts::truncate_data_for_prompt(data, 4000);
+        if self.redact_ai {
+            data_context.insert_str(
+                0,
+                "(All values are redacted because the input holds secrets; \
+                 only keys and structure are shown.)\n",
+            );
+        }
 
         // Create provider from config
         let provider = &self.config.ai.provider;
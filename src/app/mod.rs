@@ -5,22 +5,48 @@ mod schema_handler;
 pub mod state;
 mod tree_handler;
 
+use std::cell::{Ref, RefCell};
+use std::ops::Range;
 use std::sync::mpsc;
+use std::time::{Duration, Instant};
 
 use crossterm::event::{Event, KeyCode, KeyModifiers};
 use serde_json::Value;
 
 use crate::config::AppConfig;
-use crate::engine::json::{pretty_print, traverse};
+use crate::engine::alias;
+use crate::engine::exec::ExecPolicy;
+use crate::engine::json::{get_available_keys, pretty_print, traverse};
+use crate::engine::lexer::{find_transform_start, parse_chain, SyntaxError};
 use crate::engine::query::{self, get_last_keyword};
+use crate::engine::registry::{self, TransformContext};
 use crate::engine::suggestion::Suggester;
-// get_available_keys used in render.rs and query_handler.rs
-use crate::engine::transform::apply_transform;
+use crate::engine::transform::{apply_transform_in, resolve_document, Documents};
 use crate::history::History;
 use crate::modes::AppMode;
 
 pub use state::{AiState, QueryState, SchemaState, TreeState};
 
+/// How long the query text must stay unchanged before the view evaluates a
+/// query that runs `:exec`, so typing does not start a command per keystroke.
+const EXEC_DEBOUNCE: Duration = Duration::from_millis(400);
+
+/// The result of evaluating one query text, kept so that every frame does
+/// not evaluate the query again (and re-run its `:exec` commands or scripts).
+struct Evaluation {
+    query: String,
+    result: Result<Option<Value>, String>,
+    /// Byte range of the query text that a transform error points at
+    error_span: Option<Range<usize>>,
+}
+
+/// Whether a query runs an external command with `:exec`.
+fn runs_exec(query: &str) -> bool {
+    find_transform_start(query)
+        .and_then(|start| parse_chain(&query[start..]).ok())
+        .is_some_and(|commands| commands.iter().any(|c| c.name == ":exec"))
+}
+
 /// The main application state.
 pub struct App {
     /// The root JSON data
@@ -53,16 +79,35 @@ pub struct App {
     pub split_view: bool,
     /// Monochrome mode
     pub monochrome: bool,
-    /// Receiver for streaming NDJSON lines from stdin
+    /// Secondary documents loaded with `--with name=file`, queried as `$name`
+    pub documents: Documents,
+    /// Whether the data is a stream of line records, NDJSON or logs
+    /// (`:exec` then sends one line per record)
+    pub ndjson_input: bool,
+    /// Whether the data was read from a multi-document YAML stream and is
+    /// an array of its documents (labelled as such in the tree view)
+    pub multi_document: bool,
+    /// Whether the input holds secrets (`.env`), so the AI panel is only
+    /// sent its keys and structure with every value redacted
+    pub redact_ai: bool,
+    /// Which commands `:exec` may run, from the `[exec]` config section
+    exec_policy: ExecPolicy,
+    /// Receiver for records streamed from stdin
     stdin_rx: Option<mpsc::Receiver<Value>>,
     /// Whether stdin is still streaming data
     pub streaming: bool,
+    /// The last query evaluation; cleared when the data changes
+    evaluation: RefCell<Option<Evaluation>>,
+    /// The query text the view is waiting on before it runs `:exec`, and
+    /// since when
+    exec_pending: RefCell<Option<(String, Instant)>>,
 }
 
 impl App {
     pub fn new(data: Value, query_output_mode: bool, monochrome: bool) -> Self {
         let (config, config_warning) = crate::config::load_config();
         let history = History::load();
+        let exec_policy = ExecPolicy::from(&config.exec);
 
         Self {
             data,
@@ -80,8 +125,15 @@ impl App {
             status_message: config_warning,
             split_view: false,
             monochrome,
+            documents: Documents::new(),
+            ndjson_input: false,
+            multi_document: false,
+            redact_ai: false,
+            exec_policy,
             stdin_rx: None,
             streaming: false,
+            evaluation: RefCell::new(None),
+            exec_pending: RefCell::new(None),
         }
     }
 
@@ -112,13 +164,30 @@ impl App {
         }
     }
 
-    /// Set the receiver for streaming NDJSON lines.
+    /// Set the secondary documents available as `$name` in queries and transforms.
+    pub fn set_documents(&mut self, documents: Documents) {
+        self.documents = documents;
+        self.invalidate_evaluation();
+    }
+
+    /// The policy `:exec` runs commands under.
+    pub fn exec_policy(&self) -> &ExecPolicy {
+        &self.exec_policy
+    }
+
+    /// Replace the policy `:exec` runs commands under.
+    pub fn set_exec_policy(&mut self, policy: ExecPolicy) {
+        self.exec_policy = policy;
+        self.invalidate_evaluation();
+    }
+
+    /// Set the receiver for records streamed from stdin.
     pub fn set_stdin_rx(&mut self, rx: mpsc::Receiver<Value>) {
         self.stdin_rx = Some(rx);
         self.streaming = true;
     }
 
-    /// Poll for new NDJSON lines from the background stdin reader (non-blocking).
+    /// Poll for new records from the background stdin reader (non-blocking).
     pub fn poll_stdin(&mut self) {
         if let Some(rx) = &self.stdin_rx {
             let mut got_data = false;
@@ -139,8 +208,9 @@ impl App {
                 }
             }
             if got_data {
-                // Invalidate cached schema so it gets regenerated with new data
+                // Invalidate cached schema and result so they reflect the new data
                 self.schema.text = None;
+                self.invalidate_evaluation();
             }
         }
     }
@@ -148,38 +218,142 @@ impl App {
     /// Get the current traversal result.
     /// Returns `Ok(Some(value))` on success, `Ok(None)` if the path doesn't match,
     /// or `Err(message)` if the query has a syntax error.
-    pub(crate) fn current_value(&self) -> Result<Option<Value>, String> {
-        // Check for transform command
-        if let Some(transform_start) = self.find_transform() {
-            let path_part = &self.query.text[..transform_start].trim_end();
-            let transform_part = &self.query.text[transform_start..];
+    pub fn current_value(&self) -> Result<Option<Value>, String> {
+        self.evaluation().result.clone()
+    }
 
-            let segments = query::parse(path_part).map_err(|e| format!("Query error: {e}"))?;
-            let result = traverse(&self.data, &segments);
-            if let Some(val) = result.value {
-                return apply_transform(&val, transform_part)
-                    .map(Some)
-                    .map_err(|e| format!("Transform error: {e}"));
+    /// The evaluation of the current query text, computed once per query.
+    fn evaluation(&self) -> Ref<'_, Evaluation> {
+        let stale = self
+            .evaluation
+            .borrow()
+            .as_ref()
+            .map_or(true, |e| e.query != self.query.text);
+        if stale {
+            *self.evaluation.borrow_mut() = Some(self.evaluate());
+        }
+        Ref::map(self.evaluation.borrow(), |e| {
+            e.as_ref().expect("evaluation was just stored")
+        })
+    }
+
+    /// The evaluation to draw. A query that runs `:exec` is evaluated once
+    /// its text has been left alone for [`EXEC_DEBOUNCE`]; until then the
+    /// previous evaluation stays on screen.
+    fn shown_evaluation(&self) -> Ref<'_, Evaluation> {
+        let cached = self.evaluation.borrow();
+        let waiting = cached
+            .as_ref()
+            .is_some_and(|e| e.query != self.query.text && runs_exec(&self.query.text));
+        if waiting {
+            let mut pending = self.exec_pending.borrow_mut();
+            let since = match &*pending {
+                Some((query, since)) if *query == self.query.text => *since,
+                _ => {
+                    let now = Instant::now();
+                    *pending = Some((self.query.text.clone(), now));
+                    now
+                }
+            };
+            if since.elapsed() < EXEC_DEBOUNCE {
+                return Ref::map(cached, |e| e.as_ref().expect("checked above"));
+            }
+        }
+        drop(cached);
+        self.evaluation()
+    }
+
+    /// The value shown in the JSON view.
+    pub(crate) fn shown_value(&self) -> Ref<'_, Option<Value>> {
+        Ref::map(self.shown_evaluation(), |e| match &e.result {
+            Ok(value) => value,
+            Err(_) => &None,
+        })
+    }
+
+    /// The error span highlighted in the query bar.
+    pub(crate) fn shown_error_span(&self) -> Option<Range<usize>> {
+        self.shown_evaluation().error_span.clone()
+    }
+
+    /// Forget the last evaluation, after the data it was computed from changed.
+    fn invalidate_evaluation(&self) {
+        self.evaluation.replace(None);
+    }
+
+    fn evaluate(&self) -> Evaluation {
+        let query = self.query.text.clone();
+        let Some(start) = self.find_transform() else {
+            return Evaluation {
+                result: self.resolve_path(&query),
+                query,
+                error_span: None,
+            };
+        };
+        let path_part = query[..start].trim_end_matches([' ', '|']);
+        let transform_part = &query[start..];
+        let offset = |span: Range<usize>| start + span.start..start + span.end;
+
+        let (result, error_span) = match self.resolve_path(path_part) {
+            Ok(Some(val)) => {
+                match apply_transform_in(&val, transform_part, &self.transform_context()) {
+                    Ok(value) => (Ok(Some(value)), None),
+                    Err(e) => {
+                        let span = e
+                            .downcast_ref::<SyntaxError>()
+                            .map(|e| offset(e.span.clone()));
+                        (Err(format!("Transform error: {e}")), span)
+                    }
+                }
             }
-            return Ok(None);
+            // Syntax errors in the transforms are highlighted even when the
+            // path matches nothing
+            Ok(None) => (
+                Ok(None),
+                parse_chain(transform_part).err().map(|e| offset(e.span)),
+            ),
+            Err(e) => (
+                Err(e),
+                parse_chain(transform_part).err().map(|e| offset(e.span)),
+            ),
+        };
+        Evaluation {
+            query,
+            result,
+            error_span,
+        }
+    }
+
+    fn transform_context(&self) -> TransformContext<'_> {
+        TransformContext {
+            docs: &self.documents,
+            stream: self.ndjson_input,
+            exec: &self.exec_policy,
         }
+    }
 
-        let segments = query::parse(&self.query.text).map_err(|e| format!("Query error: {e}"))?;
+    /// Resolve the path part of a query against the main data, or against a
+    /// secondary document when it starts with `$name`.
+    fn resolve_path(&self, path: &str) -> Result<Option<Value>, String> {
+        if path.starts_with('$') {
+            return resolve_document(path, &self.documents)
+                .map(Some)
+                .map_err(|e| format!("Query error: {e}"));
+        }
+        let segments = query::parse(path).map_err(|e| format!("Query error: {e}"))?;
         let result = traverse(&self.data, &segments);
         Ok(result.value)
     }
 
     /// Check if query contains a transform command and return its start position.
     fn find_transform(&self) -> Option<usize> {
-        for (i, c) in self.query.text.chars().enumerate() {
-            if c == ':' && i > 0 {
-                let prev = self.query.text.as_bytes().get(i - 1).copied();
-                if prev == Some(b' ') || prev == Some(b'|') {
-                    return Some(i);
-                }
-            }
-        }
-        None
+        find_transform_start(&self.query.text)
+    }
+
+    /// Byte range of the query text that a transform error points at, so
+    /// the query bar can highlight it.
+    pub fn query_error_span(&self) -> Option<Range<usize>> {
+        self.evaluation().error_span.clone()
     }
 
     /// Get the parent value (for suggestions).
@@ -198,9 +372,28 @@ impl App {
         result.value.unwrap_or(self.data.clone())
     }
 
+    /// Tab-completion candidates and the partial word they complete:
+    /// transform commands and `:@aliases` for a word starting with `:`,
+    /// otherwise the keys available at the current path.
+    pub(crate) fn completion_source(&self) -> (Vec<String>, String) {
+        let text = &self.query.text;
+        let word = text.rsplit(char::is_whitespace).next().unwrap_or("");
+        if word.starts_with(':') && word.len() < text.len() {
+            let mut names = registry::snapshot().names();
+            names.extend(alias::command_names());
+            return (names, word.to_string());
+        }
+        (
+            get_available_keys(&self.parent_value()),
+            get_last_keyword(text),
+        )
+    }
+
     /// Get stats string for the status bar.
     pub(crate) fn stats(&self) -> String {
-        let base = match self.current_value() {
+        let evaluation = self.shown_evaluation();
+        let base = match &evaluation.result {
+            _ if evaluation.query != self.query.text => "waiting to run :exec".into(),
             Ok(Some(Value::Object(map))) => format!("{} keys", map.len()),
             Ok(Some(Value::Array(arr))) => format!("{} items", arr.len()),
             Ok(Some(Value::String(s))) => format!("{} chars", s.len()),
@@ -208,7 +401,13 @@ impl App {
             Ok(Some(Value::Bool(b))) => b.to_string(),
             Ok(Some(Value::Null)) => "null".into(),
             Ok(None) => "no match".into(),
-            Err(_) => "syntax error".into(),
+            // Failures of external commands are not syntax errors; show why
+            Err(e) => match e.strip_prefix("Transform error: ") {
+                Some(msg) if msg.starts_with(":exec ") => {
+                    msg.lines().next().unwrap_or(msg).to_string()
+                }
+                _ => "syntax error".into(),
+            },
         };
         if self.streaming {
             format!("{base} (streaming...)")
@@ -258,3 +457,52 @@ impl App {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_app_evaluation_follows_data() {
+        let mut app = App::new(json!({"n": 1}), false, true);
+        app.query.text = "$extra :count".into();
+        assert!(app.current_value().is_err());
+
+        let mut documents = crate::engine::transform::Documents::new();
+        documents.insert("extra".into(), json!([1, 2, 3]));
+        app.set_documents(documents);
+        assert_eq!(app.current_value().unwrap(), Some(json!(3)));
+
+        app.data = json!([]);
+        app.query.text = ". :count".into();
+        assert_eq!(app.current_value().unwrap(), Some(json!(0)));
+        let (tx, rx) = mpsc::channel();
+        app.set_stdin_rx(rx);
+        tx.send(json!({"id": 1})).unwrap();
+        app.poll_stdin();
+        assert_eq!(app.current_value().unwrap(), Some(json!(1)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_view_waits_for_typing_to_stop_before_exec() {
+        let mut app = App::new(json!([1, 2]), false, true);
+        app.set_exec_policy(ExecPolicy {
+            allow: vec!["sh".into()],
+            ..ExecPolicy::default()
+        });
+        app.query.text = ". :count".into();
+        assert_eq!(*app.shown_value(), Some(json!(2)));
+
+        app.query.text = r#". :exec sh -c "echo 7""#.into();
+        assert_eq!(*app.shown_value(), Some(json!(2)));
+        assert_eq!(app.stats(), "waiting to run :exec");
+        std::thread::sleep(EXEC_DEBOUNCE);
+        assert_eq!(*app.shown_value(), Some(json!(7)));
+
+        // Confirming does not wait
+        app.query.text = r#". :exec sh -c "echo 8""#.into();
+        assert_eq!(app.current_value().unwrap(), Some(json!(8)));
+    }
+}
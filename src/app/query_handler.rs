@@ -1,7 +1,5 @@
 use crossterm::event;
 
-use crate::engine::json::get_available_keys;
-use crate::engine::query::get_last_keyword;
 use crate::engine::schema::{format_schema, infer_schema};
 use crate::keys::{map_key_event, Action};
 use crate::modes::AppMode;
@@ -19,17 +17,13 @@ impl App {
                 self.query.scroll = 0;
                 self.status_message = None;
             }
-            Action::Backspace => {
-                if self.query.cursor > 0 {
-                    self.query.cursor -= 1;
-                    self.query.text.remove(self.query.cursor);
-                    self.query.show_candidates = false;
-                }
+            Action::Backspace if self.query.cursor > 0 => {
+                self.query.cursor -= 1;
+                self.query.text.remove(self.query.cursor);
+                self.query.show_candidates = false;
             }
-            Action::Delete => {
-                if self.query.cursor < self.query.text.len() {
-                    self.query.text.remove(self.query.cursor);
-                }
+            Action::Delete if self.query.cursor < self.query.text.len() => {
+                self.query.text.remove(self.query.cursor);
             }
             Action::CursorLeft => {
                 self.query.cursor = self.query.cursor.saturating_sub(1);
@@ -48,20 +42,18 @@ impl App {
                 self.query.cursor = 1;
                 self.query.show_candidates = false;
             }
-            Action::DeleteWordBackward => {
+            Action::DeleteWordBackward if self.query.cursor > 0 => {
                 // Delete from cursor back to previous `.` or `[`
-                if self.query.cursor > 0 {
-                    let mut target = self.query.cursor - 1;
-                    while target > 0 {
-                        let c = self.query.text.as_bytes()[target] as char;
-                        if c == '.' || c == '[' {
-                            break;
-                        }
-                        target -= 1;
+                let mut target = self.query.cursor - 1;
+                while target > 0 {
+                    let c = self.query.text.as_bytes()[target] as char;
+                    if c == '.' || c == '[' {
+                        break;
                     }
-                    self.query.text.drain(target..self.query.cursor);
-                    self.query.cursor = target;
+                    target -= 1;
                 }
+                self.query.text.drain(target..self.query.cursor);
+                self.query.cursor = target;
             }
             Action::Tab => {
                 self.handle_tab(false);
@@ -127,7 +119,11 @@ impl App {
             }
             Action::SwitchToSchema => {
                 let schema = infer_schema(&self.data, self.config.display.schema_max_samples);
-                self.schema.text = Some(format_schema(&schema, 0));
+                let inferred = format_schema(&schema, 0);
+                self.schema.text = Some(match &self.schema.source {
+                    Some(source) => format!("{source}\nInferred from data:\n{inferred}"),
+                    None => inferred,
+                });
                 self.mode = AppMode::Schema;
             }
             Action::ToggleHelp => {
@@ -159,9 +155,7 @@ impl App {
     }
 
     pub(super) fn handle_tab(&mut self, reverse: bool) {
-        let parent = self.parent_value();
-        let keys = get_available_keys(&parent);
-        let last_keyword = get_last_keyword(&self.query.text);
+        let (keys, last_keyword) = self.completion_source();
         let candidates = self.suggester.get_candidates(&keys, &last_keyword);
 
         if candidates.is_empty() {
@@ -184,9 +178,7 @@ impl App {
     }
 
     pub(super) fn apply_candidate(&mut self) {
-        let parent = self.parent_value();
-        let keys = get_available_keys(&parent);
-        let last_keyword = get_last_keyword(&self.query.text);
+        let (keys, last_keyword) = self.completion_source();
         let candidates = self.suggester.get_candidates(&keys, &last_keyword);
 
         if let Some(candidate) = candidates.get(self.query.candidate_idx) {
@@ -3,8 +3,7 @@ use ratatui::{
     Frame,
 };
 
-use crate::engine::json::get_available_keys;
-use crate::engine::query::{self, get_last_keyword};
+use crate::engine::query;
 use crate::modes::AppMode;
 use crate::widgets::ai_panel::AiPanelWidget;
 use crate::widgets::candidate_popup::CandidatePopupWidget;
@@ -12,7 +11,7 @@ use crate::widgets::help_overlay::HelpOverlayWidget;
 use crate::widgets::json_view::JsonViewWidget;
 use crate::widgets::query_input::QueryInputWidget;
 use crate::widgets::status_bar::StatusBarWidget;
-use crate::widgets::tree_view::{build_tree, TreeViewWidget};
+use crate::widgets::tree_view::{build_tree, label_documents, TreeViewWidget};
 
 use super::App;
 
@@ -85,7 +84,10 @@ impl App {
             .split(v_chunks[0]);
 
         // Tree view on the left
-        let nodes = build_tree(&self.data, &self.tree.expanded);
+        let mut nodes = build_tree(&self.data, &self.tree.expanded);
+        if self.multi_document {
+            label_documents(&mut nodes, &self.data);
+        }
         let tree = TreeViewWidget {
             nodes: &nodes,
             selected: self.tree.selected,
@@ -106,15 +108,15 @@ impl App {
     }
 
     fn render_query_input(&self, frame: &mut Frame, area: Rect) {
-        let parent = self.parent_value();
-        let keys = get_available_keys(&parent);
-        let last_keyword = get_last_keyword(&self.query.text);
+        let (keys, last_keyword) = self.completion_source();
         let completion = self.suggester.get_completion(&keys, &last_keyword);
         let completion_text = completion.map(|(c, _)| c);
         let completion_ref = completion_text.as_deref();
 
         let query_parse_result = query::parse(&self.query.text);
-        let is_error = query_parse_result.is_err() && self.query.text.len() > 1;
+        let error_span = self.shown_error_span();
+        let is_error =
+            (query_parse_result.is_err() && self.query.text.len() > 1) || error_span.is_some();
 
         let query_focused = self.mode == AppMode::Query;
         let widget = QueryInputWidget {
@@ -122,6 +124,7 @@ impl App {
             cursor: self.query.cursor,
             completion: completion_ref,
             error: is_error,
+            error_span,
             focused: query_focused,
         };
 
@@ -135,7 +138,7 @@ impl App {
     }
 
     fn render_json_view(&self, frame: &mut Frame, area: Rect) {
-        let value = self.current_value().unwrap_or(None);
+        let value = self.shown_value();
         let widget = JsonViewWidget {
             value: value.as_ref(),
             scroll: self.query.scroll,
@@ -202,9 +205,7 @@ impl App {
     }
 
     fn render_candidates(&self, frame: &mut Frame, area: Rect) {
-        let parent = self.parent_value();
-        let keys = get_available_keys(&parent);
-        let last_keyword = get_last_keyword(&self.query.text);
+        let (keys, last_keyword) = self.completion_source();
         let candidates = self.suggester.get_candidates(&keys, &last_keyword);
 
         if !candidates.is_empty() {
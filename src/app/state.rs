@@ -69,4 +69,7 @@ pub struct AiState {
 pub struct SchemaState {
     /// Cached schema text
     pub text: Option<String>,
+    /// Schema declared by the input file (Parquet, Arrow), shown above the
+    /// inferred one
+    pub source: Option<String>,
 }
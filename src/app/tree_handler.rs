@@ -1,5 +1,8 @@
 use crossterm::event::{self, KeyCode};
 
+use crate::engine::decode::applicable_decoders;
+use crate::engine::json::traverse;
+use crate::engine::query;
 use crate::keys::{map_key_event, Action};
 use crate::modes::AppMode;
 use crate::widgets::tree_view::build_tree;
@@ -40,6 +43,11 @@ impl App {
             KeyCode::Char('q') => {
                 self.mode = AppMode::Query;
             }
+            KeyCode::Char('d') => {
+                if let Some(node) = nodes.get(self.tree.selected) {
+                    self.decode_tree_node(&node.path);
+                }
+            }
             _ => {
                 let action = map_key_event(key);
                 match action {
@@ -59,4 +67,41 @@ impl App {
             }
         }
     }
+
+    /// "Decode as…": set the query to the selected string node followed by
+    /// the next applicable decoder transform. Repeated presses cycle through
+    /// the decoders that work on this string.
+    fn decode_tree_node(&mut self, path: &str) {
+        let value = query::parse(path)
+            .ok()
+            .and_then(|segments| traverse(&self.data, &segments).value);
+        let Some(serde_json::Value::String(s)) = value else {
+            self.status_message = Some("Decode: select a string value".into());
+            return;
+        };
+
+        let decoders = applicable_decoders(&s);
+        if decoders.is_empty() {
+            self.status_message = Some("Decode: no decoder matches this string".into());
+            return;
+        }
+
+        let current = self
+            .query
+            .text
+            .strip_prefix(path)
+            .and_then(|rest| rest.trim().strip_prefix(':'));
+        let next = match current.and_then(|c| decoders.iter().position(|d| *d == c)) {
+            Some(i) => decoders[(i + 1) % decoders.len()],
+            None => decoders[0],
+        };
+
+        self.query.text = format!("{path} :{next}");
+        self.query.cursor = self.query.text.len();
+        self.status_message = Some(format!(
+            "Decoded as :{next} ({} option{}, press d to cycle)",
+            decoders.len(),
+            if decoders.len() == 1 { "" } else { "s" }
+        ));
+    }
 }
@@ -1,8 +1,13 @@
 use anyhow::Result;
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// Name of the project-local config file, looked up from the working
+/// directory upwards.
+pub const PROJECT_CONFIG_FILE: &str = ".jdx.toml";
 
 /// Application configuration loaded from `~/.config/jdx/config.toml`.
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
@@ -12,6 +17,18 @@ pub struct AppConfig {
     pub ai: AiConfig,
     /// Display settings
     pub display: DisplayConfig,
+    /// External commands for the `:exec` transform
+    pub exec: ExecConfig,
+    /// Named query stages, invoked as `:@name` (e.g. `active = ".items :sort name"`)
+    pub aliases: BTreeMap<String, String>,
+}
+
+/// Project-local settings from `.jdx.toml`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectConfig {
+    /// Aliases that add to (and override) those in the user config
+    pub aliases: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +55,24 @@ pub struct DisplayConfig {
     pub schema_max_samples: usize,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExecConfig {
+    /// Programs `:exec` may run (e.g. "./enrich.py"); empty disables `:exec`
+    pub allow: Vec<String>,
+    /// Time limit for a single run, in milliseconds
+    pub timeout_ms: u64,
+}
+
+impl Default for ExecConfig {
+    fn default() -> Self {
+        Self {
+            allow: Vec::new(),
+            timeout_ms: 2000,
+        }
+    }
+}
+
 impl Default for AiConfig {
     fn default() -> Self {
         Self {
@@ -91,6 +126,45 @@ pub fn load_config() -> (AppConfig, Option<String>) {
     }
 }
 
+/// Find `.jdx.toml` in `start` or the nearest parent directory.
+pub fn find_project_config(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_FILE))
+        .find(|path| path.is_file())
+}
+
+/// Merge the user config's aliases with those of the nearest `.jdx.toml`
+/// (project aliases win). Returns a warning if the project file is invalid.
+pub fn load_aliases(
+    config: &AppConfig,
+    start: &Path,
+) -> (BTreeMap<String, String>, Option<String>) {
+    let mut aliases = config.aliases.clone();
+    let Some(path) = find_project_config(start) else {
+        return (aliases, None);
+    };
+    let project: ProjectConfig = match fs::read_to_string(&path) {
+        Ok(content) => match toml::from_str(&content) {
+            Ok(project) => project,
+            Err(e) => {
+                return (
+                    aliases,
+                    Some(format!("{} parse error (ignored): {e}", path.display())),
+                )
+            }
+        },
+        Err(e) => {
+            return (
+                aliases,
+                Some(format!("Could not read {}: {e}", path.display())),
+            )
+        }
+    };
+    aliases.extend(project.aliases);
+    (aliases, None)
+}
+
 /// Save the current configuration to disk.
 pub fn save_config(config: &AppConfig) -> Result<()> {
     if let Some(dir) = config_dir() {
@@ -142,5 +216,48 @@ provider = "openai"
         assert_eq!(config.ai.provider, "openai");
         // Defaults for unspecified
         assert!(!config.display.monochrome);
+        assert!(config.exec.allow.is_empty());
+    }
+
+    #[test]
+    fn test_parse_exec_config() {
+        let toml_str = r#"
+[exec]
+allow = ["./enrich.py", "/usr/local/bin/geoip"]
+timeout_ms = 5000
+"#;
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.exec.allow, ["./enrich.py", "/usr/local/bin/geoip"]);
+        assert_eq!(config.exec.timeout_ms, 5000);
+    }
+
+    #[test]
+    fn test_project_aliases_override_user_aliases() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("a/b");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(
+            dir.path().join(PROJECT_CONFIG_FILE),
+            "[aliases]\nactive = \".items[status != \\\"deleted\\\"]\"\n",
+        )
+        .unwrap();
+
+        let mut config = AppConfig::default();
+        config.aliases.insert("active".into(), ":count".into());
+        config.aliases.insert("top".into(), ":sort $1 desc".into());
+
+        let (aliases, warning) = load_aliases(&config, &nested);
+        assert!(warning.is_none());
+        assert_eq!(aliases["active"], ".items[status != \"deleted\"]");
+        assert_eq!(aliases["top"], ":sort $1 desc");
+    }
+
+    #[test]
+    fn test_invalid_project_config_warns() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(PROJECT_CONFIG_FILE), "[aliases\n").unwrap();
+        let (aliases, warning) = load_aliases(&AppConfig::default(), dir.path());
+        assert!(aliases.is_empty());
+        assert!(warning.unwrap().contains("parse error"));
     }
 }
@@ -0,0 +1,193 @@
+//! Query aliases: named, reusable stages defined in the `[aliases]` table of
+//! the config file or a project-local `.jdx.toml`.
+//!
+//! ```toml
+//! [aliases]
+//! active = '.items[status != "deleted"] :pick id,name :sort name'
+//! top = ':sort $1 desc :pick $2'
+//! ```
+//!
+//! An alias is invoked as `:@name arg1 arg2` anywhere in a chain. Its body is
+//! an optional path (applied to the current value) followed by transforms;
+//! `$1` … `$9` are replaced by the arguments and `$$` is a literal `$`.
+
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::sync::{OnceLock, PoisonError, RwLock};
+
+use anyhow::{bail, Result};
+use serde_json::Value;
+
+use super::json::traverse;
+use super::lexer::{find_transform_start, Command, SyntaxError};
+use super::query::parse as parse_query;
+use super::registry::TransformContext;
+use super::transform::{apply_transform_in, resolve_document};
+
+/// Alias name (without `:@`) to body.
+pub type Aliases = BTreeMap<String, String>;
+
+/// How deeply aliases may invoke other aliases.
+const MAX_DEPTH: usize = 16;
+
+thread_local! {
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+fn store() -> &'static RwLock<Aliases> {
+    static ALIASES: OnceLock<RwLock<Aliases>> = OnceLock::new();
+    ALIASES.get_or_init(|| RwLock::new(Aliases::new()))
+}
+
+/// Replace the set of aliases, usually with those loaded from config.
+pub fn configure(aliases: Aliases) {
+    *store().write().unwrap_or_else(PoisonError::into_inner) = aliases;
+}
+
+/// Alias invocations (`:@name`) for Tab completion.
+pub fn command_names() -> Vec<String> {
+    store()
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .keys()
+        .map(|name| format!(":@{name}"))
+        .collect()
+}
+
+/// Substitute `$1` … `$9` in an alias body.
+pub fn expand(name: &str, body: &str, params: &[String]) -> Result<String> {
+    let mut out = String::with_capacity(body.len());
+    let mut used = 0;
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+        match chars.peek().copied() {
+            Some('$') => {
+                chars.next();
+                out.push('$');
+            }
+            Some(d @ '1'..='9') => {
+                chars.next();
+                let n = d as usize - '0' as usize;
+                used = used.max(n);
+                if let Some(param) = params.get(n - 1) {
+                    out.push_str(param);
+                }
+            }
+            _ => out.push('$'),
+        }
+    }
+    if params.len() != used {
+        bail!(
+            "alias @{name} takes {used} argument{}, got {}.\n\
+             Hint: @{name} = {body}",
+            if used == 1 { "" } else { "s" },
+            params.len()
+        );
+    }
+    Ok(out)
+}
+
+/// Run `:@name args...` against `value`.
+pub(crate) fn apply_alias(
+    value: &Value,
+    command: &Command,
+    ctx: &TransformContext,
+) -> Result<Value> {
+    let name = &command.name[2..];
+    let body = store()
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(name)
+        .cloned();
+    let Some(body) = body else {
+        return Err(
+            SyntaxError::new(format!("unknown alias @{name}"), command.span.clone()).into(),
+        );
+    };
+
+    // Arguments are whitespace-separated; `a,b` stays one argument
+    let mut params: Vec<String> = Vec::new();
+    for token in command.args.tokens() {
+        match params.last_mut() {
+            Some(last) if !token.space_before => last.push_str(&token.text),
+            _ => params.push(token.text.clone()),
+        }
+    }
+    let expanded = expand(name, &body, &params)?;
+
+    let depth = DEPTH.with(|d| d.get());
+    if depth >= MAX_DEPTH {
+        bail!("alias @{name} nests more than {MAX_DEPTH} levels deep (does it call itself?)");
+    }
+    DEPTH.with(|d| d.set(depth + 1));
+    let result = evaluate(value, &expanded, ctx).map_err(|e| anyhow::anyhow!("@{name}: {e}"));
+    DEPTH.with(|d| d.set(depth));
+    result
+}
+
+/// Evaluate an expanded alias body: an optional path, then transforms.
+fn evaluate(value: &Value, body: &str, ctx: &TransformContext) -> Result<Value> {
+    let body = body.trim();
+    let (path, transforms) = if body.starts_with(':') {
+        ("", Some(body))
+    } else {
+        match find_transform_start(body) {
+            Some(i) => (body[..i].trim_end_matches([' ', '|']), Some(&body[i..])),
+            None => (body, None),
+        }
+    };
+
+    let current = if path.is_empty() || path == "." {
+        value.clone()
+    } else if path.starts_with('$') {
+        resolve_document(path, ctx.docs)?
+    } else {
+        let segments = parse_query(path)?;
+        match traverse(value, &segments).value {
+            Some(v) => v,
+            None => bail!("no match for {path}"),
+        }
+    };
+    match transforms {
+        Some(chain) => apply_transform_in(&current, chain, ctx),
+        None => Ok(current),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(p: &[&str]) -> Vec<String> {
+        p.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_expand_positional_params() {
+        assert_eq!(
+            expand(
+                "top",
+                ":sort $1 desc :pick $2",
+                &params(&["price", "id,price"])
+            )
+            .unwrap(),
+            ":sort price desc :pick id,price"
+        );
+        assert_eq!(
+            expand("x", r#":replace /(\d+)/ "$$1""#, &[]).unwrap(),
+            r#":replace /(\d+)/ "$1""#
+        );
+        assert_eq!(expand("x", ".a $doc", &[]).unwrap(), ".a $doc");
+    }
+
+    #[test]
+    fn test_expand_wrong_arity() {
+        let err = expand("top", ":sort $1", &[]).unwrap_err();
+        assert!(err.to_string().contains("takes 1 argument, got 0"), "{err}");
+        assert!(expand("all", ":count", &params(&["extra"])).is_err());
+    }
+}
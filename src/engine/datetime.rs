@@ -0,0 +1,225 @@
+//! Date/time helpers shared by the date transforms, `date(...)` filter
+//! literals and schema inference.
+//!
+//! Parsing is timezone-aware and never consults the local timezone:
+//! timestamps without an offset are taken as UTC, so results are the same
+//! on every machine.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use serde_json::Value;
+
+/// Naive (offset-less) layouts tried by [`parse_datetime`], interpreted as UTC.
+const NAIVE_DATETIME_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
+/// Numbers at or above this magnitude are treated as epoch milliseconds.
+const EPOCH_MS_THRESHOLD: f64 = 1e11;
+
+/// Parse a timestamp in one of the common layouts: RFC 3339, RFC 2822,
+/// `YYYY-MM-DD[ HH:MM[:SS[.f]]]` (UTC), or `YYYY-MM-DD` (midnight UTC).
+pub fn parse_datetime(s: &str) -> Option<DateTime<FixedOffset>> {
+    let s = s.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt);
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc2822(s) {
+        return Some(dt);
+    }
+    for fmt in NAIVE_DATETIME_FORMATS {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(s, fmt) {
+            return Some(utc(naive));
+        }
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(utc)
+}
+
+/// Parse a timestamp with an explicit strftime-style format. Formats without
+/// an offset (`%z`) are taken as UTC; date-only formats give midnight.
+pub fn parse_datetime_with(s: &str, fmt: &str) -> Option<DateTime<FixedOffset>> {
+    let s = s.trim();
+    if let Ok(dt) = DateTime::parse_from_str(s, fmt) {
+        return Some(dt);
+    }
+    if let Ok(naive) = NaiveDateTime::parse_from_str(s, fmt) {
+        return Some(utc(naive));
+    }
+    NaiveDate::parse_from_str(s, fmt)
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(utc)
+}
+
+/// Interpret a JSON value as a timestamp: strings are parsed (with `fmt`
+/// if given), numbers are epoch seconds — or milliseconds when large.
+pub fn value_to_datetime(value: &Value, fmt: Option<&str>) -> Option<DateTime<FixedOffset>> {
+    match value {
+        Value::String(s) => match fmt {
+            Some(f) => parse_datetime_with(s, f),
+            None => parse_datetime(s),
+        },
+        Value::Number(n) => {
+            let n = n.as_f64()?;
+            let millis = if n.abs() >= EPOCH_MS_THRESHOLD {
+                n
+            } else {
+                n * 1000.0
+            };
+            DateTime::from_timestamp_millis(millis as i64).map(|dt| dt.fixed_offset())
+        }
+        _ => None,
+    }
+}
+
+/// Whether a string looks like a date or date-time (used by schema inference).
+/// Stricter than [`parse_datetime`]: only ISO 8601 style `YYYY-MM-DD...`.
+pub fn is_datetime_string(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    bytes.len() >= 10
+        && bytes[..4].iter().all(u8::is_ascii_digit)
+        && bytes[4] == b'-'
+        && bytes[7] == b'-'
+        && parse_datetime(s).is_some()
+}
+
+/// Format a timestamp as RFC 3339, using `Z` for UTC.
+pub fn to_rfc3339(dt: &DateTime<FixedOffset>) -> String {
+    dt.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// Parse a timezone offset: `UTC`, `Z`, `+02:00`, `-0530`.
+pub fn parse_offset(s: &str) -> Result<FixedOffset> {
+    if matches!(s, "UTC" | "utc" | "Z" | "z") {
+        return Ok(FixedOffset::east_opt(0).expect("zero offset is valid"));
+    }
+    let (sign, rest) = match s.as_bytes().first() {
+        Some(b'+') => (1, &s[1..]),
+        Some(b'-') => (-1, &s[1..]),
+        _ => bail!("invalid timezone offset '{s}' (expected UTC or ±HH:MM)"),
+    };
+    let digits: String = rest.chars().filter(|c| *c != ':').collect();
+    if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        bail!("invalid timezone offset '{s}' (expected UTC or ±HH:MM)");
+    }
+    let hours: i32 = digits[..2].parse()?;
+    let minutes: i32 = digits[2..].parse()?;
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+        .with_context(|| format!("timezone offset out of range: {s}"))
+}
+
+/// Parse a bucket duration such as `30s`, `15m`, `1h`, `1d`, `1w` into seconds.
+pub fn parse_duration(s: &str) -> Result<i64> {
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (num, unit) = s.split_at(split);
+    let n: i64 = num
+        .parse()
+        .with_context(|| format!("invalid duration '{s}' (e.g. 15m, 1h, 1d)"))?;
+    let unit_secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        "w" => 604_800,
+        _ => bail!("invalid duration unit in '{s}' (expected s, m, h, d or w)"),
+    };
+    if n <= 0 {
+        bail!("duration must be positive: {s}");
+    }
+    Ok(n * unit_secs)
+}
+
+/// Truncate a timestamp to the start of its bucket. Buckets are aligned to
+/// the Unix epoch in the timestamp's own offset, so `1d` buckets start at
+/// local midnight for that offset.
+pub fn bucket_start(dt: &DateTime<FixedOffset>, bucket_secs: i64) -> DateTime<FixedOffset> {
+    let offset = dt.offset().local_minus_utc() as i64;
+    let local = dt.timestamp() + offset;
+    let start = local.div_euclid(bucket_secs) * bucket_secs - offset;
+    dt.offset().timestamp_opt(start, 0).single().unwrap_or(*dt)
+}
+
+fn utc(naive: NaiveDateTime) -> DateTime<FixedOffset> {
+    Utc.from_utc_datetime(&naive).fixed_offset()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_rfc3339_keeps_offset() {
+        let dt = parse_datetime("2024-03-01T10:00:00+02:00").unwrap();
+        assert_eq!(dt.offset().local_minus_utc(), 7200);
+        assert_eq!(to_rfc3339(&dt), "2024-03-01T10:00:00+02:00");
+    }
+
+    #[test]
+    fn test_parse_naive_as_utc() {
+        let dt = parse_datetime("2024-03-01 10:00").unwrap();
+        assert_eq!(to_rfc3339(&dt), "2024-03-01T10:00:00Z");
+        let date = parse_datetime("2024-03-01").unwrap();
+        assert_eq!(to_rfc3339(&date), "2024-03-01T00:00:00Z");
+    }
+
+    #[test]
+    fn test_parse_with_format() {
+        let dt = parse_datetime_with("01/03/2024", "%d/%m/%Y").unwrap();
+        assert_eq!(to_rfc3339(&dt), "2024-03-01T00:00:00Z");
+        assert!(parse_datetime_with("2024-03-01", "%d/%m/%Y").is_none());
+    }
+
+    #[test]
+    fn test_value_to_datetime_epoch() {
+        let secs = value_to_datetime(&json!(1_700_000_000), None).unwrap();
+        let millis = value_to_datetime(&json!(1_700_000_000_000i64), None).unwrap();
+        assert_eq!(secs, millis);
+        assert_eq!(to_rfc3339(&secs), "2023-11-14T22:13:20Z");
+    }
+
+    #[test]
+    fn test_is_datetime_string() {
+        assert!(is_datetime_string("2024-01-01"));
+        assert!(is_datetime_string("2024-01-01T00:00:00Z"));
+        assert!(!is_datetime_string("Mon, 01 Jan 2024 00:00:00 +0000"));
+        assert!(!is_datetime_string("2024"));
+        assert!(!is_datetime_string("2024-13-45"));
+    }
+
+    #[test]
+    fn test_parse_offset() {
+        assert_eq!(parse_offset("UTC").unwrap().local_minus_utc(), 0);
+        assert_eq!(parse_offset("+05:30").unwrap().local_minus_utc(), 19800);
+        assert_eq!(parse_offset("-0100").unwrap().local_minus_utc(), -3600);
+        assert!(parse_offset("CET").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("15m").unwrap(), 900);
+        assert_eq!(parse_duration("1d").unwrap(), 86_400);
+        assert!(parse_duration("1y").is_err());
+        assert!(parse_duration("0h").is_err());
+        assert!(parse_duration("h").is_err());
+    }
+
+    #[test]
+    fn test_bucket_start_respects_offset() {
+        let dt = parse_datetime("2024-03-01T01:30:00+02:00").unwrap();
+        assert_eq!(
+            to_rfc3339(&bucket_start(&dt, 86_400)),
+            "2024-03-01T00:00:00+02:00"
+        );
+        assert_eq!(
+            to_rfc3339(&bucket_start(&dt, 3600)),
+            "2024-03-01T01:00:00+02:00"
+        );
+    }
+}
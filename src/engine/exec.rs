@@ -0,0 +1,418 @@
+//! The `:exec` transform: pipe the current value through an external command.
+//!
+//! The value is written to the command's stdin as JSON and its stdout is
+//! parsed as JSON. In NDJSON mode (the default when the input is an NDJSON
+//! stream) each array element is written as one line and every non-empty
+//! output line becomes one element of the result.
+//!
+//! Because the TUI re-evaluates the query as it is typed, commands only run
+//! when they are listed in the allow-list of the [`ExecPolicy`] in the
+//! transform context (from the `[exec]` config section), are killed after a
+//! timeout, and results, failures included, are cached by command and input.
+
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Result};
+use serde_json::Value;
+
+use super::datetime::parse_duration;
+use super::lexer::Args;
+use super::registry::TransformContext;
+use crate::config::ExecConfig;
+
+/// Number of results kept in the exec cache.
+const CACHE_SIZE: usize = 32;
+
+/// Which commands `:exec` may run, and for how long.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecPolicy {
+    /// Programs that may be run, as written in the query (`./enrich.py`) or
+    /// as a path that resolves to the same file.
+    pub allow: Vec<String>,
+    /// Default time limit for a single run.
+    pub timeout: Duration,
+}
+
+impl Default for ExecPolicy {
+    fn default() -> Self {
+        Self {
+            allow: Vec::new(),
+            timeout: Duration::from_millis(2000),
+        }
+    }
+}
+
+impl From<&ExecConfig> for ExecPolicy {
+    fn from(config: &ExecConfig) -> Self {
+        Self {
+            allow: config.allow.clone(),
+            timeout: Duration::from_millis(config.timeout_ms),
+        }
+    }
+}
+
+impl ExecPolicy {
+    /// Whether `program` is on the allow-list.
+    pub fn allows(&self, program: &str) -> bool {
+        let canonical = Path::new(program).canonicalize().ok();
+        self.allow.iter().any(|allowed| {
+            allowed == program
+                || (canonical.is_some() && Path::new(allowed).canonicalize().ok() == canonical)
+        })
+    }
+}
+
+/// How values are framed on the subprocess's stdin and stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExecMode {
+    /// One JSON document in, one out.
+    Json,
+    /// One line per array element in, one element per line out.
+    Ndjson,
+}
+
+/// Command line, framing, time limit and stdin of a run.
+type CacheKey = (Vec<String>, ExecMode, Duration, String);
+
+/// The result of a run, with failures kept as their message.
+type CachedRun = Result<Value, String>;
+
+fn cache() -> &'static Mutex<Vec<(CacheKey, CachedRun)>> {
+    static CACHE: OnceLock<Mutex<Vec<(CacheKey, CachedRun)>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+/// `:exec [mode=json|ndjson] [timeout=5s] program [args...]`
+pub(crate) fn transform_exec(value: &Value, args: &Args, ctx: &TransformContext) -> Result<Value> {
+    let mut mode = None;
+    let mut timeout = None;
+    let mut argv: Vec<String> = Vec::new();
+    for token in args.tokens() {
+        // Options come before the program; after it everything is passed on
+        let option = match token.text.split_once('=') {
+            Some((name, val)) if argv.is_empty() => Some((name, val)),
+            _ => None,
+        };
+        match option {
+            Some(("mode", "json")) => mode = Some(ExecMode::Json),
+            Some(("mode", "ndjson")) => mode = Some(ExecMode::Ndjson),
+            Some(("mode", other)) => {
+                return Err(args.error(
+                    format!("unknown mode '{other}' (expected json or ndjson)"),
+                    token.span.clone(),
+                ))
+            }
+            Some(("timeout", val)) => {
+                let secs = parse_duration(val).map_err(|e| args.error(e, token.span.clone()))?;
+                timeout = Some(Duration::from_secs(secs.unsigned_abs()));
+            }
+            _ if token.text.is_empty() && argv.is_empty() => {
+                return Err(args.error("empty program name", token.span.clone()))
+            }
+            _ => match argv.last_mut() {
+                // Glue `--flag=value`-style pieces back together
+                Some(last) if !token.space_before => last.push_str(&token.text),
+                _ => argv.push(token.text.clone()),
+            },
+        }
+    }
+    if argv.is_empty() {
+        bail!(
+            ":exec requires a command.\n\
+             usage: :exec [mode=json|ndjson] [timeout=5s] ./script.py [args...]"
+        );
+    }
+
+    let mode = mode.unwrap_or(if ctx.stream {
+        ExecMode::Ndjson
+    } else {
+        ExecMode::Json
+    });
+    let policy = ExecPolicy {
+        timeout: timeout.unwrap_or(ctx.exec.timeout),
+        ..ctx.exec.clone()
+    };
+    run_cached(&argv, value, mode, &policy)
+}
+
+/// Run a command through the cache. Failures are cached too, so a failing
+/// or slow command is not started again for the same input; commands that
+/// are not allowed are refused before the cache is consulted.
+fn run_cached(
+    argv: &[String],
+    value: &Value,
+    mode: ExecMode,
+    policy: &ExecPolicy,
+) -> Result<Value> {
+    if let Some(program) = argv.first() {
+        check_allowed(program, policy)?;
+    }
+    let input = encode_input(value, mode)?;
+    let key = (argv.to_vec(), mode, policy.timeout, input);
+    {
+        let cache = cache().lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some((_, cached)) = cache.iter().find(|(k, _)| *k == key) {
+            return cached.clone().map_err(|e| anyhow!(e));
+        }
+    }
+    let result = run(argv, &key.3, mode, policy).map_err(|e| e.to_string());
+    let mut cache = cache().lock().unwrap_or_else(PoisonError::into_inner);
+    if cache.len() >= CACHE_SIZE {
+        drop(cache.remove(0));
+    }
+    cache.push((key, result.clone()));
+    result.map_err(|e| anyhow!(e))
+}
+
+fn encode_input(value: &Value, mode: ExecMode) -> Result<String> {
+    Ok(match (mode, value) {
+        (ExecMode::Ndjson, Value::Array(items)) => {
+            let mut out = String::new();
+            for item in items {
+                out.push_str(&serde_json::to_string(item)?);
+                out.push('\n');
+            }
+            out
+        }
+        _ => serde_json::to_string(value)? + "\n",
+    })
+}
+
+fn check_allowed(program: &str, policy: &ExecPolicy) -> Result<()> {
+    if !policy.allows(program) {
+        bail!(
+            ":exec {program}: command is not in the exec allow-list.\n\
+             Hint: add it to `allow` in the [exec] section of the jdx config file"
+        );
+    }
+    Ok(())
+}
+
+/// Run `argv` with `input` on stdin, enforcing the allow-list and timeout.
+pub fn run(argv: &[String], input: &str, mode: ExecMode, policy: &ExecPolicy) -> Result<Value> {
+    let Some((program, rest)) = argv.split_first() else {
+        bail!(":exec requires a command");
+    };
+    check_allowed(program, policy)?;
+
+    let mut child = Command::new(program)
+        .args(rest)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!(":exec {program}: failed to start: {e}"))?;
+
+    // Feed stdin and drain stdout/stderr on threads so a chatty child can't
+    // deadlock against a full pipe. The readers report back over channels
+    // so that a background process still holding a pipe open after the
+    // command exits can't hold us past the deadline.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = input.to_string();
+    thread::spawn(move || {
+        // A child that exits without reading stdin is not an error here
+        let _ = stdin.write_all(input.as_bytes());
+    });
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let (out_tx, out_rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buf = String::new();
+        let _ = out_tx.send(stdout.read_to_string(&mut buf).map(|_| buf));
+    });
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let (err_tx, err_rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buf = String::new();
+        let _ = stderr.read_to_string(&mut buf);
+        let _ = err_tx.send(buf);
+    });
+
+    let deadline = Instant::now() + policy.timeout;
+    let timed_out = || {
+        anyhow!(
+            ":exec {program}: timed out after {:.1}s.\n\
+             Hint: pass timeout=30s or raise timeout_ms in the [exec] config section",
+            policy.timeout.as_secs_f64()
+        )
+    };
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(timed_out());
+        }
+        thread::sleep(Duration::from_millis(5));
+    };
+    let stdout = recv_until(&out_rx, deadline)
+        .ok_or_else(timed_out)?
+        .map_err(|e| anyhow!(":exec {program}: output is not valid UTF-8: {e}"))?;
+    let stderr = recv_until(&err_rx, deadline).ok_or_else(timed_out)?;
+
+    if !status.success() {
+        let detail = stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+        match status.code() {
+            Some(code) => bail!(":exec {program}: exited with status {code}: {detail}"),
+            None => bail!(":exec {program}: terminated by a signal: {detail}"),
+        }
+    }
+    decode_output(program, &stdout, mode)
+}
+
+/// Wait for a pipe reader to finish, giving up at `deadline`.
+fn recv_until<T>(rx: &Receiver<T>, deadline: Instant) -> Option<T> {
+    rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        .ok()
+}
+
+fn decode_output(program: &str, stdout: &str, mode: ExecMode) -> Result<Value> {
+    match mode {
+        ExecMode::Json => {
+            if stdout.trim().is_empty() {
+                bail!(":exec {program}: command produced no output");
+            }
+            serde_json::from_str(stdout)
+                .map_err(|e| anyhow!(":exec {program}: output is not valid JSON: {e}"))
+        }
+        ExecMode::Ndjson => stdout
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line).map_err(|e| {
+                    anyhow!(
+                        ":exec {program}: output line {} is not valid JSON: {e}",
+                        i + 1
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(Value::Array),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sh(script: &str) -> Vec<String> {
+        vec!["sh".into(), "-c".into(), script.into()]
+    }
+
+    fn allow_sh() -> ExecPolicy {
+        ExecPolicy {
+            allow: vec!["sh".into()],
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    #[test]
+    fn test_run_json_roundtrip() {
+        let out = run(&sh("cat"), "{\"a\": 1}\n", ExecMode::Json, &allow_sh()).unwrap();
+        assert_eq!(out, json!({"a": 1}));
+    }
+
+    #[test]
+    fn test_run_ndjson_per_element() {
+        let input = encode_input(&json!([{"n": 1}, {"n": 2}]), ExecMode::Ndjson).unwrap();
+        assert_eq!(input, "{\"n\":1}\n{\"n\":2}\n");
+        let out = run(&sh("cat; echo"), &input, ExecMode::Ndjson, &allow_sh()).unwrap();
+        assert_eq!(out, json!([{"n": 1}, {"n": 2}]));
+    }
+
+    #[test]
+    fn test_run_rejects_unlisted_command() {
+        let err = run(&sh("cat"), "1", ExecMode::Json, &ExecPolicy::default()).unwrap_err();
+        assert!(err.to_string().contains("allow-list"), "{err}");
+    }
+
+    #[test]
+    fn test_run_reports_exit_status_and_stderr() {
+        let err = run(
+            &sh("echo 'region db missing' >&2; exit 3"),
+            "1",
+            ExecMode::Json,
+            &allow_sh(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            ":exec sh: exited with status 3: region db missing"
+        );
+    }
+
+    #[test]
+    fn test_run_times_out() {
+        let policy = ExecPolicy {
+            timeout: Duration::from_millis(100),
+            ..allow_sh()
+        };
+        let start = Instant::now();
+        let err = run(&sh("sleep 5"), "1", ExecMode::Json, &policy).unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{err}");
+        assert!(start.elapsed() < Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_run_times_out_on_background_child() {
+        let policy = ExecPolicy {
+            timeout: Duration::from_millis(300),
+            ..allow_sh()
+        };
+        let start = Instant::now();
+        let err = run(&sh("sleep 5 & echo 1"), "1", ExecMode::Json, &policy).unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{err}");
+        assert!(start.elapsed() < Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_run_invalid_json_output() {
+        let err = run(&sh("echo nope"), "1", ExecMode::Json, &allow_sh()).unwrap_err();
+        assert!(err.to_string().contains("not valid JSON"), "{err}");
+    }
+
+    #[test]
+    fn test_failures_are_cached() {
+        let dir = std::env::temp_dir().join(format!("jdx-exec-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("runs");
+        let script = format!("echo run >> '{}'; exit 1", log.display());
+        for _ in 0..3 {
+            let err = run_cached(&sh(&script), &json!(1), ExecMode::Json, &allow_sh());
+            assert!(err.unwrap_err().to_string().contains("status 1"));
+        }
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "run\n");
+
+        // Refusals are not cached, so allowing the command takes effect
+        let refused = run_cached(
+            &sh("cat"),
+            &json!(2),
+            ExecMode::Json,
+            &ExecPolicy::default(),
+        );
+        assert!(refused.unwrap_err().to_string().contains("allow-list"));
+        let allowed = run_cached(&sh("cat"), &json!(2), ExecMode::Json, &allow_sh());
+        assert_eq!(allowed.unwrap(), json!(2));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_policy_allows_equivalent_paths() {
+        let policy = ExecPolicy {
+            allow: vec!["/bin/sh".into()],
+            ..ExecPolicy::default()
+        };
+        assert!(policy.allows("/bin/sh"));
+        assert!(policy.allows("/bin/../bin/sh"));
+        assert!(!policy.allows("sh"));
+    }
+}
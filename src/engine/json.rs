@@ -173,6 +173,29 @@ pub fn traverse(root: &Value, segments: &[PathSegment]) -> TraversalResult {
     }
 }
 
+/// Return a copy of `root` with the value at `segments` replaced by `value`.
+///
+/// Only plain keys and indexes name a single place to write to, so this
+/// returns `None` for slices, wildcards and filters, and for paths that
+/// don't exist in `root`.
+pub fn splice(root: &Value, segments: &[PathSegment], value: Value) -> Option<Value> {
+    let mut out = root.clone();
+    let mut target = &mut out;
+    for segment in segments {
+        target = match (segment, target) {
+            (PathSegment::Key(key), Value::Object(map)) => map.get_mut(key.as_str())?,
+            (PathSegment::Index(idx), Value::Array(arr)) => {
+                let len = arr.len() as i64;
+                let idx = if *idx < 0 { len + idx } else { *idx };
+                arr.get_mut(usize::try_from(idx).ok()?)?
+            }
+            _ => return None,
+        };
+    }
+    *target = value;
+    Some(out)
+}
+
 /// Evaluate a compound filter expression (AND/OR/Single) against a JSON value.
 pub fn eval_filter_expr(value: &Value, expr: &FilterExpr) -> bool {
     match expr {
@@ -220,6 +243,10 @@ pub fn eval_predicate(value: &Value, pred: &Predicate) -> bool {
                 false
             }
         }
+        FilterValue::Date(millis) => match super::datetime::value_to_datetime(field_val, None) {
+            Some(dt) => compare_f64(dt.timestamp_millis() as f64, *millis as f64, &pred.op),
+            None => false,
+        },
         FilterValue::Null => {
             let is_null = field_val.is_null();
             match pred.op {
@@ -280,6 +307,7 @@ pub fn compact_print(value: &Value) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::engine::query::parse;
     use serde_json::json;
 
     #[test]
@@ -550,4 +578,54 @@ mod tests {
         };
         assert!(!eval_predicate(&item, &pred));
     }
+
+    #[test]
+    fn test_eval_predicate_date() {
+        let pred = Predicate {
+            field: "created_at".into(),
+            op: CompareOp::Gt,
+            value: FilterValue::Date(1_704_067_200_000), // 2024-01-01T00:00:00Z
+        };
+        assert!(eval_predicate(
+            &json!({"created_at": "2024-01-01T01:00:00Z"}),
+            &pred
+        ));
+        // Same instant expressed in another offset is not strictly greater.
+        assert!(!eval_predicate(
+            &json!({"created_at": "2024-01-01T02:00:00+02:00"}),
+            &pred
+        ));
+        assert!(eval_predicate(&json!({"created_at": 1_704_067_201}), &pred));
+        assert!(!eval_predicate(&json!({"created_at": "soon"}), &pred));
+    }
+
+    #[test]
+    fn test_splice_keeps_siblings() {
+        let data = json!({"spec": {"replicas": 1, "image": "app:1"}, "kind": "Deployment"});
+        let segments = parse(".spec").unwrap();
+        let out = splice(&data, &segments, json!({"replicas": 3})).unwrap();
+        assert_eq!(out, json!({"spec": {"replicas": 3}, "kind": "Deployment"}));
+
+        let segments = parse(".items[-1]").unwrap();
+        let data = json!({"items": [1, 2, 3], "total": 3});
+        let out = splice(&data, &segments, json!(9)).unwrap();
+        assert_eq!(out, json!({"items": [1, 2, 9], "total": 3}));
+
+        assert_eq!(splice(&data, &[], json!(1)), Some(json!(1)));
+    }
+
+    #[test]
+    fn test_splice_rejects_fan_out_and_missing_paths() {
+        let data = json!({"items": [1, 2], "name": "x"});
+        for query in [
+            ".items[*]",
+            ".items[0:1]",
+            ".missing",
+            ".items[5]",
+            ".name.first",
+        ] {
+            let segments = parse(query).unwrap();
+            assert_eq!(splice(&data, &segments, json!(0)), None, "{query}");
+        }
+    }
 }
@@ -0,0 +1,637 @@
+//! Lexer for transform chains such as `:pick "first name",age :sort age desc`.
+//!
+//! A chain is split into commands, each with a list of typed argument tokens.
+//! Quoted strings (`"..."` or `'...'`, with `\` escapes) keep spaces, commas
+//! and ` :` together, and `/regex/flags` literals are recognized as the first
+//! argument of commands whose [`registry::ArgSpec`] takes a pattern. Errors
+//! carry byte spans into the input so the query bar can highlight the
+//! offending text.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use anyhow::Result;
+
+use super::registry;
+
+/// A transform syntax or argument error, with the byte span it refers to.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{message}")]
+pub struct SyntaxError {
+    pub message: String,
+    pub span: Range<usize>,
+}
+
+impl SyntaxError {
+    pub fn new(message: impl Into<String>, span: Range<usize>) -> Self {
+        Self {
+            message: message.into(),
+            span,
+        }
+    }
+}
+
+/// The type of an argument token.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    /// A bare word, e.g. `name`, `desc`, `key=name`
+    Word,
+    /// A fully quoted string, e.g. `", "`
+    Str,
+    /// A bare numeric literal, e.g. `3`, `-1.5`
+    Number(f64),
+    /// A field path or document reference, e.g. `.user.id`, `$users`
+    Path,
+    /// A `/pattern/flags` literal; the token text is the pattern
+    Regex { flags: String },
+    /// A `,` separator outside of quotes
+    Comma,
+}
+
+/// One argument token. `text` has quotes removed and escapes resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+    pub span: Range<usize>,
+    /// Whether whitespace separates this token from the previous one.
+    pub space_before: bool,
+}
+
+/// A single transform command with its arguments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Command {
+    /// Command name including the leading `:`
+    pub name: String,
+    pub span: Range<usize>,
+    pub args: Args,
+}
+
+/// The arguments of a transform command, with typed accessors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Args {
+    command: String,
+    raw: String,
+    span: Range<usize>,
+    tokens: Vec<Token>,
+}
+
+/// Split a transform chain into commands and lex their arguments.
+pub fn parse_chain(input: &str) -> Result<Vec<Command>, SyntaxError> {
+    let mut lexer = Lexer { src: input, pos: 0 };
+    let mut commands: Vec<Command> = Vec::new();
+
+    loop {
+        let space_before = lexer.skip_whitespace();
+        let start = lexer.pos;
+        let Some(c) = lexer.peek() else { break };
+
+        if (start == 0 || space_before) && lexer.at_command() {
+            let name = lexer.command_name().to_string();
+            commands.push(Command {
+                name: name.clone(),
+                span: start..lexer.pos,
+                args: Args {
+                    command: name,
+                    raw: String::new(),
+                    span: lexer.pos..lexer.pos,
+                    tokens: Vec::new(),
+                },
+            });
+            continue;
+        }
+
+        let Some(command) = commands.last_mut() else {
+            let end = lexer.src[start..]
+                .find(char::is_whitespace)
+                .map_or(input.len(), |i| start + i);
+            return Err(SyntaxError::new(
+                "expected a transform command starting with ':'",
+                start..end,
+            ));
+        };
+        let args = &mut command.args;
+        let token = if c == '/'
+            && args.tokens.is_empty()
+            && registry::get(&command.name).is_some_and(|t| t.args().regex)
+        {
+            lexer.regex(space_before)?
+        } else {
+            lexer.token(space_before)?
+        };
+        if args.tokens.is_empty() {
+            args.span.start = token.span.start;
+        }
+        args.span.end = token.span.end;
+        args.tokens.push(token);
+    }
+
+    for command in &mut commands {
+        command.args.raw = input[command.args.span.clone()].to_string();
+    }
+    Ok(commands)
+}
+
+/// Split a query into its path and the transform chain that follows it.
+pub fn split_query(query: &str) -> (&str, Option<&str>) {
+    match find_transform_start(query) {
+        Some(idx) => (
+            query[..idx].trim_end_matches([' ', '|']),
+            Some(&query[idx..]),
+        ),
+        None => (query, None),
+    }
+}
+
+/// Find where the transform part of a query starts: the first `:` that is
+/// preceded by a space or `|` and is not inside quotes or brackets.
+pub fn find_transform_start(query: &str) -> Option<usize> {
+    let mut quote: Option<char> = None;
+    let mut depth = 0usize;
+    let mut prev: Option<char> = None;
+    for (i, c) in query.char_indices() {
+        match quote {
+            Some(q) if c == q && prev != Some('\\') => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '[' => depth += 1,
+                ']' => depth = depth.saturating_sub(1),
+                ':' if depth == 0 && matches!(prev, Some(' ' | '|')) => return Some(i),
+                _ => {}
+            },
+        }
+        prev = Some(c);
+    }
+    None
+}
+
+struct Lexer<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl Lexer<'_> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    /// Skip whitespace, returning whether any was skipped.
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.pos;
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+        self.pos > start
+    }
+
+    fn at_command(&self) -> bool {
+        let mut chars = self.src[self.pos..].chars();
+        chars.next() == Some(':')
+            && chars
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '@')
+    }
+
+    fn command_name(&mut self) -> &str {
+        let start = self.pos;
+        self.bump(); // ':'
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '@' | '-'))
+        {
+            self.bump();
+        }
+        &self.src[start..self.pos]
+    }
+
+    /// Lex a word, string, number or path; quoted segments may be embedded
+    /// in a word (e.g. `fmt="%d %m"`).
+    fn token(&mut self, space_before: bool) -> Result<Token, SyntaxError> {
+        let start = self.pos;
+        if self.peek() == Some(',') {
+            self.bump();
+            return Ok(Token {
+                kind: TokenKind::Comma,
+                text: ",".into(),
+                span: start..self.pos,
+                space_before,
+            });
+        }
+
+        let mut text = String::new();
+        let mut quoted = false;
+        let mut bare = false;
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || c == ',' {
+                break;
+            }
+            // Quotes open a string at the start of a token or after `=` / `(`
+            // (`fmt="%d %m"`, `date("2024-01-01")`); elsewhere they are
+            // literal, so `O'Brien` stays one word.
+            let opens_string = self.pos == start || matches!(text.chars().last(), Some('=' | '('));
+            if (c == '"' || c == '\'') && opens_string {
+                text.push_str(&self.quoted(c)?);
+                quoted = true;
+            } else {
+                self.bump();
+                text.push(c);
+                bare = true;
+            }
+        }
+
+        let kind = if quoted {
+            if bare {
+                TokenKind::Word
+            } else {
+                TokenKind::Str
+            }
+        } else if let Some(n) = parse_number(&text) {
+            TokenKind::Number(n)
+        } else if text.len() > 1 && (text.starts_with('.') || text.starts_with('$')) {
+            TokenKind::Path
+        } else {
+            TokenKind::Word
+        };
+        Ok(Token {
+            kind,
+            text,
+            span: start..self.pos,
+            space_before,
+        })
+    }
+
+    /// Lex a quoted string starting at the opening quote, resolving escapes.
+    fn quoted(&mut self, quote: char) -> Result<String, SyntaxError> {
+        let start = self.pos;
+        self.bump();
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                Some(c) if c == quote => return Ok(out),
+                Some('\\') => match self.bump() {
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some('r') => out.push('\r'),
+                    Some(c @ ('\\' | '"' | '\'')) => out.push(c),
+                    // Keep unknown escapes as written, so `"\d"` stays `\d`
+                    Some(c) => {
+                        out.push('\\');
+                        out.push(c);
+                    }
+                    None => break,
+                },
+                Some(c) => out.push(c),
+                None => break,
+            }
+        }
+        Err(SyntaxError::new(
+            format!("unterminated string (missing closing {quote})"),
+            start..self.src.len(),
+        ))
+    }
+
+    /// Lex a `/pattern/flags` literal. `\/` escapes a slash in the pattern.
+    fn regex(&mut self, space_before: bool) -> Result<Token, SyntaxError> {
+        let start = self.pos;
+        self.bump(); // '/'
+        let mut pattern = String::new();
+        loop {
+            match self.bump() {
+                Some('/') => break,
+                Some('\\') => match self.bump() {
+                    Some('/') => pattern.push('/'),
+                    Some(c) => {
+                        pattern.push('\\');
+                        pattern.push(c);
+                    }
+                    None => pattern.push('\\'),
+                },
+                Some(c) => pattern.push(c),
+                None => {
+                    return Err(SyntaxError::new(
+                        "unterminated regex (missing closing '/')",
+                        start..self.src.len(),
+                    ))
+                }
+            }
+        }
+        let mut flags = String::new();
+        while let Some(c) = self.peek().filter(char::is_ascii_alphabetic) {
+            self.bump();
+            flags.push(c);
+        }
+        Ok(Token {
+            kind: TokenKind::Regex { flags },
+            text: pattern,
+            span: start..self.pos,
+            space_before,
+        })
+    }
+}
+
+/// Parse a bare numeric literal. Words like `inf` or `nan` stay words.
+fn parse_number(text: &str) -> Option<f64> {
+    let digits = text.strip_prefix(['-', '+']).unwrap_or(text);
+    if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+impl Args {
+    /// Build arguments by lexing `args` as if they followed `command`.
+    pub fn parse(command: &str, args: &str) -> Result<Self, SyntaxError> {
+        let input = format!("{command} {args}");
+        let offset = command.len() + 1;
+        let mut parsed = parse_chain(&input)?;
+        let mut args = match parsed.pop() {
+            Some(cmd) if parsed.is_empty() && cmd.name == command => cmd.args,
+            _ => {
+                return Err(SyntaxError::new(
+                    format!("{command}: unexpected transform in arguments"),
+                    0..args.len(),
+                ))
+            }
+        };
+        // Re-base spans onto `args`
+        let shift = |span: &Range<usize>| span.start.saturating_sub(offset)..span.end - offset;
+        args.span = shift(&args.span);
+        for token in &mut args.tokens {
+            token.span = shift(&token.span);
+        }
+        Ok(args)
+    }
+
+    /// Name of the command these arguments belong to (with the leading `:`).
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// The argument source text exactly as written, for commands with
+    /// their own expression syntax (e.g. `:filter`).
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// Build an error pointing at `span`, prefixed with the command name.
+    pub fn error(&self, message: impl std::fmt::Display, span: Range<usize>) -> anyhow::Error {
+        SyntaxError::new(format!("{}: {message}", self.command), span).into()
+    }
+
+    /// Fail if any arguments were given.
+    pub fn expect_none(&self) -> Result<()> {
+        if self.tokens.is_empty() {
+            Ok(())
+        } else {
+            Err(self.error("takes no arguments", self.span()))
+        }
+    }
+
+    /// The single argument, if any. A lone `,` counts as a value so that
+    /// `:join ,` keeps working.
+    pub fn optional_value(&self) -> Result<Option<&str>> {
+        match self.tokens.as_slice() {
+            [] => Ok(None),
+            [token] => Ok(Some(&token.text)),
+            [_, extra, ..] => Err(self.error(
+                format!(
+                    "unexpected argument '{}' (quote values containing spaces)",
+                    extra.text
+                ),
+                extra.span.clone(),
+            )),
+        }
+    }
+
+    /// A list of values separated by commas and/or whitespace, e.g.
+    /// `name,age` or `"first name", age`.
+    pub fn list(&self) -> Result<Vec<String>> {
+        let mut items = Vec::new();
+        for token in &self.tokens {
+            match token.kind {
+                TokenKind::Comma => {}
+                TokenKind::Regex { .. } => {
+                    return Err(self.error("unexpected regex", token.span.clone()))
+                }
+                _ => items.push(token.text.clone()),
+            }
+        }
+        Ok(items)
+    }
+
+    /// `name=value` options only.
+    pub fn options(&self, allowed: &[&str]) -> Result<BTreeMap<String, String>> {
+        let (positional, options) = self.positional_and_options(allowed)?;
+        match positional.first() {
+            Some(token) => Err(self.error(
+                format!(
+                    "unexpected argument '{}' (expected {})",
+                    token.text,
+                    expected_options(allowed)
+                ),
+                token.span.clone(),
+            )),
+            None => Ok(options),
+        }
+    }
+
+    /// Separate positional arguments from `name=value` options. Option values
+    /// may be quoted (`fmt="%d %m"`) or comma lists (`keep=id,date`).
+    pub fn positional_and_options(
+        &self,
+        allowed: &[&str],
+    ) -> Result<(Vec<&Token>, BTreeMap<String, String>)> {
+        let mut positional = Vec::new();
+        let mut options = BTreeMap::new();
+        let mut i = 0;
+        while i < self.tokens.len() {
+            let token = &self.tokens[i];
+            i += 1;
+            let option = match token.kind {
+                TokenKind::Word => token
+                    .text
+                    .split_once('=')
+                    .filter(|(name, _)| is_option_name(name)),
+                _ => None,
+            };
+            let Some((name, value)) = option else {
+                positional.push(token);
+                continue;
+            };
+            if !allowed.contains(&name) {
+                return Err(self.error(
+                    format!(
+                        "unknown option '{name}' (expected {})",
+                        expected_options(allowed)
+                    ),
+                    token.span.clone(),
+                ));
+            }
+            // Glue on `,b,c` that directly follows (no whitespace)
+            let mut value = value.to_string();
+            while let Some(next) = self.tokens.get(i).filter(|t| !t.space_before) {
+                value.push_str(&next.text);
+                i += 1;
+            }
+            if value.is_empty() {
+                return Err(
+                    self.error(format!("option '{name}' needs a value"), token.span.clone())
+                );
+            }
+            options.insert(name.to_string(), value);
+        }
+        Ok((positional, options))
+    }
+}
+
+fn is_option_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn expected_options(allowed: &[&str]) -> String {
+    allowed
+        .iter()
+        .map(|a| format!("{a}=..."))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(cmd: &Command) -> Vec<&str> {
+        cmd.args.tokens().iter().map(|t| t.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_chain_split() {
+        let cmds = parse_chain(":pick name,age :sort age desc").unwrap();
+        assert_eq!(cmds.len(), 2);
+        assert_eq!(cmds[0].name, ":pick");
+        assert_eq!(texts(&cmds[0]), ["name", ",", "age"]);
+        assert_eq!(cmds[1].args.raw(), "age desc");
+    }
+
+    #[test]
+    fn test_quoted_strings_keep_spaces_and_colons() {
+        let cmds = parse_chain(r#":filter name == "a :b" :join ", ""#).unwrap();
+        assert_eq!(cmds.len(), 2);
+        assert_eq!(cmds[0].args.raw(), r#"name == "a :b""#);
+        assert_eq!(cmds[1].args.optional_value().unwrap(), Some(", "));
+    }
+
+    #[test]
+    fn test_escapes() {
+        let cmds = parse_chain(r#":join "\"\t\\" :split 'it\'s' :replace /a/ "\d""#).unwrap();
+        assert_eq!(texts(&cmds[0]), ["\"\t\\"]);
+        assert_eq!(texts(&cmds[1]), ["it's"]);
+        assert_eq!(texts(&cmds[2])[1], "\\d");
+    }
+
+    #[test]
+    fn test_apostrophe_inside_word() {
+        let cmds = parse_chain(":filter name == O'Brien").unwrap();
+        assert_eq!(texts(&cmds[0]), ["name", "==", "O'Brien"]);
+    }
+
+    #[test]
+    fn test_token_kinds() {
+        let cmds = parse_chain(r#":x word "str" 42 -1.5 .a.b $doc , inf"#).unwrap();
+        let kinds: Vec<&TokenKind> = cmds[0].args.tokens().iter().map(|t| &t.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                &TokenKind::Word,
+                &TokenKind::Str,
+                &TokenKind::Number(42.0),
+                &TokenKind::Number(-1.5),
+                &TokenKind::Path,
+                &TokenKind::Path,
+                &TokenKind::Comma,
+                &TokenKind::Word,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_regex_only_for_regex_commands() {
+        let cmds = parse_chain(r":match /a\/b c/i :join /").unwrap();
+        let token = &cmds[0].args.tokens()[0];
+        assert_eq!(token.text, "a/b c");
+        assert_eq!(token.kind, TokenKind::Regex { flags: "i".into() });
+        assert_eq!(cmds[1].args.optional_value().unwrap(), Some("/"));
+    }
+
+    #[test]
+    fn test_error_spans() {
+        let err = parse_chain(r#":join ", "#).unwrap_err();
+        assert!(err.message.contains("unterminated string"));
+        assert_eq!(err.span, 6..9);
+
+        let err = parse_chain(":match /abc").unwrap_err();
+        assert_eq!(err.span, 7..11);
+
+        let err = parse_chain("pick name").unwrap_err();
+        assert_eq!(err.span, 0..4);
+    }
+
+    #[test]
+    fn test_options_with_quotes_and_lists() {
+        let args = Args::parse(":x", r#"fmt="%d %m" keep=id,date extra"#).unwrap();
+        let (positional, options) = args.positional_and_options(&["fmt", "keep"]).unwrap();
+        assert_eq!(options["fmt"], "%d %m");
+        assert_eq!(options["keep"], "id,date");
+        assert_eq!(positional.len(), 1);
+        assert_eq!(positional[0].span, 25..30);
+
+        let err = args.options(&["fmt", "keep"]).unwrap_err();
+        let err = err.downcast_ref::<SyntaxError>().unwrap();
+        assert_eq!(err.span, 25..30);
+
+        let err = Args::parse(":x", "nope=1")
+            .unwrap()
+            .options(&["fmt"])
+            .unwrap_err();
+        assert!(err.to_string().contains("unknown option 'nope'"));
+    }
+
+    #[test]
+    fn test_list_and_optional_value() {
+        let args = Args::parse(":pick", r#""first name", age,email"#).unwrap();
+        assert_eq!(args.list().unwrap(), ["first name", "age", "email"]);
+        assert!(args.optional_value().is_err());
+        assert!(Args::parse(":keys", "x").unwrap().expect_none().is_err());
+    }
+
+    #[test]
+    fn test_find_transform_start() {
+        assert_eq!(find_transform_start(".users :count"), Some(7));
+        assert_eq!(find_transform_start(".a|:keys"), Some(3));
+        assert_eq!(
+            find_transform_start(r#".users[note == "x :y"] :count"#),
+            Some(23)
+        );
+        assert_eq!(find_transform_start(".users"), None);
+        assert_eq!(find_transform_start(":keys"), None);
+    }
+}
@@ -1,5 +1,14 @@
+pub mod alias;
+pub mod datetime;
+pub mod decode;
+pub mod exec;
 pub mod json;
+pub mod lexer;
+pub mod mutate;
 pub mod query;
+pub mod registry;
 pub mod schema;
+pub mod script;
 pub mod suggestion;
+pub mod template;
 pub mod transform;
@@ -0,0 +1,454 @@
+//! Mutation transforms: `:set`, `:del`, `:rename` and `:default`.
+//!
+//! Unlike the other transforms these return the whole (modified) value, so
+//! `. :set .spec.replicas 3` prints the full document with one field changed.
+//! Paths use query syntax relative to the current value and may fan out with
+//! `[*]`, slices and filters: `:set .items[status == "old"].status "new"`.
+
+use anyhow::{bail, Result};
+use serde_json::{Map, Value};
+
+use super::json::eval_filter_expr;
+use super::lexer::Args;
+use super::query::{parse as parse_query, PathSegment};
+
+/// `:set <path> <value>` — set a value, creating missing objects on the way.
+pub(crate) fn transform_set(value: &Value, args: &Args) -> Result<Value> {
+    const USAGE: &str = "usage: :set .path value (e.g. :set .spec.replicas 3)";
+    let raw = args.raw();
+    let Some((path, rest)) = split_path(raw) else {
+        bail!(":set requires a path and a value.\n{USAGE}");
+    };
+    let segments = parse_path(args, path, 0)?;
+    let rest = rest.trim();
+    if rest.is_empty() {
+        let end = args.span().start + path.len();
+        return Err(args.error(format!("missing value ({USAGE})"), end..end));
+    }
+    let new_value = parse_literal(rest);
+
+    let mut out = value.clone();
+    let matched = update_at(&mut out, &segments, true, &mut |target| {
+        *target = new_value.clone();
+    })?;
+    if matched == 0 {
+        return Err(args.error(
+            format!("path {path} matched nothing"),
+            args.span().start..args.span().start + path.len(),
+        ));
+    }
+    Ok(out)
+}
+
+/// `:del <path> [path...]` — remove keys or array elements. Missing paths
+/// are ignored so that `:del` is idempotent.
+pub(crate) fn transform_del(value: &Value, args: &Args) -> Result<Value> {
+    let mut out = value.clone();
+    let mut rest = args.raw();
+    let mut offset = 0;
+    if rest.trim().is_empty() {
+        bail!(":del requires at least one path (e.g. :del .metadata.managedFields)");
+    }
+    while let Some((path, tail)) = split_path(rest) {
+        let start = offset + (rest.len() - rest.trim_start().len());
+        let segments = parse_path(args, path, start)?;
+        let Some((last, parents)) = segments.split_last() else {
+            return Err(args.error(
+                "cannot delete the whole value",
+                args.span().start + start..args.span().start + start + path.len(),
+            ));
+        };
+        update_at(&mut out, parents, false, &mut |parent| remove(parent, last))?;
+        offset += rest.len() - tail.len();
+        rest = tail;
+    }
+    Ok(out)
+}
+
+/// `:rename old=new [old2=new2 ...]` — rename keys of an object or of every
+/// object in an array.
+pub(crate) fn transform_rename(value: &Value, args: &Args) -> Result<Value> {
+    let pairs = key_value_args(args, ":rename", "old=new")?;
+    let rename = |map: &Map<String, Value>| {
+        let mut map = map.clone();
+        for (old, new) in &pairs {
+            if let Some(v) = map.remove(old) {
+                map.insert(unquote(new.trim()), v);
+            }
+        }
+        Value::Object(map)
+    };
+    match value {
+        Value::Object(map) => Ok(rename(map)),
+        Value::Array(arr) => Ok(Value::Array(
+            arr.iter()
+                .map(|item| match item {
+                    Value::Object(map) => rename(map),
+                    other => other.clone(),
+                })
+                .collect(),
+        )),
+        _ => bail!(":rename requires an object or an array of objects"),
+    }
+}
+
+/// `:default field=value [...]` — fill in fields that are missing or null,
+/// on an object or every object in an array.
+pub(crate) fn transform_default(value: &Value, args: &Args) -> Result<Value> {
+    let pairs: Vec<(String, Value)> = key_value_args(args, ":default", "field=value")?
+        .into_iter()
+        .map(|(k, v)| (k, parse_literal(&v)))
+        .collect();
+    let fill = |map: &Map<String, Value>| {
+        let mut map = map.clone();
+        for (key, default) in &pairs {
+            let slot = map.entry(key.clone()).or_insert(Value::Null);
+            if slot.is_null() {
+                *slot = default.clone();
+            }
+        }
+        Value::Object(map)
+    };
+    match value {
+        Value::Object(map) => Ok(fill(map)),
+        Value::Array(arr) => Ok(Value::Array(
+            arr.iter()
+                .map(|item| match item {
+                    Value::Object(map) => fill(map),
+                    other => other.clone(),
+                })
+                .collect(),
+        )),
+        _ => bail!(":default requires an object or an array of objects"),
+    }
+}
+
+/// Split `name=value` arguments. Values keep their source text so that
+/// `:default n="3"` stays a string while `n=3` is a number.
+fn key_value_args(args: &Args, cmd: &str, example: &str) -> Result<Vec<(String, String)>> {
+    let raw = args.raw();
+    let base = args.span().start;
+    let mut pairs = Vec::new();
+    let mut pieces: Vec<(usize, usize)> = Vec::new();
+    for token in args.tokens() {
+        let (start, end) = (token.span.start - base, token.span.end - base);
+        match pieces.last_mut() {
+            Some(last) if !token.space_before => last.1 = end,
+            _ => pieces.push((start, end)),
+        }
+    }
+    for (start, end) in pieces {
+        let piece = &raw[start..end];
+        let Some((key, val)) = piece.split_once('=') else {
+            return Err(args.error(
+                format!("expected {example}, got '{piece}'"),
+                base + start..base + end,
+            ));
+        };
+        let key = unquote(key.trim());
+        if key.is_empty() {
+            return Err(args.error("empty key", base + start..base + end));
+        }
+        pairs.push((key, val.to_string()));
+    }
+    if pairs.is_empty() {
+        bail!("{cmd} requires {example} arguments");
+    }
+    Ok(pairs)
+}
+
+/// Split the first path off `s`: up to whitespace outside brackets/quotes.
+fn split_path(s: &str) -> Option<(&str, &str)> {
+    let s = s.trim_start();
+    if s.is_empty() {
+        return None;
+    }
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    for (i, c) in s.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[') => depth += 1,
+            (None, ']') => depth = depth.saturating_sub(1),
+            (None, c) if c.is_whitespace() && depth == 0 => return Some((&s[..i], &s[i..])),
+            _ => {}
+        }
+    }
+    Some((s, ""))
+}
+
+fn parse_path(args: &Args, path: &str, offset: usize) -> Result<Vec<PathSegment>> {
+    let full = if path.starts_with('.') {
+        path.to_string()
+    } else {
+        format!(".{path}")
+    };
+    parse_query(&full).map_err(|e| {
+        let start = args.span().start + offset;
+        args.error(
+            format!("invalid path {path}: {e}"),
+            start..start + path.len(),
+        )
+    })
+}
+
+/// Parse a literal: JSON if it parses (`3`, `true`, `"x"`, `[1]`, `{..}`),
+/// a single-quoted string, or else the text itself as a string.
+fn parse_literal(s: &str) -> Value {
+    let s = s.trim();
+    if let Ok(v) = serde_json::from_str(s) {
+        return v;
+    }
+    Value::String(unquote(s))
+}
+
+fn unquote(s: &str) -> String {
+    let quoted = s.len() >= 2
+        && ((s.starts_with('\'') && s.ends_with('\'')) || (s.starts_with('"') && s.ends_with('"')));
+    if quoted {
+        s[1..s.len() - 1].to_string()
+    } else {
+        s.to_string()
+    }
+}
+
+/// Apply `f` to every location matching `segments`, returning how many
+/// matched. With `create`, missing object keys (and index == len on arrays)
+/// are created on the way.
+fn update_at(
+    value: &mut Value,
+    segments: &[PathSegment],
+    create: bool,
+    f: &mut dyn FnMut(&mut Value),
+) -> Result<usize> {
+    let Some((segment, rest)) = segments.split_first() else {
+        f(value);
+        return Ok(1);
+    };
+    match segment {
+        PathSegment::Key(key) => {
+            if create && value.is_null() {
+                *value = Value::Object(Map::new());
+            }
+            let Value::Object(map) = value else {
+                return Ok(0);
+            };
+            if !map.contains_key(key) {
+                if !create {
+                    return Ok(0);
+                }
+                map.insert(key.clone(), Value::Null);
+            }
+            update_at(map.get_mut(key).expect("key exists"), rest, create, f)
+        }
+        PathSegment::Index(idx) => {
+            if create && value.is_null() {
+                *value = Value::Array(Vec::new());
+            }
+            let Value::Array(arr) = value else {
+                return Ok(0);
+            };
+            let len = arr.len() as i64;
+            let i = if *idx < 0 { len + idx } else { *idx };
+            if create && i == len {
+                arr.push(Value::Null);
+            }
+            match usize::try_from(i).ok().and_then(|i| arr.get_mut(i)) {
+                Some(item) => update_at(item, rest, create, f),
+                None => Ok(0),
+            }
+        }
+        PathSegment::Slice(start, end) => {
+            let Value::Array(arr) = value else {
+                return Ok(0);
+            };
+            let (s, e) = slice_bounds(arr.len(), *start, *end);
+            let mut matched = 0;
+            for item in arr.iter_mut().take(e).skip(s) {
+                matched += update_at(item, rest, create, f)?;
+            }
+            Ok(matched)
+        }
+        PathSegment::Wildcard => {
+            let mut matched = 0;
+            match value {
+                Value::Array(arr) => {
+                    for item in arr {
+                        matched += update_at(item, rest, create, f)?;
+                    }
+                }
+                Value::Object(map) => {
+                    for item in map.values_mut() {
+                        matched += update_at(item, rest, create, f)?;
+                    }
+                }
+                _ => {}
+            }
+            Ok(matched)
+        }
+        PathSegment::Filter(expr) => {
+            let Value::Array(arr) = value else {
+                return Ok(0);
+            };
+            let mut matched = 0;
+            for item in arr.iter_mut() {
+                if eval_filter_expr(item, expr) {
+                    matched += update_at(item, rest, create, f)?;
+                }
+            }
+            Ok(matched)
+        }
+    }
+}
+
+/// Remove the child selected by `segment` from `parent`.
+fn remove(parent: &mut Value, segment: &PathSegment) {
+    match (parent, segment) {
+        (Value::Object(map), PathSegment::Key(key)) => {
+            map.remove(key);
+        }
+        (Value::Object(map), PathSegment::Wildcard) => map.clear(),
+        (Value::Array(arr), PathSegment::Index(idx)) => {
+            let len = arr.len() as i64;
+            let i = if *idx < 0 { len + idx } else { *idx };
+            if (0..len).contains(&i) {
+                arr.remove(i as usize);
+            }
+        }
+        (Value::Array(arr), PathSegment::Slice(start, end)) => {
+            let (s, e) = slice_bounds(arr.len(), *start, *end);
+            if s < e {
+                arr.drain(s..e);
+            }
+        }
+        (Value::Array(arr), PathSegment::Wildcard) => arr.clear(),
+        (Value::Array(arr), PathSegment::Filter(expr)) => {
+            arr.retain(|item| !eval_filter_expr(item, expr));
+        }
+        _ => {}
+    }
+}
+
+fn slice_bounds(len: usize, start: Option<i64>, end: Option<i64>) -> (usize, usize) {
+    let resolve = |i: i64| {
+        if i < 0 {
+            (len as i64 + i).max(0) as usize
+        } else {
+            (i as usize).min(len)
+        }
+    };
+    (
+        resolve(start.unwrap_or(0)),
+        resolve(end.unwrap_or(len as i64)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::transform::apply_transform;
+    use serde_json::json;
+
+    fn deployment() -> Value {
+        json!({
+            "metadata": {"name": "web", "managedFields": [{"manager": "kubectl"}]},
+            "spec": {"replicas": 1, "containers": [
+                {"name": "app", "image": "app:1"},
+                {"name": "sidecar", "image": "proxy:1"}
+            ]}
+        })
+    }
+
+    #[test]
+    fn test_set_existing_and_new_paths() {
+        let out = apply_transform(&deployment(), ":set .spec.replicas 3").unwrap();
+        assert_eq!(out["spec"]["replicas"], json!(3));
+        assert_eq!(out["metadata"]["name"], json!("web"));
+
+        let out = apply_transform(&json!({}), ":set .a.b.c true").unwrap();
+        assert_eq!(out, json!({"a": {"b": {"c": true}}}));
+    }
+
+    #[test]
+    fn test_set_value_literals() {
+        let set = |arg: &str| {
+            apply_transform(&json!({}), &format!(":set .v {arg}")).unwrap()["v"].clone()
+        };
+        assert_eq!(set("3"), json!(3));
+        assert_eq!(set("\"3\""), json!("3"));
+        assert_eq!(set("null"), json!(null));
+        assert_eq!(set("hello world"), json!("hello world"));
+        assert_eq!(set("'single quoted'"), json!("single quoted"));
+        assert_eq!(set(r#"{"a": [1, 2]}"#), json!({"a": [1, 2]}));
+    }
+
+    #[test]
+    fn test_set_fan_out() {
+        let out = apply_transform(
+            &deployment(),
+            r#":set .spec.containers[name == "app"].image "app:2""#,
+        )
+        .unwrap();
+        assert_eq!(out["spec"]["containers"][0]["image"], json!("app:2"));
+        assert_eq!(out["spec"]["containers"][1]["image"], json!("proxy:1"));
+
+        let out = apply_transform(&deployment(), ":set .spec.containers[*].pull Always").unwrap();
+        assert_eq!(out["spec"]["containers"][1]["pull"], json!("Always"));
+    }
+
+    #[test]
+    fn test_set_errors() {
+        assert!(apply_transform(&json!({}), ":set").is_err());
+        assert!(apply_transform(&json!({}), ":set .a").is_err());
+        assert!(apply_transform(&json!({"a": 1}), ":set .a.b 2").is_err());
+    }
+
+    #[test]
+    fn test_del_paths() {
+        let out = apply_transform(
+            &deployment(),
+            ":del .metadata.managedFields .spec.containers[-1]",
+        )
+        .unwrap();
+        assert_eq!(out["metadata"], json!({"name": "web"}));
+        assert_eq!(out["spec"]["containers"].as_array().unwrap().len(), 1);
+
+        // Missing paths are ignored
+        let out = apply_transform(&deployment(), ":del .nope.x").unwrap();
+        assert_eq!(out, deployment());
+
+        let out =
+            apply_transform(&deployment(), r#":del .spec.containers[name == "app"]"#).unwrap();
+        assert_eq!(out["spec"]["containers"][0]["name"], json!("sidecar"));
+        assert!(apply_transform(&deployment(), ":del").is_err());
+    }
+
+    #[test]
+    fn test_rename_object_and_array() {
+        let out = apply_transform(&json!({"a": 1, "b": 2}), ":rename a=x b=y").unwrap();
+        assert_eq!(out, json!({"x": 1, "y": 2}));
+
+        let data = json!([{"id": 1, "n": "a"}, {"id": 2}, 3]);
+        let out = apply_transform(&data, r#":rename n="full name""#).unwrap();
+        assert_eq!(out, json!([{"id": 1, "full name": "a"}, {"id": 2}, 3]));
+        assert!(apply_transform(&data, ":rename n").is_err());
+    }
+
+    #[test]
+    fn test_default_fills_missing_and_null() {
+        let data = json!([{"role": "admin"}, {"role": null}, {}]);
+        let out = apply_transform(&data, ":default role=user active=true").unwrap();
+        assert_eq!(
+            out,
+            json!([
+                {"role": "admin", "active": true},
+                {"role": "user", "active": true},
+                {"role": "user", "active": true}
+            ])
+        );
+        let out = apply_transform(&json!({}), r#":default n="3""#).unwrap();
+        assert_eq!(out, json!({"n": "3"}));
+    }
+}
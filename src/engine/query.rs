@@ -18,6 +18,8 @@ pub enum FilterValue {
     Number(f64),
     Bool(bool),
     Null,
+    /// A `date("...")` literal, as epoch milliseconds (UTC).
+    Date(i64),
 }
 
 // Manual Eq impl because f64 doesn't implement Eq, but we need it for PathSegment.
@@ -165,10 +167,24 @@ pub fn parse_predicate(expr: &str) -> Result<Predicate, String> {
     Err(format!("no valid operator found in: {expr}"))
 }
 
-/// Parse a filter value literal: string, number, bool, or null.
+/// Parse a filter value literal: string, number, bool, null, or `date("...")`.
 fn parse_filter_value(s: &str) -> Result<FilterValue, String> {
     let s = s.trim();
 
+    // Date literal: date("2024-01-01") or date('2024-01-01T10:00:00Z')
+    if let Some(inner) = s.strip_prefix("date(").and_then(|r| r.strip_suffix(')')) {
+        let inner = inner.trim();
+        let unquoted = inner
+            .strip_prefix('"')
+            .and_then(|r| r.strip_suffix('"'))
+            .or_else(|| inner.strip_prefix('\'').and_then(|r| r.strip_suffix('\'')))
+            .unwrap_or(inner);
+        return match super::datetime::parse_datetime(unquoted) {
+            Some(dt) => Ok(FilterValue::Date(dt.timestamp_millis())),
+            None => Err(format!("invalid date literal: {s}")),
+        };
+    }
+
     // Quoted string
     if (s.starts_with('"') && s.ends_with('"')) || (s.starts_with('\'') && s.ends_with('\'')) {
         return Ok(FilterValue::String(s[1..s.len() - 1].to_string()));
@@ -738,4 +754,27 @@ mod tests {
         assert_eq!(pred.field, "price");
         assert_eq!(pred.value, FilterValue::Number(9.99));
     }
+
+    #[test]
+    fn test_parse_predicate_date_literal() {
+        let pred = parse_predicate("created_at > date(\"2024-01-01\")").unwrap();
+        assert_eq!(pred.value, FilterValue::Date(1_704_067_200_000));
+        let pred = parse_predicate("ts <= date('2024-01-01T02:00:00+02:00')").unwrap();
+        assert_eq!(pred.value, FilterValue::Date(1_704_067_200_000));
+        assert!(parse_predicate("ts < date(\"not a date\")").is_err());
+    }
+
+    #[test]
+    fn test_parse_filter_with_date_containing_dots() {
+        let result = parse(".events[ts >= date(\"2024-01-01T00:00:00.500Z\")].id").unwrap();
+        assert_eq!(result.len(), 3);
+        assert_eq!(
+            result[1],
+            PathSegment::Filter(FilterExpr::Single(Predicate {
+                field: "ts".into(),
+                op: CompareOp::Ge,
+                value: FilterValue::Date(1_704_067_200_500),
+            }))
+        );
+    }
 }
@@ -0,0 +1,307 @@
+//! Registry of transform commands.
+//!
+//! Every `:command` in a transform chain — built-in or added by an embedding
+//! application — is a [`Transform`] looked up by name in the global registry.
+//! The help overlay, the AI system prompt and the MCP tool description are
+//! generated from it, so they always list exactly what can be run.
+//!
+//! Registering a transform from an application that uses `jdx` as a library:
+//!
+//! ```
+//! use anyhow::Result;
+//! use jdx::engine::lexer::Args;
+//! use jdx::engine::registry::{self, ArgSpec, Transform, TransformContext};
+//! use serde_json::{json, Value};
+//!
+//! struct Double;
+//!
+//! impl Transform for Double {
+//!     fn name(&self) -> &str {
+//!         ":double"
+//!     }
+//!     fn help(&self) -> &str {
+//!         "Multiply numbers by two"
+//!     }
+//!     fn apply(&self, value: &Value, _args: &Args, _ctx: &TransformContext) -> Result<Value> {
+//!         Ok(json!(value.as_f64().unwrap_or(0.0) * 2.0))
+//!     }
+//! }
+//!
+//! registry::register(Double).unwrap();
+//! let out = jdx::engine::transform::apply_transform(&json!(21), ":double").unwrap();
+//! assert_eq!(out, json!(42.0));
+//! ```
+
+use std::sync::{Arc, OnceLock, PoisonError, RwLock};
+
+use anyhow::{bail, Result};
+use serde_json::Value;
+
+use super::exec::ExecPolicy;
+use super::lexer::Args;
+use super::transform::{builtins, Documents};
+
+/// A transform command that can appear in a chain, e.g. `:sort age desc`.
+pub trait Transform: Send + Sync {
+    /// Command name including the leading `:`, e.g. `":sort"`.
+    fn name(&self) -> &str;
+
+    /// The arguments the command accepts. Defaults to none.
+    fn args(&self) -> ArgSpec {
+        ArgSpec::none()
+    }
+
+    /// One-line description used by the help overlay and the AI prompt.
+    fn help(&self) -> &str;
+
+    /// Apply the transform to the current value.
+    fn apply(&self, value: &Value, args: &Args, ctx: &TransformContext) -> Result<Value>;
+}
+
+/// Describes the arguments of a [`Transform`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArgSpec {
+    /// Usage shown after the command name, e.g. `field [asc|desc]`.
+    pub usage: String,
+    /// Whether the command accepts arguments at all. If not, any argument
+    /// is rejected before [`Transform::apply`] is called.
+    pub takes_args: bool,
+    /// Lex a leading `/pattern/flags` argument as a regex literal.
+    pub regex: bool,
+}
+
+impl ArgSpec {
+    /// A command that takes no arguments.
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// A command that takes arguments, described by `usage`.
+    pub fn usage(usage: impl Into<String>) -> Self {
+        Self {
+            usage: usage.into(),
+            takes_args: true,
+            regex: false,
+        }
+    }
+
+    /// Mark the first argument as a `/regex/` literal.
+    pub fn with_regex(mut self) -> Self {
+        self.regex = true;
+        self
+    }
+}
+
+/// State available to a transform besides its input value and arguments.
+pub struct TransformContext<'a> {
+    /// Secondary documents loaded with `--with name=file`.
+    pub docs: &'a Documents,
+    /// Whether the input is a stream of NDJSON records rather than a single
+    /// document; `:exec` then talks NDJSON to its subprocess.
+    pub stream: bool,
+    /// Which commands `:exec` may run, and for how long.
+    pub exec: &'a ExecPolicy,
+}
+
+/// An ordered set of transforms, keyed by name.
+#[derive(Clone, Default)]
+pub struct Registry {
+    transforms: Vec<Arc<dyn Transform>>,
+}
+
+impl Registry {
+    /// An empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry holding the built-in transforms.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        for builtin in builtins() {
+            registry.transforms.push(Arc::new(*builtin));
+        }
+        registry
+    }
+
+    /// Add a transform, replacing any existing one with the same name.
+    pub fn register(&mut self, transform: impl Transform + 'static) -> Result<()> {
+        let name = transform.name().to_string();
+        let valid = name
+            .strip_prefix(':')
+            .and_then(|rest| {
+                let mut chars = rest.chars();
+                let first = chars.next()?;
+                Some(
+                    (first.is_ascii_alphabetic() || first == '_')
+                        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_'),
+                )
+            })
+            .unwrap_or(false);
+        if !valid {
+            bail!(
+                "invalid transform name '{name}'.\n\
+                 Hint: names start with ':' followed by letters, digits or '_', e.g. :my_transform"
+            );
+        }
+        let transform: Arc<dyn Transform> = Arc::new(transform);
+        match self.transforms.iter_mut().find(|t| t.name() == name) {
+            Some(existing) => *existing = transform,
+            None => self.transforms.push(transform),
+        }
+        Ok(())
+    }
+
+    /// Look up a transform by name (with the leading `:`).
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Transform>> {
+        self.transforms.iter().find(|t| t.name() == name).cloned()
+    }
+
+    /// All transforms, built-ins first, in registration order.
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn Transform>> {
+        self.transforms.iter()
+    }
+
+    /// All command names, in registration order.
+    pub fn names(&self) -> Vec<String> {
+        self.iter().map(|t| t.name().to_string()).collect()
+    }
+}
+
+fn global() -> &'static RwLock<Registry> {
+    static REGISTRY: OnceLock<RwLock<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| RwLock::new(Registry::with_builtins()))
+}
+
+/// Register a transform globally, making it available to every query.
+/// A transform with the name of a built-in replaces the built-in.
+pub fn register(transform: impl Transform + 'static) -> Result<()> {
+    global()
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .register(transform)
+}
+
+/// Look up a globally registered transform by name.
+pub fn get(name: &str) -> Option<Arc<dyn Transform>> {
+    global()
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(name)
+}
+
+/// A snapshot of the global registry.
+pub fn snapshot() -> Registry {
+    global()
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Usage line for a transform, e.g. `:sort field [asc|desc]`.
+pub fn usage_line(transform: &dyn Transform) -> String {
+    let spec = transform.args();
+    if spec.usage.is_empty() {
+        transform.name().to_string()
+    } else {
+        format!("{} {}", transform.name(), spec.usage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::transform::apply_transform;
+    use serde_json::json;
+
+    struct Shout;
+
+    impl Transform for Shout {
+        fn name(&self) -> &str {
+            ":test_shout"
+        }
+        fn args(&self) -> ArgSpec {
+            ArgSpec::usage("[suffix]")
+        }
+        fn help(&self) -> &str {
+            "Uppercase a string and add a suffix"
+        }
+        fn apply(&self, value: &Value, args: &Args, _ctx: &TransformContext) -> Result<Value> {
+            let suffix = args.optional_value()?.unwrap_or("!");
+            match value {
+                Value::String(s) => Ok(Value::String(format!("{}{suffix}", s.to_uppercase()))),
+                _ => bail!(":test_shout requires a string"),
+            }
+        }
+    }
+
+    struct Named(&'static str);
+
+    impl Transform for Named {
+        fn name(&self) -> &str {
+            self.0
+        }
+        fn help(&self) -> &str {
+            "test"
+        }
+        fn apply(&self, _value: &Value, _args: &Args, _ctx: &TransformContext) -> Result<Value> {
+            Ok(Value::String(self.0.to_string()))
+        }
+    }
+
+    #[test]
+    fn test_builtins_registered() {
+        let registry = Registry::with_builtins();
+        let names = registry.names();
+        for name in [
+            ":keys",
+            ":sort",
+            ":pick",
+            ":lookup",
+            ":replace",
+            ":bucket_time",
+        ] {
+            assert!(names.iter().any(|n| n == name), "missing {name}");
+        }
+        assert!(registry.get(":replace").unwrap().args().regex);
+        assert!(!registry.get(":keys").unwrap().args().takes_args);
+    }
+
+    #[test]
+    fn test_register_global_transform() {
+        register(Shout).unwrap();
+        let result = apply_transform(&json!("hi"), ":test_shout :test_shout \"?\"").unwrap();
+        assert_eq!(result, json!("HI!?"));
+        assert!(snapshot().names().contains(&":test_shout".to_string()));
+    }
+
+    #[test]
+    fn test_register_replaces_same_name() {
+        let mut registry = Registry::new();
+        registry.register(Named(":same")).unwrap();
+        registry.register(Named(":other")).unwrap();
+        registry.register(Named(":same")).unwrap();
+        assert_eq!(registry.names(), vec![":same", ":other"]);
+    }
+
+    #[test]
+    fn test_register_rejects_invalid_names() {
+        let mut registry = Registry::new();
+        for name in ["shout", ":", ":1st", ":has space", ":@alias"] {
+            assert!(registry.register(Named(name)).is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_no_arg_transform_rejects_arguments() {
+        let err = apply_transform(&json!({"a": 1}), ":keys extra").unwrap_err();
+        assert!(err.to_string().contains("takes no arguments"), "{err}");
+    }
+
+    #[test]
+    fn test_usage_line() {
+        let registry = Registry::with_builtins();
+        assert_eq!(usage_line(&*registry.get(":keys").unwrap()), ":keys");
+        assert!(usage_line(&*registry.get(":sort").unwrap()).starts_with(":sort field"));
+    }
+}
@@ -13,6 +13,10 @@ pub enum SchemaType {
     String {
         sample: Option<String>,
     },
+    /// A string that parses as a date or date-time (ISO 8601 style).
+    DateTime {
+        sample: Option<String>,
+    },
     Array {
         len_min: usize,
         len_max: usize,
@@ -46,6 +50,9 @@ pub fn infer_schema(value: &Value, max_samples: usize) -> SchemaType {
             min: n.as_f64(),
             max: n.as_f64(),
         },
+        Value::String(s) if super::datetime::is_datetime_string(s) => SchemaType::DateTime {
+            sample: Some(s.chars().take(30).collect()),
+        },
         Value::String(s) => SchemaType::String {
             sample: Some(s.chars().take(30).collect()),
         },
@@ -120,6 +127,14 @@ fn merge_schemas(a: SchemaType, b: SchemaType) -> SchemaType {
             },
         },
         (SchemaType::String { .. }, SchemaType::String { sample }) => SchemaType::String { sample },
+        (SchemaType::DateTime { .. }, SchemaType::DateTime { sample }) => {
+            SchemaType::DateTime { sample }
+        }
+        // A field that is only sometimes a date is just a string
+        (
+            SchemaType::String { .. } | SchemaType::DateTime { .. },
+            SchemaType::String { sample } | SchemaType::DateTime { sample },
+        ) => SchemaType::String { sample },
         // Merge objects
         (
             SchemaType::Object {
@@ -188,6 +203,7 @@ fn type_name(schema: &SchemaType) -> String {
         SchemaType::Bool => "bool".into(),
         SchemaType::Number { .. } => "number".into(),
         SchemaType::String { .. } => "string".into(),
+        SchemaType::DateTime { .. } => "datetime".into(),
         SchemaType::Array { .. } => "array".into(),
         SchemaType::Object { .. } => "object".into(),
         SchemaType::Union(types) => types.iter().cloned().collect::<Vec<_>>().join(" | "),
@@ -210,6 +226,10 @@ pub fn format_schema(schema: &SchemaType, indent: usize) -> String {
             Some(s) => format!("string  # \"{s}\""),
             None => "string".into(),
         },
+        SchemaType::DateTime { sample } => match sample {
+            Some(s) => format!("datetime  # \"{s}\""),
+            None => "datetime".into(),
+        },
         SchemaType::Array {
             len_min,
             len_max,
@@ -348,4 +368,25 @@ mod tests {
         assert!(output.contains("string"));
         assert!(output.contains("number"));
     }
+
+    #[test]
+    fn test_infer_datetime_field() {
+        let data = json!([
+            {"created_at": "2024-01-01T10:00:00Z", "note": "2024-01-01"},
+            {"created_at": "2024-02-01", "note": "later"}
+        ]);
+        let schema = infer_schema(&data, 10);
+        let SchemaType::Array { items, .. } = schema else {
+            panic!("expected array schema");
+        };
+        let SchemaType::Object { fields } = *items else {
+            panic!("expected object items");
+        };
+        assert!(matches!(
+            fields["created_at"].schema,
+            SchemaType::DateTime { .. }
+        ));
+        assert!(matches!(fields["note"].schema, SchemaType::String { .. }));
+        assert!(format_schema(&fields["created_at"].schema, 0).starts_with("datetime"));
+    }
 }
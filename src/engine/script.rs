@@ -0,0 +1,333 @@
+//! The `:script` transform: evaluate a sandboxed [Rhai](https://rhai.rs)
+//! expression for logic that the transform DSL can't express.
+//!
+//! The current value is bound to `it` (and its position to `index`). On an
+//! array the script runs once per element and its results form the new
+//! array; returning `()` drops the element, so `if it.age > 30 { it }` is a
+//! filter. Pass `each=false` to run once with the whole array as `it`.
+//!
+//! Scripts have no access to the filesystem, network or modules, cannot
+//! print, and are stopped after [`MAX_OPERATIONS`] steps or [`TIME_LIMIT`], so
+//! they are safe to re-evaluate on every keystroke. Helpers:
+//!
+//! - `path(v, ".a.b[0]")` — traverse like a query, `()` when missing
+//! - `to_date(v)` — RFC 3339 string (or `()`), `epoch(v)` / `epoch_ms(v)`
+//! - `format_date(v, "%Y-%m-%d")`, `days_between(a, b)`
+//!
+//! A bare word instead of a quoted expression runs a named script from
+//! `<config dir>/scripts/<name>.rhai`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::serde::{from_dynamic, to_dynamic};
+use rhai::{Dynamic, Engine, EvalAltResult, Scope, AST};
+use serde_json::Value;
+
+use super::datetime::{to_rfc3339, value_to_datetime};
+use super::json::traverse;
+use super::lexer::{Args, TokenKind};
+use super::query::parse as parse_query;
+
+/// Operations a single evaluation may perform before it is stopped.
+pub const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// Wall-clock budget for one `:script` stage, across all elements.
+pub const TIME_LIMIT: Duration = Duration::from_millis(500);
+
+type ScriptResult<T> = std::result::Result<T, Box<EvalAltResult>>;
+
+/// `:script "<expr>" [each=false]` or `:script <name> [each=false]`
+pub(crate) fn transform_script(value: &Value, args: &Args) -> Result<Value> {
+    const USAGE: &str = "usage: :script \"<expr>\" [each=false] or :script <name>";
+    let (positional, options) = args.positional_and_options(&["each"])?;
+    let each = match options.get("each").map(String::as_str) {
+        None | Some("true") => true,
+        Some("false") => false,
+        Some(other) => bail!(":script: each must be true or false, got '{other}'"),
+    };
+    let source = match positional.as_slice() {
+        [token] if token.kind == TokenKind::Word => {
+            let Some(dir) = scripts_dir() else {
+                bail!(":script: no config directory to load '{}' from", token.text);
+            };
+            load_named(&dir, &token.text).map_err(|e| args.error(e, token.span.clone()))?
+        }
+        [token] => token.text.clone(),
+        [] => bail!(":script requires an expression.\n{USAGE}"),
+        [_, extra, ..] => {
+            return Err(args.error(
+                format!("unexpected argument; quote the whole expression ({USAGE})"),
+                extra.span.clone(),
+            ))
+        }
+    };
+    let span = positional[0].span.clone();
+
+    let engine = sandboxed_engine(Instant::now());
+    let ast = engine
+        .compile(&source)
+        .map_err(|e| args.error(format!("script error: {e}"), span.clone()))?;
+
+    match value {
+        Value::Array(items) if each => {
+            let mut out = Vec::with_capacity(items.len());
+            for (i, item) in items.iter().enumerate() {
+                let result = run(&engine, &ast, item, i)
+                    .map_err(|e| anyhow::anyhow!(":script: element {i}: {e}"))?;
+                out.extend(result);
+            }
+            Ok(Value::Array(out))
+        }
+        other => Ok(run(&engine, &ast, other, 0)
+            .map_err(|e| anyhow::anyhow!(":script: {e}"))?
+            .unwrap_or(Value::Null)),
+    }
+}
+
+/// Directory that named scripts are loaded from.
+pub fn scripts_dir() -> Option<PathBuf> {
+    crate::config::config_dir().map(|d| d.join("scripts"))
+}
+
+/// Read `<dir>/<name>.rhai`.
+pub fn load_named(dir: &Path, name: &str) -> Result<String> {
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        bail!("invalid script name '{name}' (quote inline expressions: :script \"it.x\")");
+    }
+    let path = dir.join(format!("{name}.rhai"));
+    match fs::read_to_string(&path) {
+        Ok(source) => Ok(source),
+        Err(_) => bail!(
+            "no script named '{name}' (looked for {}).\n\
+             Hint: quote inline expressions, e.g. :script \"it.price * 2\"",
+            path.display()
+        ),
+    }
+}
+
+/// An engine with no I/O, no modules, no `eval`, and operation/time limits.
+fn sandboxed_engine(started: Instant) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_module_resolver(DummyModuleResolver::new());
+    engine.disable_symbol("eval");
+    engine.on_print(|_| {});
+    engine.on_debug(|_, _, _| {});
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(32);
+    engine.set_max_expr_depths(64, 32);
+    engine.set_max_string_size(1 << 20);
+    engine.set_max_array_size(100_000);
+    engine.set_max_map_size(10_000);
+    engine.on_progress(move |_| {
+        (started.elapsed() > TIME_LIMIT).then(|| Dynamic::from("time limit exceeded"))
+    });
+
+    engine.register_fn("path", script_path);
+    engine.register_fn("to_date", |v: Dynamic| -> ScriptResult<Dynamic> {
+        Ok(to_datetime(v)?.map_or(Dynamic::UNIT, |dt| to_rfc3339(&dt).into()))
+    });
+    engine.register_fn("epoch", |v: Dynamic| -> ScriptResult<Dynamic> {
+        Ok(to_datetime(v)?.map_or(Dynamic::UNIT, |dt| dt.timestamp().into()))
+    });
+    engine.register_fn("epoch_ms", |v: Dynamic| -> ScriptResult<Dynamic> {
+        Ok(to_datetime(v)?.map_or(Dynamic::UNIT, |dt| dt.timestamp_millis().into()))
+    });
+    engine.register_fn(
+        "format_date",
+        |v: Dynamic, fmt: &str| -> ScriptResult<Dynamic> {
+            // chrono panics while formatting an unknown specifier, so check first
+            let items = chrono::format::StrftimeItems::new(fmt)
+                .parse()
+                .map_err(|_| format!("format_date: invalid format '{fmt}'"))?;
+            Ok(to_datetime(v)?.map_or(Dynamic::UNIT, |dt| {
+                dt.format_with_items(items.iter()).to_string().into()
+            }))
+        },
+    );
+    engine.register_fn(
+        "days_between",
+        |a: Dynamic, b: Dynamic| -> ScriptResult<Dynamic> {
+            Ok(match (to_datetime(a)?, to_datetime(b)?) {
+                (Some(a), Some(b)) => ((b - a).num_seconds() as f64 / 86_400.0).into(),
+                _ => Dynamic::UNIT,
+            })
+        },
+    );
+    engine
+}
+
+/// Evaluate the script for one value. `None` means the script returned `()`.
+fn run(engine: &Engine, ast: &AST, value: &Value, index: usize) -> Result<Option<Value>> {
+    let mut scope = Scope::new();
+    scope.push("it", to_dynamic(value).map_err(|e| describe(&e))?);
+    scope.push_constant("index", index as i64);
+    let result: Dynamic = engine
+        .eval_ast_with_scope(&mut scope, ast)
+        .map_err(|e| describe(&e))?;
+    if result.is_unit() {
+        return Ok(None);
+    }
+    Ok(Some(from_dynamic(&result).map_err(|e| describe(&e))?))
+}
+
+/// Turn Rhai errors into messages that fit the status bar.
+fn describe(err: &EvalAltResult) -> anyhow::Error {
+    match err {
+        EvalAltResult::ErrorTooManyOperations(_) => {
+            anyhow::anyhow!("script stopped after {MAX_OPERATIONS} operations (infinite loop?)")
+        }
+        EvalAltResult::ErrorTerminated(..) => {
+            anyhow::anyhow!("script stopped after {}ms", TIME_LIMIT.as_millis())
+        }
+        other => anyhow::anyhow!("{other}"),
+    }
+}
+
+fn script_path(v: Dynamic, path: &str) -> ScriptResult<Dynamic> {
+    let value: Value = from_dynamic(&v)?;
+    let path = if path.starts_with('.') {
+        path.to_string()
+    } else {
+        format!(".{path}")
+    };
+    let segments = parse_query(&path).map_err(|e| format!("invalid path '{path}': {e}"))?;
+    match traverse(&value, &segments).value {
+        Some(found) => to_dynamic(found),
+        None => Ok(Dynamic::UNIT),
+    }
+}
+
+fn to_datetime(v: Dynamic) -> ScriptResult<Option<chrono::DateTime<chrono::FixedOffset>>> {
+    let value: Value = from_dynamic(&v)?;
+    Ok(value_to_datetime(&value, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn script(value: Value, args: &str) -> Result<Value> {
+        transform_script(&value, &Args::parse(":script", args)?)
+    }
+
+    #[test]
+    fn test_script_maps_elements() {
+        let data = json!([{"price": 2, "qty": 3}, {"price": 1.5, "qty": 2}]);
+        assert_eq!(
+            script(data, r#""it.price * it.qty""#).unwrap(),
+            json!([6, 3.0])
+        );
+    }
+
+    #[test]
+    fn test_script_unit_drops_element() {
+        let data = json!([{"age": 25}, {"age": 40}, {"age": 35}]);
+        assert_eq!(
+            script(data, r#""if it.age > 30 { it }""#).unwrap(),
+            json!([{"age": 40}, {"age": 35}])
+        );
+    }
+
+    #[test]
+    fn test_script_builds_objects_and_uses_index() {
+        let data = json!(["a", "b"]);
+        assert_eq!(
+            script(data, r##""#{ pos: index, name: it.to_upper() }""##).unwrap(),
+            json!([{"pos": 0, "name": "A"}, {"pos": 1, "name": "B"}])
+        );
+    }
+
+    #[test]
+    fn test_script_whole_value() {
+        let data = json!([1, 2, 3]);
+        assert_eq!(script(data, r#""it.len()" each=false"#).unwrap(), json!(3));
+        assert_eq!(script(json!({"a": 1}), r#""it.a + 1""#).unwrap(), json!(2));
+    }
+
+    #[test]
+    fn test_script_path_and_date_helpers() {
+        let data = json!({
+            "user": {"tags": ["x", "y"]},
+            "start": "2024-01-01T00:00:00Z",
+            "end": "2024-01-03T12:00:00Z"
+        });
+        assert_eq!(
+            script(data.clone(), r#""path(it, \"user.tags[1]\")""#).unwrap(),
+            json!("y")
+        );
+        assert_eq!(
+            script(data.clone(), r#""path(it, \".missing\")""#).unwrap(),
+            Value::Null
+        );
+        assert_eq!(
+            script(data.clone(), r#""days_between(it.start, it.end)""#).unwrap(),
+            json!(2.5)
+        );
+        assert_eq!(
+            script(data.clone(), r#""format_date(it.end, \"%d/%m\")""#).unwrap(),
+            json!("03/01")
+        );
+        assert_eq!(
+            script(data, r#""epoch(it.start)""#).unwrap(),
+            json!(1_704_067_200)
+        );
+    }
+
+    #[test]
+    fn test_script_format_date_rejects_bad_format() {
+        let err = script(
+            json!("2024-01-03T12:00:00Z"),
+            r#""format_date(it, \"%Q\")""#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("invalid format"), "{err}");
+    }
+
+    #[test]
+    fn test_script_is_sandboxed() {
+        let err = script(json!(1), r#""import \"os\" as os; 1""#).unwrap_err();
+        assert!(err.to_string().contains(":script"), "{err}");
+        assert!(script(json!(1), r#""eval(\"1 + 1\")""#).is_err());
+        // print must not write to the terminal; it just returns ()
+        assert_eq!(script(json!(1), r#""print(\"x\"); it""#).unwrap(), json!(1));
+    }
+
+    #[test]
+    fn test_script_operation_limit() {
+        let err = script(json!(1), r#""loop { }""#).unwrap_err();
+        let msg = err.to_string();
+        assert!(
+            msg.contains("operations") || msg.contains("stopped after"),
+            "{msg}"
+        );
+    }
+
+    #[test]
+    fn test_script_errors_point_at_script() {
+        let args = Args::parse(":script", r#""it +""#).unwrap();
+        let err = transform_script(&json!(1), &args).unwrap_err();
+        let span = err
+            .downcast_ref::<super::super::lexer::SyntaxError>()
+            .map(|e| e.span.clone());
+        assert_eq!(span, Some(0..6));
+        let err = script(json!([1, "x"]), r#""it * 2""#).unwrap_err();
+        assert!(err.to_string().starts_with(":script: element 1:"), "{err}");
+    }
+
+    #[test]
+    fn test_load_named_script() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("double.rhai"), "it * 2").unwrap();
+        assert_eq!(load_named(dir.path(), "double").unwrap(), "it * 2");
+        assert!(load_named(dir.path(), "missing").is_err());
+        assert!(load_named(dir.path(), "../etc/passwd").is_err());
+    }
+}
@@ -60,7 +60,7 @@ impl Suggester {
             .collect();
 
         // Sort by score descending (best match first)
-        candidates.sort_by(|a, b| b.score.cmp(&a.score));
+        candidates.sort_by_key(|c| std::cmp::Reverse(c.score));
         candidates
     }
 
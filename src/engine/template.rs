@@ -0,0 +1,373 @@
+//! String templates for the `:format` transform.
+//!
+//! A template is literal text with `{placeholder}` fields:
+//!
+//! - `{name}`, `{user.email}`, `{items[0].sku}` — paths into the current value
+//!   (`{.}` is the value itself)
+//! - `{orders.length}` — length of an array, string or object
+//! - `{nick|name|"anonymous"}` — the first alternative that is present and
+//!   not null; quoted alternatives are literals
+//! - `{price:.2}`, `{total:,.2}`, `{id:05}`, `{name:<12}` — format specifiers:
+//!   optional alignment (`<`, `>`, `^`), zero padding, width, thousands
+//!   separator (`,`) and precision (decimals for numbers, max chars for strings)
+//! - `{{` and `}}` — literal braces
+
+use anyhow::{bail, Result};
+use serde_json::Value;
+
+use super::json::traverse;
+use super::query::{parse as parse_query, PathSegment};
+
+/// A parsed template, ready to be rendered against many values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Field {
+        alternatives: Vec<Alternative>,
+        spec: Spec,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Alternative {
+    Path(Vec<PathSegment>),
+    Literal(String),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Spec {
+    align: Option<char>,
+    zero_pad: bool,
+    width: usize,
+    thousands: bool,
+    precision: Option<usize>,
+}
+
+impl Template {
+    /// Parse a template string, reporting unbalanced braces and bad specifiers.
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.char_indices().peekable();
+
+        while let Some((start, c)) = chars.next() {
+            match c {
+                '{' if chars.peek().map(|(_, c)| *c) == Some('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek().map(|(_, c)| *c) == Some('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '}' => bail!("unmatched '}}' at position {start} (use '}}}}' for a literal brace)"),
+                '{' => {
+                    let mut body = String::new();
+                    let mut quote: Option<char> = None;
+                    let mut closed = false;
+                    for (_, c) in chars.by_ref() {
+                        match (quote, c) {
+                            (None, '}') => {
+                                closed = true;
+                                break;
+                            }
+                            (None, '"' | '\'') => quote = Some(c),
+                            (Some(q), c) if c == q => quote = None,
+                            _ => {}
+                        }
+                        body.push(c);
+                    }
+                    if !closed {
+                        bail!("unclosed '{{' at position {start}");
+                    }
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(parse_field(&body)?);
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Self { parts })
+    }
+
+    /// Render the template against a value. Missing fields render as empty.
+    pub fn render(&self, value: &Value) -> String {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(s) => out.push_str(s),
+                Part::Field { alternatives, spec } => {
+                    let resolved = alternatives.iter().find_map(|alt| match alt {
+                        Alternative::Literal(s) => Some(Value::String(s.clone())),
+                        Alternative::Path(segments) => resolve(value, segments),
+                    });
+                    if let Some(v) = resolved {
+                        out.push_str(&apply_spec(&v, spec));
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Parse the inside of a `{...}` placeholder: `alt|alt|"literal":spec`.
+fn parse_field(body: &str) -> Result<Part> {
+    let (exprs, spec) = match split_unquoted(body, ':').as_slice() {
+        [exprs] => (exprs.to_string(), Spec::default()),
+        [exprs, spec] => (exprs.to_string(), parse_spec(spec)?),
+        _ => bail!("too many ':' in placeholder {{{body}}}"),
+    };
+    let mut alternatives = Vec::new();
+    for alt in split_unquoted(&exprs, '|') {
+        let alt = alt.trim();
+        let quoted = alt.len() >= 2
+            && ((alt.starts_with('"') && alt.ends_with('"'))
+                || (alt.starts_with('\'') && alt.ends_with('\'')));
+        if quoted {
+            alternatives.push(Alternative::Literal(alt[1..alt.len() - 1].to_string()));
+        } else if alt.is_empty() {
+            bail!("empty field in placeholder {{{body}}}");
+        } else {
+            let path = if alt.starts_with('.') {
+                alt.to_string()
+            } else {
+                format!(".{alt}")
+            };
+            let segments = parse_query(&path)
+                .map_err(|e| anyhow::anyhow!("invalid path '{alt}' in {{{body}}}: {e}"))?;
+            alternatives.push(Alternative::Path(segments));
+        }
+    }
+    Ok(Part::Field { alternatives, spec })
+}
+
+/// Split on `sep` outside of single or double quotes.
+fn split_unquoted(s: &str, sep: char) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == sep => {
+                pieces.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            None => {}
+        }
+    }
+    pieces.push(&s[start..]);
+    pieces
+}
+
+/// Parse a format specifier: `[<>^][0][width][,][.precision]`.
+fn parse_spec(spec: &str) -> Result<Spec> {
+    let mut out = Spec::default();
+    let mut rest = spec.trim();
+    if let Some(c) = rest.chars().next().filter(|c| matches!(c, '<' | '>' | '^')) {
+        out.align = Some(c);
+        rest = &rest[1..];
+    }
+    if let Some(r) = rest.strip_prefix('0') {
+        out.zero_pad = true;
+        rest = r;
+    }
+    let width_len = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    if width_len > 0 {
+        out.width = rest[..width_len].parse()?;
+        rest = &rest[width_len..];
+    }
+    if let Some(r) = rest.strip_prefix(',') {
+        out.thousands = true;
+        rest = r;
+    }
+    if let Some(r) = rest.strip_prefix('.') {
+        match r.parse() {
+            Ok(p) => out.precision = Some(p),
+            Err(_) => bail!("invalid precision in format specifier ':{spec}'"),
+        }
+        rest = "";
+    }
+    if !rest.is_empty() {
+        bail!("invalid format specifier ':{spec}' (expected e.g. .2, 8, <10, 05, ,.2)");
+    }
+    Ok(out)
+}
+
+/// Resolve a path, falling back to `.length` on arrays, strings and objects.
+/// Null counts as missing so that `{a|b}` falls through.
+fn resolve(value: &Value, segments: &[PathSegment]) -> Option<Value> {
+    if let Some(v) = traverse(value, segments).value {
+        return (!v.is_null()).then_some(v);
+    }
+    match segments.split_last() {
+        Some((PathSegment::Key(key), parent)) if key == "length" => {
+            let len = match traverse(value, parent).value? {
+                Value::Array(arr) => arr.len(),
+                Value::String(s) => s.chars().count(),
+                Value::Object(map) => map.len(),
+                _ => return None,
+            };
+            Some(Value::Number(len.into()))
+        }
+        _ => None,
+    }
+}
+
+fn apply_spec(value: &Value, spec: &Spec) -> String {
+    let (text, numeric) = match value {
+        Value::Number(n) => {
+            let mut s = match (spec.precision, n.as_f64()) {
+                (Some(p), Some(f)) => format!("{f:.p$}"),
+                _ => n.to_string(),
+            };
+            if spec.thousands {
+                s = group_thousands(&s);
+            }
+            (s, true)
+        }
+        Value::String(s) => match spec.precision {
+            Some(p) => (s.chars().take(p).collect(), false),
+            None => (s.clone(), false),
+        },
+        other => (other.to_string(), false),
+    };
+
+    let len = text.chars().count();
+    if len >= spec.width {
+        return text;
+    }
+    let fill = spec.width - len;
+    if spec.zero_pad && numeric && spec.align.is_none() {
+        return match text.strip_prefix('-') {
+            Some(digits) => format!("-{}{digits}", "0".repeat(fill)),
+            None => format!("{}{text}", "0".repeat(fill)),
+        };
+    }
+    let align = spec.align.unwrap_or(if numeric { '>' } else { '<' });
+    match align {
+        '>' => format!("{}{text}", " ".repeat(fill)),
+        '^' => format!(
+            "{}{text}{}",
+            " ".repeat(fill / 2),
+            " ".repeat(fill - fill / 2)
+        ),
+        _ => format!("{text}{}", " ".repeat(fill)),
+    }
+}
+
+/// Insert `,` between groups of three digits in the integer part.
+fn group_thousands(s: &str) -> String {
+    let (sign, unsigned) = match s.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", s),
+    };
+    let (int, frac) = match unsigned.find('.') {
+        Some(i) => unsigned.split_at(i),
+        None => (unsigned, ""),
+    };
+    let mut grouped = String::new();
+    for (i, c) in int.chars().enumerate() {
+        if i > 0 && (int.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    format!("{sign}{grouped}{frac}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str, value: &Value) -> String {
+        Template::parse(template).unwrap().render(value)
+    }
+
+    #[test]
+    fn test_render_nested_paths_and_length() {
+        let user = json!({
+            "name": "Ada",
+            "email": "ada@example.com",
+            "orders": [{"sku": "A1"}, {"sku": "B2"}]
+        });
+        assert_eq!(
+            render(
+                "{name} <{email}> — {orders.length} orders, first {orders[0].sku}",
+                &user
+            ),
+            "Ada <ada@example.com> — 2 orders, first A1"
+        );
+    }
+
+    #[test]
+    fn test_render_defaults() {
+        let template = "{nick|name|\"anon\"}";
+        assert_eq!(
+            render(template, &json!({"nick": "ace", "name": "Ada"})),
+            "ace"
+        );
+        assert_eq!(
+            render(template, &json!({"nick": null, "name": "Ada"})),
+            "Ada"
+        );
+        assert_eq!(render(template, &json!({})), "anon");
+        assert_eq!(render("[{missing}]", &json!({})), "[]");
+    }
+
+    #[test]
+    fn test_render_number_specs() {
+        let v = json!({"price": 1234567.891, "id": 42, "neg": -7, "ratio": 0.5});
+        assert_eq!(render("{price:.2}", &v), "1234567.89");
+        assert_eq!(render("{price:,.2}", &v), "1,234,567.89");
+        assert_eq!(render("{id:05}", &v), "00042");
+        assert_eq!(render("{neg:04}", &v), "-007");
+        assert_eq!(render("{id:6}|", &v), "    42|");
+        assert_eq!(render("{ratio:.0}", &v), "0");
+    }
+
+    #[test]
+    fn test_render_string_specs() {
+        let v = json!({"name": "Ada", "long": "abcdefgh"});
+        assert_eq!(render("{name:<6}|", &v), "Ada   |");
+        assert_eq!(render("{name:>6}|", &v), "   Ada|");
+        assert_eq!(render("{name:^7}|", &v), "  Ada  |");
+        assert_eq!(render("{long:.3}", &v), "abc");
+    }
+
+    #[test]
+    fn test_render_escapes_and_self() {
+        assert_eq!(render("{{{.}}}", &json!("x")), "{x}");
+        assert_eq!(render("{.}", &json!([1, 2])), "[1,2]");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Template::parse("{name").is_err());
+        assert!(Template::parse("name}").is_err());
+        assert!(Template::parse("{name:xyz}").is_err());
+        assert!(Template::parse("{a||b}").is_err());
+    }
+
+    #[test]
+    fn test_group_thousands() {
+        assert_eq!(group_thousands("1000"), "1,000");
+        assert_eq!(group_thousands("-123456.5"), "-123,456.5");
+        assert_eq!(group_thousands("999"), "999");
+    }
+}
@@ -1,86 +1,448 @@
 use anyhow::{bail, Result};
+use regex::Regex;
 use serde_json::Value;
+use std::cmp::Ordering;
 use std::collections::BTreeMap;
 
-use super::json::eval_filter_expr;
-use super::query::parse_filter_expr;
+use super::alias;
+use super::datetime;
+use super::decode;
+use super::exec;
+use super::json::{eval_filter_expr, traverse};
+use super::lexer::{parse_chain, Args, Command, SyntaxError, Token, TokenKind};
+use super::mutate;
+use super::query::{parse as parse_query, parse_filter_expr};
+use super::registry::{self, ArgSpec, Transform, TransformContext};
+use super::script;
+use super::template::Template;
 
 /// Parse and execute one or more chained transform commands on a JSON value.
 ///
 /// Transform commands start with `:` and operate on the current value.
-/// Multiple commands can be chained: `:pick name,age :sort age`
-/// Supported commands: :keys, :values, :count, :flatten, :pick, :omit, :sort, :uniq, :group_by
+/// Multiple commands can be chained: `:pick name,age :sort age`. Arguments are
+/// lexed by [`super::lexer`], so values may be quoted: `:join ", "`.
+/// Syntax and argument errors are [`super::lexer::SyntaxError`]s with spans.
 pub fn apply_transform(value: &Value, command: &str) -> Result<Value> {
-    let commands = split_chain(command);
+    apply_transform_with(value, command, &Documents::new())
+}
+
+/// Named secondary documents (loaded with `--with name=file`) that queries
+/// and transforms can reference as `$name`.
+pub type Documents = BTreeMap<String, Value>;
+
+/// Like [`apply_transform`], but with secondary documents available to
+/// transforms such as `:lookup $users on user_id=id`.
+pub fn apply_transform_with(value: &Value, command: &str, docs: &Documents) -> Result<Value> {
+    apply_transform_in(
+        value,
+        command,
+        &TransformContext {
+            docs,
+            stream: false,
+            exec: &exec::ExecPolicy::default(),
+        },
+    )
+}
+
+/// Like [`apply_transform`], with an explicit [`TransformContext`].
+pub fn apply_transform_in(value: &Value, command: &str, ctx: &TransformContext) -> Result<Value> {
+    let commands = parse_chain(command)?;
     let mut result = value.clone();
-    for single_cmd in commands {
-        result = apply_single_transform(&result, &single_cmd)?;
+    for single_cmd in &commands {
+        result = apply_single_transform(&result, single_cmd, ctx)?;
     }
     Ok(result)
 }
 
-/// Split a (possibly chained) transform string into individual commands.
-/// e.g. ":pick name,age :sort age" → [":pick name,age", ":sort age"]
-fn split_chain(input: &str) -> Vec<String> {
-    let input = input.trim();
-    let mut commands = Vec::new();
-    let mut current_start = 0;
-
-    // Walk through the string looking for ` :` boundaries (space then colon)
-    // that start a new transform command.
-    let bytes = input.as_bytes();
-    for i in 1..bytes.len() {
-        if bytes[i] == b':' && bytes[i - 1] == b' ' {
-            // Check if this is the very first command (starts at position 0 with `:`)
-            if i > current_start {
-                let chunk = input[current_start..i].trim();
-                if !chunk.is_empty() {
-                    commands.push(chunk.to_string());
-                }
-                current_start = i;
-            }
+/// Resolve a `$name` or `$name.path[0]` reference against the loaded documents.
+pub fn resolve_document(reference: &str, docs: &Documents) -> Result<Value> {
+    let body = match reference.strip_prefix('$') {
+        Some(body) => body,
+        None => bail!("document reference must start with '$': {reference}"),
+    };
+    let name_end = body.find(['.', '[']).unwrap_or(body.len());
+    let (name, path) = body.split_at(name_end);
+    let doc = match docs.get(name) {
+        Some(doc) => doc,
+        None if docs.is_empty() => bail!(
+            "unknown document ${name}.\n\
+             Hint: load secondary documents with --with {name}=file.json"
+        ),
+        None => bail!(
+            "unknown document ${name} (loaded: {})",
+            docs.keys()
+                .map(|k| format!("${k}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+
+    let path = if path.starts_with('.') {
+        path.to_string()
+    } else {
+        format!(".{path}")
+    };
+    let segments =
+        parse_query(&path).map_err(|e| anyhow::anyhow!("invalid path in {reference}: {e}"))?;
+    match traverse(doc, &segments).value {
+        Some(v) => Ok(v),
+        None => bail!("no match for {reference}"),
+    }
+}
+
+/// Apply a single transform command (no chaining), dispatching through the
+/// global [`registry`], or to a user-defined [`alias`] for `:@name`.
+fn apply_single_transform(
+    value: &Value,
+    command: &Command,
+    ctx: &TransformContext,
+) -> Result<Value> {
+    let cmd = command.name.as_str();
+    if cmd.starts_with(":@") {
+        return alias::apply_alias(value, command, ctx);
+    }
+    let Some(transform) = registry::get(cmd) else {
+        return Err(SyntaxError::new(
+            format!("unknown transform command: {cmd}"),
+            command.span.clone(),
+        )
+        .into());
+    };
+    let args = &command.args;
+    if !transform.args().takes_args {
+        args.expect_none()?;
+    }
+    transform.apply(value, args, ctx)
+}
+
+/// A built-in transform: a name, usage, help line and a plain function.
+#[derive(Clone, Copy)]
+pub(crate) struct Builtin {
+    name: &'static str,
+    usage: &'static str,
+    help: &'static str,
+    regex: bool,
+    apply: fn(&Value, &Args, &TransformContext) -> Result<Value>,
+}
+
+impl Builtin {
+    const fn new(
+        name: &'static str,
+        usage: &'static str,
+        help: &'static str,
+        apply: fn(&Value, &Args, &TransformContext) -> Result<Value>,
+    ) -> Self {
+        Self {
+            name,
+            usage,
+            help,
+            regex: false,
+            apply,
         }
     }
-    // Push the last segment
-    let chunk = input[current_start..].trim();
-    if !chunk.is_empty() {
-        commands.push(chunk.to_string());
+
+    const fn regex(mut self) -> Self {
+        self.regex = true;
+        self
     }
-    commands
 }
 
-/// Apply a single transform command (no chaining).
-fn apply_single_transform(value: &Value, command: &str) -> Result<Value> {
-    let command = command.trim();
-    let (cmd, args) = match command.split_once(' ') {
-        Some((c, a)) => (c, a.trim()),
-        None => (command, ""),
-    };
+impl Transform for Builtin {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn args(&self) -> ArgSpec {
+        match (self.usage, self.regex) {
+            ("", _) => ArgSpec::none(),
+            (usage, true) => ArgSpec::usage(usage).with_regex(),
+            (usage, false) => ArgSpec::usage(usage),
+        }
+    }
+
+    fn help(&self) -> &str {
+        self.help
+    }
 
-    match cmd {
-        ":keys" => transform_keys(value),
-        ":values" => transform_values(value),
-        ":count" => transform_count(value),
-        ":flatten" => transform_flatten(value),
-        ":pick" => transform_pick(value, args),
-        ":omit" => transform_omit(value, args),
-        ":sort" => transform_sort(value, args),
-        ":uniq" => transform_uniq(value),
-        ":group_by" => transform_group_by(value, args),
-        ":filter" => transform_filter(value, args),
-        ":sum" => transform_sum(value, args),
-        ":avg" => transform_avg(value, args),
-        ":min" => transform_min(value, args),
-        ":max" => transform_max(value, args),
-        ":reverse" => transform_reverse(value),
-        ":upper" => transform_upper(value),
-        ":lower" => transform_lower(value),
-        ":split" => transform_split(value, args),
-        ":join" => transform_join(value, args),
-        _ => bail!("unknown transform command: {cmd}"),
+    fn apply(&self, value: &Value, args: &Args, ctx: &TransformContext) -> Result<Value> {
+        (self.apply)(value, args, ctx)
     }
 }
 
+/// The built-in transforms, in the order they are listed in help and prompts.
+pub(crate) fn builtins() -> &'static [Builtin] {
+    const BUILTINS: &[Builtin] = &[
+        Builtin::new(":keys", "", "Object keys as an array", |v, _, _| {
+            transform_keys(v)
+        }),
+        Builtin::new(":values", "", "Object values as an array", |v, _, _| {
+            transform_values(v)
+        }),
+        Builtin::new(":count", "", "Number of elements or keys", |v, _, _| {
+            transform_count(v)
+        }),
+        Builtin::new(
+            ":flatten",
+            "",
+            "Flatten nested arrays one level",
+            |v, _, _| transform_flatten(v),
+        ),
+        Builtin::new(
+            ":flatten_keys",
+            "[sep=.]",
+            "Nested objects to dotted keys",
+            |v, a, _| transform_flatten_keys(v, a),
+        ),
+        Builtin::new(
+            ":unflatten_keys",
+            "[sep=.]",
+            "Dotted keys back to nested objects",
+            |v, a, _| transform_unflatten_keys(v, a),
+        ),
+        Builtin::new(
+            ":pick",
+            "f1,f2",
+            "Keep only the listed fields",
+            |v, a, _| transform_pick(v, a),
+        ),
+        Builtin::new(":omit", "f1,f2", "Remove the listed fields", |v, a, _| {
+            transform_omit(v, a)
+        }),
+        Builtin::new(
+            ":sort",
+            "field [asc|desc] [nulls first|last], f2 ...",
+            "Sort an array, by fields or by value",
+            |v, a, _| transform_sort(v, a),
+        ),
+        Builtin::new(":uniq", "", "Remove duplicate elements", |v, _, _| {
+            transform_uniq(v)
+        }),
+        Builtin::new(
+            ":group_by",
+            "field",
+            "Group array elements by a field",
+            |v, a, _| transform_group_by(v, a),
+        ),
+        Builtin::new(
+            ":filter",
+            "field op value",
+            "Keep elements matching a predicate",
+            |v, a, _| transform_filter(v, a),
+        ),
+        Builtin::new(":sum", "[field]", "Sum of numbers", |v, a, _| {
+            transform_sum(v, a)
+        }),
+        Builtin::new(":avg", "[field]", "Average of numbers", |v, a, _| {
+            transform_avg(v, a)
+        }),
+        Builtin::new(":min", "[field]", "Smallest number", |v, a, _| {
+            transform_min(v, a)
+        }),
+        Builtin::new(":max", "[field]", "Largest number", |v, a, _| {
+            transform_max(v, a)
+        }),
+        Builtin::new(":reverse", "", "Reverse an array or string", |v, _, _| {
+            transform_reverse(v)
+        }),
+        Builtin::new(":upper", "", "Uppercase strings", |v, _, _| {
+            transform_upper(v)
+        }),
+        Builtin::new(":lower", "", "Lowercase strings", |v, _, _| {
+            transform_lower(v)
+        }),
+        Builtin::new(
+            ":split",
+            "[delimiter]",
+            "Split a string into an array",
+            |v, a, _| transform_split(v, a),
+        ),
+        Builtin::new(
+            ":join",
+            "[separator]",
+            "Join an array into a string",
+            |v, a, _| transform_join(v, a),
+        ),
+        Builtin::new(
+            ":to_entries",
+            "",
+            "Object to [{key, value}] pairs",
+            |v, _, _| transform_to_entries(v),
+        ),
+        Builtin::new(
+            ":from_entries",
+            "[key=f] [value=f]",
+            "[{key, value}] pairs to an object",
+            |v, a, _| transform_from_entries(v, a),
+        ),
+        Builtin::new(
+            ":zip",
+            "",
+            "Combine parallel arrays element-wise",
+            |v, _, _| transform_zip(v),
+        ),
+        Builtin::new(
+            ":transpose",
+            "",
+            "Swap rows and columns of a 2D array",
+            |v, _, _| transform_transpose(v),
+        ),
+        Builtin::new(
+            ":pivot",
+            "index=f columns=f values=f [agg=sum|avg|min|max|count|first|last]",
+            "Long rows to a wide table",
+            |v, a, _| transform_pivot(v, a),
+        ),
+        Builtin::new(
+            ":unpivot",
+            "keep=f1,f2 [key=name] [value=name]",
+            "Wide rows to long rows",
+            |v, a, _| transform_unpivot(v, a),
+        ),
+        Builtin::new(
+            ":lookup",
+            "$doc on field=doc_field [as name] [left|inner]",
+            "Join with a secondary document",
+            |v, a, ctx| transform_lookup(v, a, ctx.docs),
+        ),
+        Builtin::new(
+            ":set",
+            ".path value",
+            "Set a value in the document (creates missing keys)",
+            |v, a, _| mutate::transform_set(v, a),
+        ),
+        Builtin::new(
+            ":del",
+            ".path [.path...]",
+            "Delete keys or elements from the document",
+            |v, a, _| mutate::transform_del(v, a),
+        ),
+        Builtin::new(
+            ":rename",
+            "old=new [old=new...]",
+            "Rename keys of an object or each object",
+            |v, a, _| mutate::transform_rename(v, a),
+        ),
+        Builtin::new(
+            ":default",
+            "field=value [field=value...]",
+            "Fill in missing or null fields",
+            |v, a, _| mutate::transform_default(v, a),
+        ),
+        Builtin::new(
+            ":parse_json",
+            "[.path]",
+            "Decode stringified JSON",
+            |v, a, _| transform_decode(v, a, ":parse_json", decode::parse_json),
+        ),
+        Builtin::new(
+            ":base64_decode",
+            "[.path]",
+            "Decode base64 text",
+            |v, a, _| {
+                transform_decode(v, a, ":base64_decode", |s| {
+                    decode::base64_decode(s).map(Value::String)
+                })
+            },
+        ),
+        Builtin::new(
+            ":base64_encode",
+            "[.path]",
+            "Encode text as base64",
+            |v, a, _| {
+                transform_decode(v, a, ":base64_encode", |s| {
+                    Ok(Value::String(decode::base64_encode(s)))
+                })
+            },
+        ),
+        Builtin::new(
+            ":url_decode",
+            "[.path]",
+            "Percent-decode a URL component",
+            |v, a, _| {
+                transform_decode(v, a, ":url_decode", |s| {
+                    decode::url_decode(s).map(Value::String)
+                })
+            },
+        ),
+        Builtin::new(
+            ":parse_query_string",
+            "[.path]",
+            "Decode a query string to an object",
+            |v, a, _| transform_decode(v, a, ":parse_query_string", decode::parse_query_string),
+        ),
+        Builtin::new(
+            ":jwt_decode",
+            "[.path]",
+            "Decode a JWT's header and payload",
+            |v, a, _| transform_decode(v, a, ":jwt_decode", decode::jwt_decode),
+        ),
+        Builtin::new(
+            ":format",
+            "'{name} <{email|\"n/a\"}> {orders.length} {total:,.2}'",
+            "Render a string template",
+            |v, a, _| transform_format(v, a),
+        ),
+        Builtin::new(
+            ":replace",
+            "/re/ \"repl $1\" [field]",
+            "Regex search and replace",
+            |v, a, _| transform_replace(v, a),
+        )
+        .regex(),
+        Builtin::new(
+            ":extract",
+            "/(?P<name>re)/ [field]",
+            "Regex capture groups",
+            |v, a, _| transform_extract(v, a),
+        )
+        .regex(),
+        Builtin::new(
+            ":match",
+            "/re/i [field]",
+            "Whether a regex matches",
+            |v, a, _| transform_match(v, a),
+        )
+        .regex(),
+        Builtin::new(
+            ":to_date",
+            "[fmt=...] [tz=+02:00] [field=f]",
+            "Normalize dates to RFC 3339",
+            |v, a, _| transform_to_date(v, a),
+        ),
+        Builtin::new(
+            ":format_date",
+            "\"%Y-%m-%d\" [tz=...] [field=f]",
+            "Format dates with strftime",
+            |v, a, _| transform_format_date(v, a),
+        ),
+        Builtin::new(
+            ":epoch",
+            "[unit=ms] [field=f]",
+            "Dates to epoch seconds or millis",
+            |v, a, _| transform_epoch(v, a),
+        ),
+        Builtin::new(
+            ":bucket_time",
+            "1h [tz=...] [field=f]",
+            "Truncate dates to interval buckets",
+            |v, a, _| transform_bucket_time(v, a),
+        ),
+        Builtin::new(
+            ":exec",
+            "[mode=json|ndjson] [timeout=5s] ./script [args...]",
+            "Pipe through an allow-listed command (JSON in/out)",
+            exec::transform_exec,
+        ),
+        Builtin::new(
+            ":script",
+            "\"<rhai expr using it>\" [each=false] | <saved script name>",
+            "Sandboxed Rhai script per element; () drops it",
+            |v, a, _| script::transform_script(v, a),
+        ),
+    ];
+    BUILTINS
+}
+
 /// Return the keys of an object as an array of strings.
 fn transform_keys(value: &Value) -> Result<Value> {
     match value {
@@ -129,13 +491,158 @@ fn transform_flatten(value: &Value) -> Result<Value> {
     }
 }
 
+/// Flatten nested objects into a single level of dotted keys.
+/// Usage: `:flatten_keys`, `:flatten_keys sep=/`
+fn transform_flatten_keys(value: &Value, args: &Args) -> Result<Value> {
+    let opts = args.options(&["sep"])?;
+    let sep = opts.get("sep").map(String::as_str).unwrap_or(".");
+    match value {
+        Value::Object(_) => Ok(flatten_keys(value, sep)),
+        Value::Array(arr) => Ok(Value::Array(
+            arr.iter().map(|item| flatten_keys(item, sep)).collect(),
+        )),
+        _ => bail!(":flatten_keys requires an object or an array of objects"),
+    }
+}
+
+/// Rebuild nested objects from dotted keys (the inverse of `:flatten_keys`).
+/// Usage: `:unflatten_keys`, `:unflatten_keys sep=/`
+fn transform_unflatten_keys(value: &Value, args: &Args) -> Result<Value> {
+    let opts = args.options(&["sep"])?;
+    let sep = opts.get("sep").map(String::as_str).unwrap_or(".");
+    match value {
+        Value::Object(_) => unflatten_keys(value, sep),
+        Value::Array(arr) => Ok(Value::Array(
+            arr.iter()
+                .map(|item| unflatten_keys(item, sep))
+                .collect::<Result<_>>()?,
+        )),
+        _ => bail!(":unflatten_keys requires an object or an array of objects"),
+    }
+}
+
+/// Flatten an object so every leaf is addressed by its path joined with `sep`.
+/// Array elements use their index as the path segment, e.g. `{"a":{"b":[1]}}`
+/// becomes `{"a.b.0": 1}`. Empty objects and arrays are kept as leaves.
+/// Non-object values are returned unchanged.
+pub fn flatten_keys(value: &Value, sep: &str) -> Value {
+    match value {
+        Value::Object(_) => {
+            let mut out = serde_json::Map::new();
+            flatten_into(value, None, sep, &mut out);
+            Value::Object(out)
+        }
+        other => other.clone(),
+    }
+}
+
+fn flatten_into(
+    value: &Value,
+    prefix: Option<&str>,
+    sep: &str,
+    out: &mut serde_json::Map<String, Value>,
+) {
+    let join = |key: &str| match prefix {
+        Some(p) => format!("{p}{sep}{key}"),
+        None => key.to_string(),
+    };
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (k, v) in map {
+                flatten_into(v, Some(&join(k)), sep, out);
+            }
+        }
+        Value::Array(arr) if !arr.is_empty() && prefix.is_some() => {
+            for (i, v) in arr.iter().enumerate() {
+                flatten_into(v, Some(&join(&i.to_string())), sep, out);
+            }
+        }
+        leaf => {
+            if let Some(p) = prefix {
+                out.insert(p.to_string(), leaf.clone());
+            }
+        }
+    }
+}
+
+/// Rebuild nested structure from keys joined with `sep`. Levels whose keys
+/// are exactly `0..n` become arrays. Non-object values are returned unchanged.
+pub fn unflatten_keys(value: &Value, sep: &str) -> Result<Value> {
+    let map = match value {
+        Value::Object(map) => map,
+        other => return Ok(other.clone()),
+    };
+    let mut root = serde_json::Map::new();
+    for (key, val) in map {
+        let parts: Vec<&str> = key.split(sep).collect();
+        insert_at_path(&mut root, &parts, val.clone()).map_err(|_| {
+            anyhow::anyhow!(":unflatten_keys: key '{key}' conflicts with another key")
+        })?;
+    }
+    Ok(indexed_objects_to_arrays(Value::Object(root)))
+}
+
+/// Insert `val` at the nested path `parts`, creating intermediate objects.
+/// Fails if the path collides with an existing leaf.
+fn insert_at_path(
+    node: &mut serde_json::Map<String, Value>,
+    parts: &[&str],
+    val: Value,
+) -> Result<()> {
+    let (first, rest) = match parts.split_first() {
+        Some(split) => split,
+        None => bail!("empty path"),
+    };
+    if rest.is_empty() {
+        if node.contains_key(*first) {
+            bail!("path collision");
+        }
+        node.insert(first.to_string(), val);
+        return Ok(());
+    }
+    match node
+        .entry(first.to_string())
+        .or_insert_with(|| Value::Object(serde_json::Map::new()))
+    {
+        Value::Object(child) => insert_at_path(child, rest, val),
+        _ => bail!("path collision"),
+    }
+}
+
+/// Convert objects keyed `"0".."n-1"` into arrays, recursively.
+fn indexed_objects_to_arrays(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let is_sequence =
+                !map.is_empty() && (0..map.len()).all(|i| map.contains_key(i.to_string().as_str()));
+            if is_sequence {
+                let mut map = map;
+                let items: Vec<Value> = (0..map.len())
+                    .map(|i| {
+                        let v = map.remove(i.to_string().as_str()).unwrap_or(Value::Null);
+                        indexed_objects_to_arrays(v)
+                    })
+                    .collect();
+                Value::Array(items)
+            } else {
+                Value::Object(
+                    map.into_iter()
+                        .map(|(k, v)| (k, indexed_objects_to_arrays(v)))
+                        .collect(),
+                )
+            }
+        }
+        other => other,
+    }
+}
+
 /// Pick specific fields from objects in an array.
 /// Usage: `:pick name,email`
-fn transform_pick(value: &Value, args: &str) -> Result<Value> {
+fn transform_pick(value: &Value, args: &Args) -> Result<Value> {
     if args.is_empty() {
         bail!(":pick requires field names (e.g., :pick name,email)");
     }
-    let fields: Vec<&str> = args.split(',').map(|s| s.trim()).collect();
+    let fields = args.list()?;
 
     match value {
         Value::Array(arr) => {
@@ -145,7 +652,7 @@ fn transform_pick(value: &Value, args: &str) -> Result<Value> {
                     if let Value::Object(map) = item {
                         let picked: serde_json::Map<String, Value> = map
                             .iter()
-                            .filter(|(k, _)| fields.contains(&k.as_str()))
+                            .filter(|(k, _)| fields.contains(k))
                             .map(|(k, v)| (k.clone(), v.clone()))
                             .collect();
                         Value::Object(picked)
@@ -159,7 +666,7 @@ fn transform_pick(value: &Value, args: &str) -> Result<Value> {
         Value::Object(map) => {
             let picked: serde_json::Map<String, Value> = map
                 .iter()
-                .filter(|(k, _)| fields.contains(&k.as_str()))
+                .filter(|(k, _)| fields.contains(k))
                 .map(|(k, v)| (k.clone(), v.clone()))
                 .collect();
             Ok(Value::Object(picked))
@@ -170,11 +677,11 @@ fn transform_pick(value: &Value, args: &str) -> Result<Value> {
 
 /// Omit specific fields from objects in an array.
 /// Usage: `:omit metadata,internal`
-fn transform_omit(value: &Value, args: &str) -> Result<Value> {
+fn transform_omit(value: &Value, args: &Args) -> Result<Value> {
     if args.is_empty() {
         bail!(":omit requires field names (e.g., :omit metadata,internal)");
     }
-    let fields: Vec<&str> = args.split(',').map(|s| s.trim()).collect();
+    let fields = args.list()?;
 
     match value {
         Value::Array(arr) => {
@@ -184,7 +691,7 @@ fn transform_omit(value: &Value, args: &str) -> Result<Value> {
                     if let Value::Object(map) = item {
                         let omitted: serde_json::Map<String, Value> = map
                             .iter()
-                            .filter(|(k, _)| !fields.contains(&k.as_str()))
+                            .filter(|(k, _)| !fields.contains(k))
                             .map(|(k, v)| (k.clone(), v.clone()))
                             .collect();
                         Value::Object(omitted)
@@ -198,7 +705,7 @@ fn transform_omit(value: &Value, args: &str) -> Result<Value> {
         Value::Object(map) => {
             let omitted: serde_json::Map<String, Value> = map
                 .iter()
-                .filter(|(k, _)| !fields.contains(&k.as_str()))
+                .filter(|(k, _)| !fields.contains(k))
                 .map(|(k, v)| (k.clone(), v.clone()))
                 .collect();
             Ok(Value::Object(omitted))
@@ -207,39 +714,130 @@ fn transform_omit(value: &Value, args: &str) -> Result<Value> {
     }
 }
 
-/// Sort an array of objects by a field, optionally in descending order.
-/// Usage: `:sort name`, `:sort age desc`, `:sort desc`
-fn transform_sort(value: &Value, args: &str) -> Result<Value> {
+/// Where `null` (and missing) sort values are placed, regardless of direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NullsOrder {
+    /// Nulls follow the normal type order: first when ascending, last when descending.
+    Default,
+    First,
+    Last,
+}
+
+/// One key of a (possibly multi-key) `:sort` specification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SortKey {
+    /// Field to sort by; `None` sorts the elements themselves.
+    field: Option<String>,
+    descending: bool,
+    nulls: NullsOrder,
+}
+
+/// Parse the arguments of `:sort` into a list of sort keys.
+/// Keys are comma-separated, each `[field] [asc|desc] [nulls first|last]`;
+/// quote field names that contain spaces.
+fn parse_sort_keys(args: &Args) -> Result<Vec<SortKey>> {
+    const USAGE: &str = "usage: :sort [field] [asc|desc] [nulls first|last], ...";
+
+    let mut keys = Vec::new();
+    for spec in args.tokens().split(|t| t.kind == TokenKind::Comma) {
+        let Some(first) = spec.first() else {
+            if args.is_empty() {
+                break;
+            }
+            return Err(args.error(USAGE, args.span()));
+        };
+
+        let mut key = SortKey {
+            field: None,
+            descending: false,
+            nulls: NullsOrder::Default,
+        };
+        let mut rest = spec;
+        let is_keyword = first.kind == TokenKind::Word
+            && matches!(first.text.as_str(), "asc" | "desc" | "nulls");
+        if !is_keyword {
+            key.field = Some(first.text.clone());
+            rest = &spec[1..];
+        }
+        let words: Vec<&str> = rest.iter().map(|t| t.text.as_str()).collect();
+        let rest_words = match words.as_slice() {
+            [dir @ ("asc" | "desc"), tail @ ..] => {
+                key.descending = *dir == "desc";
+                tail
+            }
+            all => all,
+        };
+        match rest_words {
+            [] => {}
+            ["nulls", "first"] => key.nulls = NullsOrder::First,
+            ["nulls", "last"] => key.nulls = NullsOrder::Last,
+            _ => {
+                let bad = &rest[rest.len() - rest_words.len()];
+                return Err(args.error(
+                    format!("unexpected '{}' ({USAGE})", bad.text),
+                    bad.span.start..rest[rest.len() - 1].span.end,
+                ));
+            }
+        }
+        keys.push(key);
+    }
+
+    if keys.is_empty() {
+        keys.push(SortKey {
+            field: None,
+            descending: false,
+            nulls: NullsOrder::Default,
+        });
+    }
+    Ok(keys)
+}
+
+/// Stable sort of an array by one or more keys.
+/// Usage: `:sort`, `:sort desc`, `:sort age desc`, `:sort region asc, total desc nulls last`
+fn transform_sort(value: &Value, args: &Args) -> Result<Value> {
     match value {
         Value::Array(arr) => {
+            let keys = parse_sort_keys(args)?;
             let mut sorted = arr.clone();
-            let tokens: Vec<&str> = args.split_whitespace().collect();
-            let (field, descending) = match tokens.as_slice() {
-                [] => (None, false),
-                ["desc"] => (None, true),
-                ["asc"] => (None, false),
-                [field] => (Some(*field), false),
-                [field, "desc"] => (Some(*field), true),
-                [field, "asc"] => (Some(*field), false),
-                _ => bail!(":sort usage: :sort [field] [asc|desc]"),
-            };
-            match field {
-                None => sorted.sort_by_key(|a| a.to_string()),
-                Some(f) => sorted.sort_by(|a, b| {
-                    let a_val = a.get(f).unwrap_or(&Value::Null);
-                    let b_val = b.get(f).unwrap_or(&Value::Null);
-                    compare_values(a_val, b_val)
-                }),
-            }
-            if descending {
-                sorted.reverse();
-            }
+            // `sort_by` is stable, so elements that compare equal on every key
+            // keep their original relative order.
+            sorted.sort_by(|a, b| {
+                keys.iter()
+                    .map(|key| compare_by_sort_key(a, b, key))
+                    .find(|ord| ord.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            });
             Ok(Value::Array(sorted))
         }
         _ => bail!(":sort requires an array"),
     }
 }
 
+/// Compare two array elements on a single sort key.
+fn compare_by_sort_key(a: &Value, b: &Value, key: &SortKey) -> Ordering {
+    let (a, b) = match &key.field {
+        Some(f) => (
+            a.get(f.as_str()).unwrap_or(&Value::Null),
+            b.get(f.as_str()).unwrap_or(&Value::Null),
+        ),
+        None => (a, b),
+    };
+
+    match (a.is_null(), b.is_null(), key.nulls) {
+        (true, true, _) => Ordering::Equal,
+        (true, false, NullsOrder::First) | (false, true, NullsOrder::Last) => Ordering::Less,
+        (true, false, NullsOrder::Last) | (false, true, NullsOrder::First) => Ordering::Greater,
+        _ => {
+            let ord = compare_values(a, b);
+            if key.descending {
+                ord.reverse()
+            } else {
+                ord
+            }
+        }
+    }
+}
+
 /// Remove consecutive duplicate values from an array.
 fn transform_uniq(value: &Value) -> Result<Value> {
     match value {
@@ -263,11 +861,10 @@ fn transform_uniq(value: &Value) -> Result<Value> {
 
 /// Group array elements by a field value.
 /// Usage: `:group_by type`
-fn transform_group_by(value: &Value, args: &str) -> Result<Value> {
-    if args.is_empty() {
+fn transform_group_by(value: &Value, args: &Args) -> Result<Value> {
+    let Some(field) = args.optional_value()? else {
         bail!(":group_by requires a field name (e.g., :group_by type)");
-    }
-    let field = args.trim();
+    };
 
     match value {
         Value::Array(arr) => {
@@ -292,13 +889,13 @@ fn transform_group_by(value: &Value, args: &str) -> Result<Value> {
 
 /// Filter array elements by a predicate expression (supports && and ||).
 /// Usage: `:filter price < 10`, `:filter price > 5 && price < 20`
-fn transform_filter(value: &Value, args: &str) -> Result<Value> {
+fn transform_filter(value: &Value, args: &Args) -> Result<Value> {
     if args.is_empty() {
         bail!(":filter requires a predicate (e.g., :filter price < 10)");
     }
 
-    let expr =
-        parse_filter_expr(args).map_err(|e| anyhow::anyhow!(":filter invalid predicate: {e}"))?;
+    let expr = parse_filter_expr(args.raw())
+        .map_err(|e| args.error(format!("invalid predicate: {e}"), args.span()))?;
 
     match value {
         Value::Array(arr) => {
@@ -363,15 +960,15 @@ fn transform_lower(value: &Value) -> Result<Value> {
 }
 
 /// Split a string by a delimiter into an array.
-/// Usage: `:split ,` or `:split -`
-fn transform_split(value: &Value, args: &str) -> Result<Value> {
-    if args.is_empty() {
-        bail!(":split requires a delimiter (e.g., :split ,)");
-    }
+/// Usage: `:split ,`, `:split -` or `:split " "`
+fn transform_split(value: &Value, args: &Args) -> Result<Value> {
+    let Some(delimiter) = args.optional_value()? else {
+        bail!(":split requires a delimiter (e.g., :split , or :split \" \")");
+    };
     match value {
         Value::String(s) => {
             let parts: Vec<Value> = s
-                .split(args)
+                .split(delimiter)
                 .map(|p| Value::String(p.to_string()))
                 .collect();
             Ok(Value::Array(parts))
@@ -381,9 +978,9 @@ fn transform_split(value: &Value, args: &str) -> Result<Value> {
 }
 
 /// Join an array of strings with a separator.
-/// Usage: `:join ,` or `:join -`
-fn transform_join(value: &Value, args: &str) -> Result<Value> {
-    let sep = if args.is_empty() { "," } else { args };
+/// Usage: `:join ,`, `:join -` or `:join ", "`
+fn transform_join(value: &Value, args: &Args) -> Result<Value> {
+    let sep = args.optional_value()?.unwrap_or(",");
     match value {
         Value::Array(arr) => {
             let parts: Vec<String> = arr
@@ -399,9 +996,743 @@ fn transform_join(value: &Value, args: &str) -> Result<Value> {
     }
 }
 
+/// Convert an object into an array of `{key, value}` objects.
+fn transform_to_entries(value: &Value) -> Result<Value> {
+    match value {
+        Value::Object(map) => {
+            let entries: Vec<Value> = map
+                .iter()
+                .map(|(k, v)| {
+                    let mut entry = serde_json::Map::new();
+                    entry.insert("key".into(), Value::String(k.clone()));
+                    entry.insert("value".into(), v.clone());
+                    Value::Object(entry)
+                })
+                .collect();
+            Ok(Value::Array(entries))
+        }
+        _ => bail!(
+            ":to_entries requires an object.\n\
+             Hint: use :from_entries to turn an array of entries back into an object"
+        ),
+    }
+}
+
+/// Build an object from an array of entry objects.
+/// Usage: `:from_entries`, `:from_entries key=name value=count`
+fn transform_from_entries(value: &Value, args: &Args) -> Result<Value> {
+    let opts = args.options(&["key", "value"])?;
+    let key_field = opts.get("key").map(String::as_str).unwrap_or("key");
+    let value_field = opts.get("value").map(String::as_str).unwrap_or("value");
+
+    let arr = match value {
+        Value::Array(arr) => arr,
+        _ => bail!(
+            ":from_entries requires an array of {{{key_field}, {value_field}}} objects.\n\
+             Hint: use :to_entries to turn an object into entries first"
+        ),
+    };
+
+    let mut result = serde_json::Map::new();
+    for (i, item) in arr.iter().enumerate() {
+        let key = match item.get(key_field) {
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Null) | None => bail!(
+                ":from_entries: element {i} has no '{key_field}' field.\n\
+                 Hint: name the fields explicitly, e.g.:\n  \
+                 :from_entries key=name value=count"
+            ),
+            Some(v) => v.to_string(),
+        };
+        let val = item.get(value_field).cloned().unwrap_or(Value::Null);
+        result.insert(key, val);
+    }
+    Ok(Value::Object(result))
+}
+
+/// Combine an array of arrays element-wise into tuples, stopping at the
+/// shortest input. e.g. `[[1,2],["a","b"]]` → `[[1,"a"],[2,"b"]]`
+fn transform_zip(value: &Value) -> Result<Value> {
+    let arrays = as_array_of_arrays(value).ok_or_else(|| {
+        anyhow::anyhow!(
+            ":zip requires an array of arrays, e.g. [[1,2],[\"a\",\"b\"]].\n\
+             Hint: use :values to turn an object of arrays into an array of arrays"
+        )
+    })?;
+    let len = arrays.iter().map(|a| a.len()).min().unwrap_or(0);
+    let zipped: Vec<Value> = (0..len)
+        .map(|i| Value::Array(arrays.iter().map(|a| a[i].clone()).collect()))
+        .collect();
+    Ok(Value::Array(zipped))
+}
+
+/// Swap rows and columns.
+///
+/// - array of arrays → transposed array of arrays (short rows padded with null)
+/// - array of objects → object of column arrays
+/// - object of arrays → array of row objects
+fn transform_transpose(value: &Value) -> Result<Value> {
+    if let Some(rows) = as_array_of_arrays(value) {
+        let width = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+        let columns: Vec<Value> = (0..width)
+            .map(|i| {
+                Value::Array(
+                    rows.iter()
+                        .map(|r| r.get(i).cloned().unwrap_or(Value::Null))
+                        .collect(),
+                )
+            })
+            .collect();
+        return Ok(Value::Array(columns));
+    }
+
+    match value {
+        Value::Array(arr) if arr.iter().all(Value::is_object) => {
+            let headers = collect_object_keys(arr);
+            let columns: serde_json::Map<String, Value> = headers
+                .into_iter()
+                .map(|h| {
+                    let column: Vec<Value> = arr
+                        .iter()
+                        .map(|row| row.get(&h).cloned().unwrap_or(Value::Null))
+                        .collect();
+                    (h, Value::Array(column))
+                })
+                .collect();
+            Ok(Value::Object(columns))
+        }
+        Value::Object(map) if map.values().all(Value::is_array) => {
+            let height = map
+                .values()
+                .filter_map(Value::as_array)
+                .map(Vec::len)
+                .max()
+                .unwrap_or(0);
+            let rows: Vec<Value> = (0..height)
+                .map(|i| {
+                    let row: serde_json::Map<String, Value> = map
+                        .iter()
+                        .map(|(k, col)| (k.clone(), col.get(i).cloned().unwrap_or(Value::Null)))
+                        .collect();
+                    Value::Object(row)
+                })
+                .collect();
+            Ok(Value::Array(rows))
+        }
+        _ => bail!(
+            ":transpose requires an array of arrays, an array of objects, \
+             or an object of arrays"
+        ),
+    }
+}
+
+/// Reshape long-format rows into a wide table: one row per distinct `index`
+/// value, one column per distinct `columns` value. Missing cells are null.
+/// Usage: `:pivot index=date columns=metric values=value agg=sum`
+///
+/// `index` accepts a comma-separated list of fields. `agg` is one of
+/// first (default), last, sum, avg, min, max, count.
+fn transform_pivot(value: &Value, args: &Args) -> Result<Value> {
+    const USAGE: &str = "Hint: :pivot index=<field> columns=<field> values=<field> [agg=sum]";
+    let opts = args.options(&["index", "columns", "values", "agg"])?;
+    let (Some(index), Some(columns), Some(values)) =
+        (opts.get("index"), opts.get("columns"), opts.get("values"))
+    else {
+        bail!(":pivot requires index, columns and values.\n{USAGE}");
+    };
+    let index_fields: Vec<&str> = index.split(',').map(str::trim).collect();
+    let agg = opts.get("agg").map(String::as_str).unwrap_or("first");
+    if !matches!(
+        agg,
+        "first" | "last" | "sum" | "avg" | "min" | "max" | "count"
+    ) {
+        bail!(":pivot: unknown agg '{agg}' (expected first, last, sum, avg, min, max or count)");
+    }
+
+    let arr = match value {
+        Value::Array(arr) => arr,
+        _ => bail!(":pivot requires an array of objects.\n{USAGE}"),
+    };
+
+    // Rows keep first-seen index order; column headers are collected into a
+    // sorted set so the output shape is the same on every run.
+    let mut row_order: Vec<Vec<Value>> = Vec::new();
+    let mut cells: BTreeMap<String, BTreeMap<String, Vec<Value>>> = BTreeMap::new();
+    let mut headers: std::collections::BTreeSet<String> = std::collections::BTreeSet::new();
+
+    for item in arr {
+        if !item.is_object() {
+            bail!(":pivot requires an array of objects.\n{USAGE}");
+        }
+        let index_vals: Vec<Value> = index_fields
+            .iter()
+            .map(|f| item.get(*f).cloned().unwrap_or(Value::Null))
+            .collect();
+        let row_key = serde_json::to_string(&index_vals)?;
+        let header = match item.get(columns.as_str()) {
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Null) | None => "null".to_string(),
+            Some(v) => v.to_string(),
+        };
+        let cell = item.get(values.as_str()).cloned().unwrap_or(Value::Null);
+
+        if index_fields.contains(&header.as_str()) {
+            bail!(
+                ":pivot: column '{header}' from {columns} has the same name as an index field.\n\
+                 Hint: rename the index field first, e.g. `:rename {header}={header}_key` \
+                 and pivot with index={header}_key"
+            );
+        }
+        if !cells.contains_key(&row_key) {
+            row_order.push(index_vals);
+        }
+        headers.insert(header.clone());
+        cells
+            .entry(row_key)
+            .or_default()
+            .entry(header)
+            .or_default()
+            .push(cell);
+    }
+
+    let mut rows = Vec::with_capacity(row_order.len());
+    for index_vals in row_order {
+        let row_key = serde_json::to_string(&index_vals)?;
+        let row_cells = &cells[&row_key];
+        let mut row = serde_json::Map::new();
+        for header in &headers {
+            let cell = row_cells
+                .get(header)
+                .map_or(Value::Null, |vals| aggregate_cell(vals, agg));
+            row.insert(header.clone(), cell);
+        }
+        for (field, val) in index_fields.iter().zip(index_vals) {
+            row.insert(field.to_string(), val);
+        }
+        rows.push(Value::Object(row));
+    }
+    Ok(Value::Array(rows))
+}
+
+/// The index fields of a `:pivot` that ends a transform chain. Objects keep
+/// their keys sorted, so table output uses these to put the index columns
+/// first; a later stage could rename or drop them, so it gets none.
+pub fn pivot_index(chain: &str) -> Option<Vec<String>> {
+    let commands = super::lexer::parse_chain(chain).ok()?;
+    let pivot = commands.last().filter(|c| c.name == ":pivot")?;
+    let opts = pivot
+        .args
+        .options(&["index", "columns", "values", "agg"])
+        .ok()?;
+    Some(
+        opts.get("index")?
+            .split(',')
+            .map(|f| f.trim().to_string())
+            .collect(),
+    )
+}
+
+/// Reduce the values that landed in one pivot cell.
+fn aggregate_cell(vals: &[Value], agg: &str) -> Value {
+    let nums = || vals.iter().filter_map(Value::as_f64);
+    match agg {
+        "last" => vals.last().cloned().unwrap_or(Value::Null),
+        "count" => Value::Number(vals.len().into()),
+        "sum" => number_to_value(nums().sum()),
+        "avg" => {
+            let count = nums().count();
+            if count == 0 {
+                Value::Null
+            } else {
+                number_to_value(nums().sum::<f64>() / count as f64)
+            }
+        }
+        "min" => nums().reduce(f64::min).map_or(Value::Null, number_to_value),
+        "max" => nums().reduce(f64::max).map_or(Value::Null, number_to_value),
+        _ => vals.first().cloned().unwrap_or(Value::Null),
+    }
+}
+
+/// Reshape a wide table into long format: every field not listed in `keep`
+/// becomes its own `{...keep, key, value}` row.
+/// Usage: `:unpivot keep=id,date`, `:unpivot keep=id key=metric value=amount`
+fn transform_unpivot(value: &Value, args: &Args) -> Result<Value> {
+    let opts = args.options(&["keep", "key", "value"])?;
+    let keep: Vec<&str> = opts
+        .get("keep")
+        .map(|k| k.split(',').map(str::trim).collect())
+        .unwrap_or_default();
+    let key_name = opts.get("key").map(String::as_str).unwrap_or("key");
+    let value_name = opts.get("value").map(String::as_str).unwrap_or("value");
+
+    let arr = match value {
+        Value::Array(arr) => arr,
+        _ => bail!(
+            ":unpivot requires an array of objects.\n\
+             Hint: :unpivot keep=<field>,<field> [key=<name>] [value=<name>]"
+        ),
+    };
+
+    let mut rows = Vec::new();
+    for item in arr {
+        let map = match item {
+            Value::Object(map) => map,
+            _ => bail!(":unpivot requires an array of objects"),
+        };
+        let kept: serde_json::Map<String, Value> = keep
+            .iter()
+            .map(|k| (k.to_string(), map.get(*k).cloned().unwrap_or(Value::Null)))
+            .collect();
+        for (k, v) in map {
+            if keep.contains(&k.as_str()) {
+                continue;
+            }
+            let mut row = kept.clone();
+            row.insert(key_name.to_string(), Value::String(k.clone()));
+            row.insert(value_name.to_string(), v.clone());
+            rows.push(Value::Object(row));
+        }
+    }
+    Ok(Value::Array(rows))
+}
+
+/// Join each element with matching rows of a secondary document.
+/// Usage: `:lookup $users on user_id=id [as user] [left|inner]`
+///
+/// Builds a hash index over the right-hand side, so each side is scanned
+/// once. Every match produces one output row with the matched object under
+/// the `as` name (default: the document name). With `left` (the default),
+/// rows without a match are kept with `null`; with `inner` they are dropped.
+fn transform_lookup(value: &Value, args: &Args, docs: &Documents) -> Result<Value> {
+    const USAGE: &str = "Hint: :lookup $<doc> on <field>=<field> [as <name>] [left|inner]";
+
+    let tokens: Vec<&str> = args.tokens().iter().map(|t| t.text.as_str()).collect();
+    let (reference, on, rest) = match tokens.as_slice() {
+        [reference, "on", on, rest @ ..] if reference.starts_with('$') => (*reference, *on, rest),
+        _ => bail!(":lookup requires a document and join keys.\n{USAGE}"),
+    };
+    let (left_field, right_field) = on
+        .split_once('=')
+        .filter(|(l, r)| !l.is_empty() && !r.is_empty())
+        .ok_or_else(|| anyhow::anyhow!(":lookup: invalid join keys '{on}'.\n{USAGE}"))?;
+
+    let default_name = reference
+        .trim_start_matches('$')
+        .split(['.', '['])
+        .next()
+        .unwrap_or_default();
+    let mut target = default_name;
+    let mut inner = false;
+    let mut rest = rest;
+    while !rest.is_empty() {
+        match rest {
+            ["as", name, tail @ ..] => {
+                target = name;
+                rest = tail;
+            }
+            ["inner", tail @ ..] => {
+                inner = true;
+                rest = tail;
+            }
+            ["left", tail @ ..] => {
+                inner = false;
+                rest = tail;
+            }
+            _ => bail!(":lookup: unexpected argument '{}'.\n{USAGE}", rest[0]),
+        }
+    }
+
+    let right = resolve_document(reference, docs)?;
+    let (left_rows, right_rows) = match (value, &right) {
+        (Value::Array(l), Value::Array(r)) => (l, r),
+        (Value::Array(_), _) => bail!(":lookup: {reference} must be an array of objects"),
+        _ => bail!(":lookup requires an array of objects"),
+    };
+
+    let mut index: std::collections::HashMap<String, Vec<&Value>> =
+        std::collections::HashMap::new();
+    for row in right_rows {
+        if let Some(key) = row.get(right_field).and_then(join_key) {
+            index.entry(key).or_default().push(row);
+        }
+    }
+
+    let mut result = Vec::with_capacity(left_rows.len());
+    for row in left_rows {
+        let Value::Object(map) = row else {
+            bail!(":lookup requires an array of objects");
+        };
+        let matches = row
+            .get(left_field)
+            .and_then(join_key)
+            .and_then(|k| index.get(&k));
+        match matches {
+            Some(matched) => {
+                for m in matched {
+                    let mut joined = map.clone();
+                    joined.insert(target.to_string(), (*m).clone());
+                    result.push(Value::Object(joined));
+                }
+            }
+            None if !inner => {
+                let mut joined = map.clone();
+                joined.insert(target.to_string(), Value::Null);
+                result.push(Value::Object(joined));
+            }
+            None => {}
+        }
+    }
+    Ok(Value::Array(result))
+}
+
+/// Normalize a join key so that `1` and `"1"` match. Nulls never match.
+fn join_key(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Apply a string decoder to a string, or to every string in an array.
+/// An optional path argument navigates into the decoded result,
+/// e.g. `:parse_json .user.name` or `:jwt_decode .claims.sub`.
+fn transform_decode(
+    value: &Value,
+    args: &Args,
+    cmd_name: &str,
+    decoder: impl Fn(&str) -> Result<Value>,
+) -> Result<Value> {
+    let path = args.optional_value()?.unwrap_or_default();
+    let segments = if path.is_empty() {
+        Vec::new()
+    } else if path.starts_with('.') {
+        parse_query(path).map_err(|e| anyhow::anyhow!("{cmd_name}: invalid path '{path}': {e}"))?
+    } else {
+        bail!("{cmd_name}: expected a path starting with '.', got '{path}'");
+    };
+    let decode_one = |s: &str| -> Result<Value> {
+        let decoded = decoder(s).map_err(|e| anyhow::anyhow!("{cmd_name}: {e:#}"))?;
+        Ok(traverse(&decoded, &segments).value.unwrap_or(Value::Null))
+    };
+
+    match value {
+        Value::String(s) => decode_one(s),
+        Value::Array(arr) => {
+            let decoded: Result<Vec<Value>> = arr
+                .iter()
+                .map(|item| match item {
+                    Value::String(s) => decode_one(s),
+                    other => Ok(other.clone()),
+                })
+                .collect();
+            Ok(Value::Array(decoded?))
+        }
+        _ => bail!("{cmd_name} requires a string or array of strings"),
+    }
+}
+
+/// Render a string template per element (or for a single value).
+/// Usage: `:format "{name} <{email}> — {orders.length} orders"`
+fn transform_format(value: &Value, args: &Args) -> Result<Value> {
+    let Some(template) = args.optional_value()? else {
+        bail!(":format requires a template, e.g. :format \"{{name}}: {{total:.2}}\"");
+    };
+    let template = Template::parse(template).map_err(|e| args.error(e, args.span()))?;
+    match value {
+        Value::Array(arr) => Ok(Value::Array(
+            arr.iter()
+                .map(|item| Value::String(template.render(item)))
+                .collect(),
+        )),
+        other => Ok(Value::String(template.render(other))),
+    }
+}
+
+/// Replace every regex match, with `$1` / `${name}` capture references.
+/// Usage: `:replace /pattern/ "replacement" [field]`
+fn transform_replace(value: &Value, args: &Args) -> Result<Value> {
+    const USAGE: &str = "usage: :replace /pattern/ \"replacement\" [field]";
+    let (re, rest) = regex_arg(args, USAGE)?;
+    let Some(replacement) = rest.first() else {
+        return Err(args.error(format!("missing replacement string ({USAGE})"), args.span()));
+    };
+    let field = regex_field_arg(args, &rest[1..], USAGE)?;
+    map_items(value, field, ":replace", |v| {
+        Ok(match v {
+            Value::String(s) => {
+                Value::String(re.replace_all(s, replacement.text.as_str()).into_owned())
+            }
+            other => other.clone(),
+        })
+    })
+}
+
+/// Extract capture groups from the first match: named groups become an
+/// object, otherwise the first group (or the whole match) is returned.
+/// Non-matching strings give `null`.
+/// Usage: `:extract /(?P<code>\d{3})/ [field]`
+fn transform_extract(value: &Value, args: &Args) -> Result<Value> {
+    const USAGE: &str = "usage: :extract /(?P<name>pattern)/ [field]";
+    let (re, rest) = regex_arg(args, USAGE)?;
+    let field = regex_field_arg(args, rest, USAGE)?;
+    let names: Vec<&str> = re.capture_names().flatten().collect();
+    map_items(value, field, ":extract", |v| {
+        let Some(caps) = v.as_str().and_then(|s| re.captures(s)) else {
+            return Ok(Value::Null);
+        };
+        let text = |m: Option<regex::Match>| {
+            m.map_or(Value::Null, |m| Value::String(m.as_str().to_string()))
+        };
+        if names.is_empty() {
+            Ok(text(caps.get(1).or_else(|| caps.get(0))))
+        } else {
+            Ok(Value::Object(
+                names
+                    .iter()
+                    .map(|name| (name.to_string(), text(caps.name(name))))
+                    .collect(),
+            ))
+        }
+    })
+}
+
+/// Test strings against a regex, returning booleans (non-strings are `false`).
+/// Usage: `:match /pattern/ [field]`
+fn transform_match(value: &Value, args: &Args) -> Result<Value> {
+    const USAGE: &str = "usage: :match /pattern/ [field]";
+    let (re, rest) = regex_arg(args, USAGE)?;
+    let field = regex_field_arg(args, rest, USAGE)?;
+    map_items(value, field, ":match", |v| {
+        Ok(Value::Bool(v.as_str().is_some_and(|s| re.is_match(s))))
+    })
+}
+
+/// Compile the leading `/pattern/flags` argument, returning the regex and
+/// the remaining tokens. Flags are any of `i`, `m`, `s`, `x`.
+fn regex_arg<'a>(args: &'a Args, usage: &str) -> Result<(Regex, &'a [Token])> {
+    let Some((first, rest)) = args.tokens().split_first() else {
+        return Err(args.error(
+            format!("expected a /regex/ argument ({usage})"),
+            args.span(),
+        ));
+    };
+    let TokenKind::Regex { flags } = &first.kind else {
+        return Err(args.error(
+            format!("expected a /regex/ argument, got '{}'", first.text),
+            first.span.clone(),
+        ));
+    };
+
+    let mut builder = regex::RegexBuilder::new(&first.text);
+    for flag in flags.chars() {
+        match flag {
+            'i' => builder.case_insensitive(true),
+            'm' => builder.multi_line(true),
+            's' => builder.dot_matches_new_line(true),
+            'x' => builder.ignore_whitespace(true),
+            _ => {
+                return Err(args.error(
+                    format!("unknown regex flag '{flag}' (expected i, m, s or x)"),
+                    first.span.clone(),
+                ))
+            }
+        };
+    }
+    let re = builder.build().map_err(|e| {
+        args.error(
+            format!("invalid regex /{}/: {e}", first.text),
+            first.span.clone(),
+        )
+    })?;
+    Ok((re, rest))
+}
+
+/// Read the optional trailing field name of a regex transform.
+fn regex_field_arg<'a>(args: &Args, rest: &'a [Token], usage: &str) -> Result<Option<&'a str>> {
+    match rest {
+        [] => Ok(None),
+        [field] => Ok(Some(&field.text)),
+        [_, extra, ..] => Err(args.error(
+            format!("unexpected argument '{}' ({usage})", extra.text),
+            extra.span.clone(),
+        )),
+    }
+}
+
+/// Normalize timestamps to RFC 3339.
+/// Usage: `:to_date [fmt=%d/%m/%Y] [tz=+02:00] [field=name]`
+fn transform_to_date(value: &Value, args: &Args) -> Result<Value> {
+    let (_, opts) = parse_date_args(args, false, &["fmt", "tz", "field"])?;
+    let tz = opts
+        .get("tz")
+        .map(|t| datetime::parse_offset(t))
+        .transpose()?;
+    map_dates(value, &opts, ":to_date", |dt| {
+        let dt = tz.map_or(dt, |tz| dt.with_timezone(&tz));
+        Ok(Value::String(datetime::to_rfc3339(&dt)))
+    })
+}
+
+/// Format timestamps with a strftime-style pattern.
+/// Usage: `:format_date "%Y-%m-%d" [tz=+02:00] [field=name]`
+fn transform_format_date(value: &Value, args: &Args) -> Result<Value> {
+    let (fmt, opts) = parse_date_args(args, true, &["tz", "field"])?;
+    let Some(fmt) = fmt else {
+        bail!(":format_date requires a format, e.g. :format_date \"%Y-%m-%d\"");
+    };
+    let items = chrono::format::StrftimeItems::new(&fmt)
+        .parse()
+        .map_err(|_| anyhow::anyhow!(":format_date: invalid format '{fmt}'"))?;
+    let tz = opts
+        .get("tz")
+        .map(|t| datetime::parse_offset(t))
+        .transpose()?;
+    map_dates(value, &opts, ":format_date", |dt| {
+        let dt = tz.map_or(dt, |tz| dt.with_timezone(&tz));
+        Ok(Value::String(
+            dt.format_with_items(items.iter()).to_string(),
+        ))
+    })
+}
+
+/// Convert timestamps to Unix epoch seconds (or milliseconds with `unit=ms`).
+/// Usage: `:epoch [unit=ms] [field=name]`
+fn transform_epoch(value: &Value, args: &Args) -> Result<Value> {
+    let (_, opts) = parse_date_args(args, false, &["unit", "fmt", "field"])?;
+    let millis = match opts.get("unit").map(String::as_str) {
+        None | Some("s") => false,
+        Some("ms") => true,
+        Some(other) => bail!(":epoch: unknown unit '{other}' (expected s or ms)"),
+    };
+    map_dates(value, &opts, ":epoch", |dt| {
+        let n = if millis {
+            dt.timestamp_millis()
+        } else {
+            dt.timestamp()
+        };
+        Ok(Value::Number(n.into()))
+    })
+}
+
+/// Truncate timestamps to the start of fixed-size buckets (e.g. `15m`, `1h`,
+/// `1d`). Buckets align to the epoch in `tz` (UTC by default).
+/// Usage: `:bucket_time 1h [tz=+02:00] [field=name]`
+fn transform_bucket_time(value: &Value, args: &Args) -> Result<Value> {
+    let (size, opts) = parse_date_args(args, true, &["tz", "fmt", "field"])?;
+    let Some(size) = size else {
+        bail!(":bucket_time requires a bucket size, e.g. :bucket_time 1h");
+    };
+    let secs = datetime::parse_duration(&size).map_err(|e| anyhow::anyhow!(":bucket_time: {e}"))?;
+    let tz = match opts.get("tz") {
+        Some(t) => datetime::parse_offset(t)?,
+        None => datetime::parse_offset("UTC")?,
+    };
+    map_dates(value, &opts, ":bucket_time", |dt| {
+        let start = datetime::bucket_start(&dt.with_timezone(&tz), secs);
+        Ok(Value::String(datetime::to_rfc3339(&start)))
+    })
+}
+
+/// Split date transform arguments into an optional positional argument
+/// (a format may be quoted to include spaces) and `name=value` options.
+fn parse_date_args(
+    args: &Args,
+    positional: bool,
+    allowed: &[&str],
+) -> Result<(Option<String>, BTreeMap<String, String>)> {
+    let (tokens, opts) = args.positional_and_options(allowed)?;
+    let max = usize::from(positional);
+    if let Some(extra) = tokens.get(max) {
+        return Err(args.error(
+            format!(
+                "unexpected argument '{}' (expected {})",
+                extra.text,
+                allowed
+                    .iter()
+                    .map(|a| format!("{a}=..."))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+            extra.span.clone(),
+        ));
+    }
+    Ok((tokens.first().map(|t| t.text.clone()), opts))
+}
+
+/// Apply `f` to every timestamp (see [`map_items`]); nulls pass through.
+fn map_dates(
+    value: &Value,
+    opts: &BTreeMap<String, String>,
+    cmd_name: &str,
+    f: impl Fn(chrono::DateTime<chrono::FixedOffset>) -> Result<Value>,
+) -> Result<Value> {
+    let fmt = opts.get("fmt").map(String::as_str);
+    let convert = |v: &Value| -> Result<Value> {
+        if v.is_null() {
+            return Ok(Value::Null);
+        }
+        match datetime::value_to_datetime(v, fmt) {
+            Some(dt) => f(dt),
+            None => bail!(
+                "{cmd_name}: cannot parse {v} as a date.\n\
+                 Hint: expected RFC 3339, YYYY-MM-DD or epoch seconds; use fmt=... for other layouts"
+            ),
+        }
+    };
+
+    if !matches!(
+        value,
+        Value::String(_) | Value::Number(_) | Value::Null | Value::Array(_)
+    ) {
+        bail!("{cmd_name} requires a date string, epoch number, or array of them");
+    }
+    map_items(
+        value,
+        opts.get("field").map(String::as_str),
+        cmd_name,
+        convert,
+    )
+}
+
+/// Apply `f` to a scalar, to each element of an array, or — when `field` is
+/// given — to that field of each object in an array (other elements and
+/// objects without the field are left as they are).
+fn map_items(
+    value: &Value,
+    field: Option<&str>,
+    cmd_name: &str,
+    f: impl Fn(&Value) -> Result<Value>,
+) -> Result<Value> {
+    match (value, field) {
+        (Value::Array(arr), Some(field)) => {
+            let mapped: Result<Vec<Value>> = arr
+                .iter()
+                .map(|item| match item {
+                    Value::Object(map) => {
+                        let mut map = map.clone();
+                        if let Some(v) = map.get(field) {
+                            let converted = f(v)?;
+                            map.insert(field.to_string(), converted);
+                        }
+                        Ok(Value::Object(map))
+                    }
+                    other => Ok(other.clone()),
+                })
+                .collect();
+            Ok(Value::Array(mapped?))
+        }
+        (_, Some(field)) => bail!("{cmd_name}: field '{field}' requires an array of objects"),
+        (Value::Array(arr), None) => Ok(Value::Array(
+            arr.iter().map(&f).collect::<Result<Vec<_>>>()?,
+        )),
+        _ => f(value),
+    }
+}
+
 /// Sum numeric values in an array, or sum a specific field from objects.
 /// Usage: `:sum` or `:sum price`
-fn transform_sum(value: &Value, args: &str) -> Result<Value> {
+fn transform_sum(value: &Value, args: &Args) -> Result<Value> {
     let nums = extract_numbers(value, args, ":sum")?;
     let total: f64 = nums.iter().sum();
     Ok(number_to_value(total))
@@ -409,7 +1740,7 @@ fn transform_sum(value: &Value, args: &str) -> Result<Value> {
 
 /// Average numeric values in an array, or average a specific field from objects.
 /// Usage: `:avg` or `:avg price`
-fn transform_avg(value: &Value, args: &str) -> Result<Value> {
+fn transform_avg(value: &Value, args: &Args) -> Result<Value> {
     let nums = extract_numbers(value, args, ":avg")?;
     if nums.is_empty() {
         return Ok(Value::Null);
@@ -421,7 +1752,7 @@ fn transform_avg(value: &Value, args: &str) -> Result<Value> {
 
 /// Minimum value in an array, or minimum of a specific field from objects.
 /// Usage: `:min` or `:min price`
-fn transform_min(value: &Value, args: &str) -> Result<Value> {
+fn transform_min(value: &Value, args: &Args) -> Result<Value> {
     let nums = extract_numbers(value, args, ":min")?;
     match nums.iter().copied().reduce(f64::min) {
         Some(v) => Ok(number_to_value(v)),
@@ -431,7 +1762,7 @@ fn transform_min(value: &Value, args: &str) -> Result<Value> {
 
 /// Maximum value in an array, or maximum of a specific field from objects.
 /// Usage: `:max` or `:max price`
-fn transform_max(value: &Value, args: &str) -> Result<Value> {
+fn transform_max(value: &Value, args: &Args) -> Result<Value> {
     let nums = extract_numbers(value, args, ":max")?;
     match nums.iter().copied().reduce(f64::max) {
         Some(v) => Ok(number_to_value(v)),
@@ -440,8 +1771,8 @@ fn transform_max(value: &Value, args: &str) -> Result<Value> {
 }
 
 /// Extract numeric values from an array. If `field` is given, extract from objects.
-fn extract_numbers(value: &Value, args: &str, cmd_name: &str) -> Result<Vec<f64>> {
-    let field = args.trim();
+fn extract_numbers(value: &Value, args: &Args, cmd_name: &str) -> Result<Vec<f64>> {
+    let field = args.optional_value()?.unwrap_or_default();
     match value {
         Value::Array(arr) => {
             let mut nums = Vec::new();
@@ -461,30 +1792,124 @@ fn extract_numbers(value: &Value, args: &str, cmd_name: &str) -> Result<Vec<f64>
     }
 }
 
-/// Convert an f64 to a JSON Value, using integer representation when possible.
-fn number_to_value(n: f64) -> Value {
-    if n.fract() == 0.0 && n.abs() < (i64::MAX as f64) {
-        Value::Number(serde_json::Number::from(n as i64))
-    } else {
-        serde_json::Number::from_f64(n)
+/// Borrow a value as a list of arrays, if it is a non-empty array whose
+/// elements are all arrays.
+fn as_array_of_arrays(value: &Value) -> Option<Vec<&Vec<Value>>> {
+    match value {
+        Value::Array(arr) if !arr.is_empty() => arr.iter().map(Value::as_array).collect(),
+        _ => None,
+    }
+}
+
+/// Collect the union of keys across an array of objects, in first-seen order.
+fn collect_object_keys(arr: &[Value]) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::new();
+    for item in arr {
+        if let Value::Object(map) = item {
+            for key in map.keys() {
+                if !keys.contains(key) {
+                    keys.push(key.clone());
+                }
+            }
+        }
+    }
+    keys
+}
+
+/// Convert an f64 to a JSON Value, using integer representation when possible.
+fn number_to_value(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < (i64::MAX as f64) {
+        Value::Number(serde_json::Number::from(n as i64))
+    } else {
+        serde_json::Number::from_f64(n)
             .map(Value::Number)
             .unwrap_or(Value::Null)
     }
 }
 
 /// Compare two JSON values for sorting.
-fn compare_values(a: &Value, b: &Value) -> std::cmp::Ordering {
+///
+/// Values of different types are ordered null < bool < number < string <
+/// array < object. Strings use natural ordering, so `v1.9` sorts before `v1.10`.
+fn compare_values(a: &Value, b: &Value) -> Ordering {
     match (a, b) {
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
         (Value::Number(a), Value::Number(b)) => {
             let a = a.as_f64().unwrap_or(0.0);
             let b = b.as_f64().unwrap_or(0.0);
-            a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
         }
-        (Value::String(a), Value::String(b)) => a.cmp(b),
-        _ => a.to_string().cmp(&b.to_string()),
+        (Value::String(a), Value::String(b)) => natural_cmp(a, b),
+        (Value::Array(a), Value::Array(b)) => a
+            .iter()
+            .zip(b.iter())
+            .map(|(x, y)| compare_values(x, y))
+            .find(|ord| ord.is_ne())
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        (Value::Object(_), Value::Object(_)) => a.to_string().cmp(&b.to_string()),
+        _ => type_rank(a).cmp(&type_rank(b)),
     }
 }
 
+/// Rank of a value's type in the cross-type sort order.
+fn type_rank(value: &Value) -> u8 {
+    match value {
+        Value::Null => 0,
+        Value::Bool(_) => 1,
+        Value::Number(_) => 2,
+        Value::String(_) => 3,
+        Value::Array(_) => 4,
+        Value::Object(_) => 5,
+    }
+}
+
+/// Compare two strings treating runs of ASCII digits as numbers.
+/// Falls back to plain byte order so the result is a total order.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let a_num = take_digits(&mut a_chars);
+                let b_num = take_digits(&mut b_chars);
+                let a_trimmed = a_num.trim_start_matches('0');
+                let b_trimmed = b_num.trim_start_matches('0');
+                let ord = a_trimmed
+                    .len()
+                    .cmp(&b_trimmed.len())
+                    .then_with(|| a_trimmed.cmp(b_trimmed));
+                if ord.is_ne() {
+                    return ord;
+                }
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.peek().copied() {
+        if !c.is_ascii_digit() {
+            break;
+        }
+        digits.push(c);
+        chars.next();
+    }
+    digits
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -527,6 +1952,41 @@ mod tests {
         assert_eq!(result, json!([1, 2, 3, 4, 5]));
     }
 
+    #[test]
+    fn test_flatten_keys() {
+        let data = json!({"a": {"b": 1, "c": [10, {"d": 2}]}, "e": {}, "f": "x"});
+        let result = apply_transform(&data, ":flatten_keys").unwrap();
+        assert_eq!(
+            result,
+            json!({"a.b": 1, "a.c.0": 10, "a.c.1.d": 2, "e": {}, "f": "x"})
+        );
+    }
+
+    #[test]
+    fn test_flatten_keys_custom_sep_on_array() {
+        let data = json!([{"a": {"b": 1}}, {"a": {"b": 2}}]);
+        let result = apply_transform(&data, ":flatten_keys sep=/").unwrap();
+        assert_eq!(result, json!([{"a/b": 1}, {"a/b": 2}]));
+    }
+
+    #[test]
+    fn test_unflatten_keys_round_trip() {
+        let data = json!({"a": {"b": 1, "c": [10, {"d": 2}]}, "e": {}, "f": "x"});
+        let result = apply_transform(&data, ":flatten_keys :unflatten_keys").unwrap();
+        assert_eq!(result, data);
+    }
+
+    #[test]
+    fn test_unflatten_keys_conflict() {
+        let data = json!({"a": 1, "a.b": 2});
+        assert!(apply_transform(&data, ":unflatten_keys").is_err());
+    }
+
+    #[test]
+    fn test_flatten_keys_on_scalar() {
+        assert!(apply_transform(&json!(1), ":flatten_keys").is_err());
+    }
+
     #[test]
     fn test_pick() {
         let data = json!([
@@ -602,6 +2062,91 @@ mod tests {
         assert_eq!(arr[2]["name"], "Charlie");
     }
 
+    #[test]
+    fn test_sort_multi_key() {
+        let data = json!([
+            {"region": "us", "total": 10},
+            {"region": "eu", "total": 5},
+            {"region": "us", "total": 30},
+            {"region": "eu", "total": 20}
+        ]);
+        let result = apply_transform(&data, ":sort region asc, total desc").unwrap();
+        assert_eq!(
+            result,
+            json!([
+                {"region": "eu", "total": 20},
+                {"region": "eu", "total": 5},
+                {"region": "us", "total": 30},
+                {"region": "us", "total": 10}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_sort_natural_strings() {
+        let data = json!(["v1.10", "v1.9", "v1.2", "v10.0"]);
+        let result = apply_transform(&data, ":sort").unwrap();
+        assert_eq!(result, json!(["v1.2", "v1.9", "v1.10", "v10.0"]));
+    }
+
+    #[test]
+    fn test_sort_numbers_not_lexicographic() {
+        let data = json!([10, 9, 100, 1]);
+        let result = apply_transform(&data, ":sort").unwrap();
+        assert_eq!(result, json!([1, 9, 10, 100]));
+    }
+
+    #[test]
+    fn test_sort_mixed_types() {
+        let data = json!(["b", 2, null, true, "a", 1]);
+        let result = apply_transform(&data, ":sort").unwrap();
+        assert_eq!(result, json!([null, true, 1, 2, "a", "b"]));
+    }
+
+    #[test]
+    fn test_sort_nulls_last() {
+        let data = json!([
+            {"name": "A", "score": null},
+            {"name": "B", "score": 2},
+            {"name": "C"},
+            {"name": "D", "score": 1}
+        ]);
+        let result = apply_transform(&data, ":sort score nulls last :pick name").unwrap();
+        assert_eq!(
+            result,
+            json!([{"name": "D"}, {"name": "B"}, {"name": "A"}, {"name": "C"}])
+        );
+    }
+
+    #[test]
+    fn test_sort_desc_nulls_first() {
+        let data = json!([{"v": 1}, {"v": null}, {"v": 3}]);
+        let result = apply_transform(&data, ":sort v desc nulls first").unwrap();
+        assert_eq!(result, json!([{"v": null}, {"v": 3}, {"v": 1}]));
+    }
+
+    #[test]
+    fn test_sort_desc_is_stable() {
+        let data = json!([
+            {"k": 1, "id": "a"},
+            {"k": 2, "id": "b"},
+            {"k": 1, "id": "c"},
+            {"k": 2, "id": "d"}
+        ]);
+        let result = apply_transform(&data, ":sort k desc :pick id").unwrap();
+        assert_eq!(
+            result,
+            json!([{"id": "b"}, {"id": "d"}, {"id": "a"}, {"id": "c"}])
+        );
+    }
+
+    #[test]
+    fn test_sort_invalid_spec() {
+        let data = json!([1, 2]);
+        assert!(apply_transform(&data, ":sort a desc nulls").is_err());
+        assert!(apply_transform(&data, ":sort a,,b").is_err());
+    }
+
     #[test]
     fn test_uniq() {
         let data = json!([1, 2, 2, 3, 1, 3]);
@@ -754,6 +2299,587 @@ mod tests {
         assert_eq!(result, json!(3));
     }
 
+    // --- :to_entries, :from_entries, :zip, :transpose tests ---
+
+    #[test]
+    fn test_to_entries() {
+        let data = json!({"us": 3, "de": 5});
+        let result = apply_transform(&data, ":to_entries").unwrap();
+        assert_eq!(
+            result,
+            json!([{"key": "de", "value": 5}, {"key": "us", "value": 3}])
+        );
+    }
+
+    #[test]
+    fn test_to_entries_round_trip_through_sort() {
+        let data = json!({"us": 3, "de": 5, "fr": 1});
+        let result = apply_transform(&data, ":to_entries :sort value desc :from_entries").unwrap();
+        assert_eq!(result, data);
+    }
+
+    #[test]
+    fn test_from_entries_named_fields() {
+        let data = json!([{"name": "a", "count": 1}, {"name": "b", "count": 2}]);
+        let result = apply_transform(&data, ":from_entries key=name value=count").unwrap();
+        assert_eq!(result, json!({"a": 1, "b": 2}));
+    }
+
+    #[test]
+    fn test_from_entries_missing_key() {
+        let data = json!([{"name": "a"}]);
+        let err = apply_transform(&data, ":from_entries").unwrap_err();
+        assert!(err.to_string().contains("key=name"));
+    }
+
+    #[test]
+    fn test_from_entries_bad_argument() {
+        let data = json!([]);
+        assert!(apply_transform(&data, ":from_entries k=name").is_err());
+    }
+
+    #[test]
+    fn test_to_entries_on_array() {
+        let err = apply_transform(&json!([1]), ":to_entries").unwrap_err();
+        assert!(err.to_string().contains("requires an object"));
+    }
+
+    #[test]
+    fn test_zip() {
+        let data = json!([[1, 2, 3], ["a", "b"]]);
+        let result = apply_transform(&data, ":zip").unwrap();
+        assert_eq!(result, json!([[1, "a"], [2, "b"]]));
+    }
+
+    #[test]
+    fn test_zip_requires_arrays() {
+        assert!(apply_transform(&json!([1, 2]), ":zip").is_err());
+    }
+
+    #[test]
+    fn test_transpose_arrays() {
+        let data = json!([[1, 2, 3], [4, 5]]);
+        let result = apply_transform(&data, ":transpose").unwrap();
+        assert_eq!(result, json!([[1, 4], [2, 5], [3, null]]));
+    }
+
+    #[test]
+    fn test_transpose_rows_to_columns_and_back() {
+        let rows = json!([{"a": 1, "b": 2}, {"a": 3}]);
+        let columns = apply_transform(&rows, ":transpose").unwrap();
+        assert_eq!(columns, json!({"a": [1, 3], "b": [2, null]}));
+        let back = apply_transform(&columns, ":transpose").unwrap();
+        assert_eq!(back, json!([{"a": 1, "b": 2}, {"a": 3, "b": null}]));
+    }
+
+    #[test]
+    fn test_transpose_invalid_shape() {
+        assert!(apply_transform(&json!("x"), ":transpose").is_err());
+    }
+
+    // --- :pivot, :unpivot tests ---
+
+    #[test]
+    fn test_pivot_sum() {
+        let data = json!([
+            {"date": "d1", "metric": "clicks", "value": 3},
+            {"date": "d1", "metric": "views", "value": 10},
+            {"date": "d2", "metric": "clicks", "value": 1},
+            {"date": "d1", "metric": "clicks", "value": 2}
+        ]);
+        let result = apply_transform(
+            &data,
+            ":pivot index=date columns=metric values=value agg=sum",
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            json!([
+                {"date": "d1", "clicks": 5, "views": 10},
+                {"date": "d2", "clicks": 1, "views": null}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_pivot_default_agg_first_and_count() {
+        let data = json!([
+            {"k": "a", "c": "x", "v": 1},
+            {"k": "a", "c": "x", "v": 2}
+        ]);
+        let first = apply_transform(&data, ":pivot index=k columns=c values=v").unwrap();
+        assert_eq!(first, json!([{"k": "a", "x": 1}]));
+        let count = apply_transform(&data, ":pivot index=k columns=c values=v agg=count").unwrap();
+        assert_eq!(count, json!([{"k": "a", "x": 2}]));
+    }
+
+    #[test]
+    fn test_pivot_multi_index() {
+        let data = json!([
+            {"d": 1, "r": "eu", "m": "x", "v": 1},
+            {"d": 1, "r": "us", "m": "x", "v": 2}
+        ]);
+        let result = apply_transform(&data, ":pivot index=d,r columns=m values=v").unwrap();
+        assert_eq!(
+            result,
+            json!([{"d": 1, "r": "eu", "x": 1}, {"d": 1, "r": "us", "x": 2}])
+        );
+    }
+
+    #[test]
+    fn test_pivot_column_clashing_with_index() {
+        let data = json!([{"name": "a", "field": "name", "v": 1}]);
+        let err = apply_transform(&data, ":pivot index=name columns=field values=v")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("same name as an index field"), "{err}");
+    }
+
+    #[test]
+    fn test_pivot_index() {
+        assert_eq!(
+            pivot_index(":sort date :pivot index=date,region columns=m values=v"),
+            Some(vec!["date".to_string(), "region".to_string()])
+        );
+        assert_eq!(
+            pivot_index(":pivot index=date columns=m values=v :rename date=day"),
+            None
+        );
+        assert_eq!(pivot_index(":sort date"), None);
+    }
+
+    #[test]
+    fn test_pivot_missing_options() {
+        let err = apply_transform(&json!([]), ":pivot index=date").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("requires index, columns and values"));
+        assert!(
+            apply_transform(&json!([]), ":pivot index=a columns=b values=c agg=median").is_err()
+        );
+    }
+
+    #[test]
+    fn test_unpivot() {
+        let data = json!([{"id": 1, "a": 10, "b": 20}]);
+        let result = apply_transform(&data, ":unpivot keep=id").unwrap();
+        assert_eq!(
+            result,
+            json!([
+                {"id": 1, "key": "a", "value": 10},
+                {"id": 1, "key": "b", "value": 20}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_unpivot_then_pivot_round_trip() {
+        let data = json!([{"id": 1, "a": 10, "b": 20}, {"id": 2, "a": 30, "b": null}]);
+        let result = apply_transform(
+            &data,
+            ":unpivot keep=id key=col value=val :pivot index=id columns=col values=val",
+        )
+        .unwrap();
+        assert_eq!(result, data);
+    }
+
+    // --- :lookup tests ---
+
+    fn lookup_docs() -> Documents {
+        let mut docs = Documents::new();
+        docs.insert(
+            "users".into(),
+            json!([
+                {"id": 1, "name": "Alice"},
+                {"id": "2", "name": "Bob"}
+            ]),
+        );
+        docs
+    }
+
+    #[test]
+    fn test_lookup_left_join() {
+        let orders = json!([
+            {"order": "a", "user_id": 1},
+            {"order": "b", "user_id": 2},
+            {"order": "c", "user_id": 9}
+        ]);
+        let result = apply_transform_with(
+            &orders,
+            ":lookup $users on user_id=id as user :pick order,user",
+            &lookup_docs(),
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            json!([
+                {"order": "a", "user": {"id": 1, "name": "Alice"}},
+                {"order": "b", "user": {"id": "2", "name": "Bob"}},
+                {"order": "c", "user": null}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_lookup_inner_join_default_name() {
+        let orders = json!([{"user_id": 1}, {"user_id": 9}]);
+        let result = apply_transform_with(
+            &orders,
+            ":lookup $users on user_id=id inner",
+            &lookup_docs(),
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            json!([{"user_id": 1, "users": {"id": 1, "name": "Alice"}}])
+        );
+    }
+
+    #[test]
+    fn test_lookup_one_to_many() {
+        let mut docs = Documents::new();
+        docs.insert(
+            "tags".into(),
+            json!([{"k": 1, "t": "x"}, {"k": 1, "t": "y"}]),
+        );
+        let result =
+            apply_transform_with(&json!([{"k": 1}]), ":lookup $tags on k=k", &docs).unwrap();
+        assert_eq!(result.as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_lookup_unknown_document() {
+        let err = apply_transform(&json!([]), ":lookup $users on a=b").unwrap_err();
+        assert!(err.to_string().contains("--with users=file.json"));
+    }
+
+    #[test]
+    fn test_lookup_bad_syntax() {
+        assert!(apply_transform_with(&json!([]), ":lookup $users", &lookup_docs()).is_err());
+        assert!(apply_transform_with(&json!([]), ":lookup $users on id", &lookup_docs()).is_err());
+    }
+
+    #[test]
+    fn test_resolve_document_path() {
+        let docs = lookup_docs();
+        assert_eq!(
+            resolve_document("$users[1].name", &docs).unwrap(),
+            json!("Bob")
+        );
+        assert_eq!(resolve_document("$users", &docs).unwrap(), docs["users"]);
+        assert!(resolve_document("$users.missing", &docs).is_err());
+    }
+
+    // --- decoder tests ---
+
+    #[test]
+    fn test_parse_json_with_path() {
+        let data = json!({"payload": "{\"user\": {\"name\": \"Ann\"}}"});
+        let payload = data.get("payload").unwrap();
+        assert_eq!(
+            apply_transform(payload, ":parse_json").unwrap(),
+            json!({"user": {"name": "Ann"}})
+        );
+        assert_eq!(
+            apply_transform(payload, ":parse_json .user.name").unwrap(),
+            json!("Ann")
+        );
+    }
+
+    #[test]
+    fn test_parse_json_array_of_strings() {
+        let data = json!(["[1]", "{\"a\": 2}", 3]);
+        let result = apply_transform(&data, ":parse_json").unwrap();
+        assert_eq!(result, json!([[1], {"a": 2}, 3]));
+    }
+
+    #[test]
+    fn test_parse_json_invalid() {
+        let err = apply_transform(&json!("{oops"), ":parse_json").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with(":parse_json: string is not valid JSON"));
+    }
+
+    #[test]
+    fn test_base64_chain() {
+        let result = apply_transform(&json!("hi there"), ":base64_encode :base64_decode").unwrap();
+        assert_eq!(result, json!("hi there"));
+    }
+
+    #[test]
+    fn test_url_decode_and_query_string() {
+        assert_eq!(
+            apply_transform(&json!("a%2Bb"), ":url_decode").unwrap(),
+            json!("a+b")
+        );
+        assert_eq!(
+            apply_transform(&json!("?q=jdx&page=2"), ":parse_query_string .page").unwrap(),
+            json!("2")
+        );
+    }
+
+    #[test]
+    fn test_jwt_decode_claims() {
+        let token = "eyJhbGciOiJIUzI1NiJ9.eyJzdWIiOiJhYmMifQ.sig";
+        let result = apply_transform(&json!(token), ":jwt_decode .claims.sub").unwrap();
+        assert_eq!(result, json!("abc"));
+    }
+
+    #[test]
+    fn test_decode_on_object() {
+        assert!(apply_transform(&json!({"a": 1}), ":parse_json").is_err());
+        assert!(apply_transform(&json!("{}"), ":parse_json user").is_err());
+    }
+
+    // --- argument quoting tests ---
+
+    #[test]
+    fn test_quoted_separators() {
+        let words = apply_transform(&json!("a b  c"), r#":split " ""#).unwrap();
+        assert_eq!(words, json!(["a", "b", "", "c"]));
+        let joined = apply_transform(&json!(["a", "b"]), r#":join ", ""#).unwrap();
+        assert_eq!(joined, json!("a, b"));
+        let joined = apply_transform(&json!(["a", "b"]), ":join ,").unwrap();
+        assert_eq!(joined, json!("a,b"));
+    }
+
+    #[test]
+    fn test_quoted_field_names() {
+        let data = json!([
+            {"first name": "Bob", "age": 1},
+            {"first name": "Ada", "age": 2}
+        ]);
+        let result =
+            apply_transform(&data, r#":pick "first name", age :sort "first name""#).unwrap();
+        assert_eq!(result[0]["first name"], "Ada");
+        assert_eq!(result[1], json!({"first name": "Bob", "age": 1}));
+    }
+
+    #[test]
+    fn test_filter_value_containing_chain_separator() {
+        let data = json!([{"name": "a :b"}, {"name": "c"}]);
+        let result = apply_transform(&data, r#":filter name == "a :b" :count"#).unwrap();
+        assert_eq!(result, json!(1));
+    }
+
+    #[test]
+    fn test_argument_errors_have_spans() {
+        use crate::engine::lexer::SyntaxError;
+        let span = |cmd: &str| {
+            let err = apply_transform(&json!([1]), cmd).unwrap_err();
+            err.downcast_ref::<SyntaxError>().map(|e| e.span.clone())
+        };
+        assert_eq!(span(":count extra"), Some(7..12));
+        assert_eq!(span(":sort :nope"), Some(6..11));
+        assert_eq!(span(":join a b"), Some(8..9));
+        assert_eq!(span(r#":split ""#), Some(7..8));
+    }
+
+    // --- :format tests ---
+
+    #[test]
+    fn test_format_array_of_records() {
+        let data = json!([
+            {"name": "Ada", "email": "ada@example.com", "orders": [1, 2, 3]},
+            {"name": "Bob", "nick": "bobby", "orders": []}
+        ]);
+        let result = apply_transform(
+            &data,
+            r#":format '{nick|name} <{email|"n/a"}> — {orders.length} orders'"#,
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            json!(["Ada <ada@example.com> — 3 orders", "bobby <n/a> — 0 orders"])
+        );
+    }
+
+    #[test]
+    fn test_format_single_object_with_specs() {
+        let data = json!({"item": "widget", "total": 1234.5});
+        let result = apply_transform(&data, r#":format "{item:<8}|{total:>10,.2}""#).unwrap();
+        assert_eq!(result, json!("widget  |  1,234.50"));
+    }
+
+    #[test]
+    fn test_format_errors() {
+        assert!(apply_transform(&json!({}), ":format").is_err());
+        let err = apply_transform(&json!({}), r#":format "{name""#).unwrap_err();
+        assert!(err.to_string().contains("unclosed"));
+    }
+
+    // --- regex tests ---
+
+    #[test]
+    fn test_replace_with_capture_groups() {
+        let data = json!("2024-03-01");
+        let result = apply_transform(&data, r#":replace /(\d+)-(\d+)-(\d+)/ "$3/$2/$1""#).unwrap();
+        assert_eq!(result, json!("01/03/2024"));
+    }
+
+    #[test]
+    fn test_replace_named_group_and_flags() {
+        let data = json!(["Hello World", "hello there", 42]);
+        let result = apply_transform(&data, r#":replace /(?P<w>hello)/i "<${w}>""#).unwrap();
+        assert_eq!(result, json!(["<Hello> World", "<hello> there", 42]));
+    }
+
+    #[test]
+    fn test_replace_field_of_objects() {
+        let data = json!([
+            {"phone": "555-123-4567"},
+            {"phone": "555 987 6543"},
+            {"name": "no phone"}
+        ]);
+        let result = apply_transform(&data, r#":replace /\D/ "" phone"#).unwrap();
+        assert_eq!(
+            result,
+            json!([
+                {"phone": "5551234567"},
+                {"phone": "5559876543"},
+                {"name": "no phone"}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_replace_escaped_slash() {
+        let result = apply_transform(&json!("a/b/c"), r#":replace /\// "." "#).unwrap();
+        assert_eq!(result, json!("a.b.c"));
+    }
+
+    #[test]
+    fn test_extract_named_groups() {
+        let data = json!(["HTTP 404 Not Found", "no status"]);
+        let result =
+            apply_transform(&data, r":extract /(?P<proto>[A-Z]+) (?P<code>\d{3})/").unwrap();
+        assert_eq!(result, json!([{"code": "404", "proto": "HTTP"}, null]));
+    }
+
+    #[test]
+    fn test_extract_unnamed_group() {
+        let data = json!([{"url": "https://example.com/users/42"}]);
+        let result = apply_transform(&data, r":extract /users\/(\d+)/ url").unwrap();
+        assert_eq!(result, json!([{"url": "42"}]));
+    }
+
+    #[test]
+    fn test_match_returns_bools() {
+        let data = json!(["a@example.com", "nope", null]);
+        let result = apply_transform(&data, r":match /^[^@]+@[^@]+$/").unwrap();
+        assert_eq!(result, json!([true, false, false]));
+    }
+
+    #[test]
+    fn test_regex_errors() {
+        assert!(apply_transform(&json!("x"), ":match abc").is_err());
+        assert!(apply_transform(&json!("x"), ":match /abc").is_err());
+        assert!(apply_transform(&json!("x"), ":match /(/").is_err());
+        assert!(apply_transform(&json!("x"), ":match /x/q").is_err());
+        assert!(apply_transform(&json!("x"), ":replace /x/").is_err());
+        assert!(apply_transform(&json!({"a": "x"}), ":match /x/ a").is_err());
+    }
+
+    // --- date/time tests ---
+
+    #[test]
+    fn test_to_date_normalizes_formats() {
+        let data = json!(["2024-03-01", "2024-03-01 12:30:00", 1_709_296_200, null]);
+        let result = apply_transform(&data, ":to_date").unwrap();
+        assert_eq!(
+            result,
+            json!([
+                "2024-03-01T00:00:00Z",
+                "2024-03-01T12:30:00Z",
+                "2024-03-01T12:30:00Z",
+                null
+            ])
+        );
+    }
+
+    #[test]
+    fn test_to_date_custom_format_and_tz() {
+        let data = json!("01/03/2024 23:30");
+        let result = apply_transform(&data, ":to_date fmt=\"%d/%m/%Y %H:%M\" tz=+02:00").unwrap();
+        assert_eq!(result, json!("2024-03-02T01:30:00+02:00"));
+    }
+
+    #[test]
+    fn test_to_date_unparseable_errors() {
+        let err = apply_transform(&json!("yesterday"), ":to_date").unwrap_err();
+        assert!(err.to_string().contains("cannot parse"));
+    }
+
+    #[test]
+    fn test_format_date_field() {
+        let data = json!([
+            {"id": 1, "created_at": "2024-03-01T22:15:00Z"},
+            {"id": 2}
+        ]);
+        let result = apply_transform(
+            &data,
+            ":format_date \"%Y-%m-%d %H:%M\" field=created_at tz=+05:00",
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            json!([
+                {"id": 1, "created_at": "2024-03-02 03:15"},
+                {"id": 2}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_format_date_requires_format() {
+        assert!(apply_transform(&json!("2024-03-01"), ":format_date").is_err());
+    }
+
+    #[test]
+    fn test_epoch_seconds_and_millis() {
+        let data = json!("2024-01-01T00:00:01+00:00");
+        assert_eq!(
+            apply_transform(&data, ":epoch").unwrap(),
+            json!(1_704_067_201)
+        );
+        assert_eq!(
+            apply_transform(&data, ":epoch unit=ms").unwrap(),
+            json!(1_704_067_201_000i64)
+        );
+    }
+
+    #[test]
+    fn test_bucket_time_hourly_then_group() {
+        let data = json!([
+            {"ts": "2024-03-01T10:05:00Z", "v": 1},
+            {"ts": "2024-03-01T10:55:00Z", "v": 2},
+            {"ts": "2024-03-01T11:00:00Z", "v": 3}
+        ]);
+        let result = apply_transform(&data, ":bucket_time 1h field=ts :group_by ts").unwrap();
+        let groups = result.as_object().unwrap();
+        assert_eq!(groups["2024-03-01T10:00:00Z"].as_array().unwrap().len(), 2);
+        assert_eq!(groups["2024-03-01T11:00:00Z"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_bucket_time_daily_in_offset() {
+        let data = json!("2024-03-01T23:30:00Z");
+        let result = apply_transform(&data, ":bucket_time 1d tz=+02:00").unwrap();
+        assert_eq!(result, json!("2024-03-02T00:00:00+02:00"));
+        assert!(apply_transform(&data, ":bucket_time 1y").is_err());
+    }
+
+    #[test]
+    fn test_filter_with_date_literal() {
+        let data = json!([
+            {"id": 1, "created_at": "2023-12-31T23:59:59Z"},
+            {"id": 2, "created_at": "2024-01-02"}
+        ]);
+        let result = apply_transform(&data, ":filter created_at > date(\"2024-01-01\")").unwrap();
+        assert_eq!(result, json!([{"id": 2, "created_at": "2024-01-02"}]));
+    }
+
     // --- :sum, :avg, :min, :max tests ---
 
     #[test]
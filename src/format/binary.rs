@@ -0,0 +1,98 @@
+//! JSON representations shared by the binary formats (MessagePack, CBOR).
+//!
+//! | Binary value              | JSON                                        |
+//! |---------------------------|---------------------------------------------|
+//! | byte string               | `{"$binary": "<base64>"}`                   |
+//! | MessagePack extension     | `{"$ext": {"type": 5, "data": "<base64>"}}` |
+//! | CBOR tag                  | `{"$tag": 1, "value": <tagged value>}`      |
+//! | non-string map key        | the key's JSON text, e.g. `"1"` or `"true"` |
+//! | NaN / infinity, undefined | `null`                                      |
+//! | integer beyond 64 bits    | decimal string                              |
+//!
+//! Objects of exactly these shapes are turned back into the binary type when
+//! writing, so data round-trips.
+
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::{json, Map, Value};
+
+use crate::engine::decode::base64_bytes;
+
+pub(crate) const BINARY: &str = "$binary";
+pub(crate) const EXT: &str = "$ext";
+pub(crate) const TAG: &str = "$tag";
+
+pub(crate) fn binary_to_json(bytes: &[u8]) -> Value {
+    json!({ BINARY: STANDARD.encode(bytes) })
+}
+
+pub(crate) fn ext_to_json(kind: i8, data: &[u8]) -> Value {
+    json!({ EXT: {"type": kind, "data": STANDARD.encode(data)} })
+}
+
+pub(crate) fn tag_to_json(tag: u64, value: Value) -> Value {
+    json!({ TAG: tag, "value": value })
+}
+
+/// The bytes of a `{"$binary": "<base64>"}` object.
+pub(crate) fn json_to_binary(map: &Map<String, Value>) -> Result<Option<Vec<u8>>> {
+    match (map.len(), map.get(BINARY)) {
+        (1, Some(Value::String(data))) => base64_bytes(data)
+            .map(Some)
+            .context("invalid base64 in $binary"),
+        _ => Ok(None),
+    }
+}
+
+/// The type and bytes of a `{"$ext": {"type": n, "data": "<base64>"}}` object.
+pub(crate) fn json_to_ext(map: &Map<String, Value>) -> Result<Option<(i8, Vec<u8>)>> {
+    let (1, Some(Value::Object(ext))) = (map.len(), map.get(EXT)) else {
+        return Ok(None);
+    };
+    let kind = ext.get("type").and_then(Value::as_i64);
+    let data = ext.get("data").and_then(Value::as_str);
+    match (kind, data) {
+        (Some(kind), Some(data)) => {
+            let kind = i8::try_from(kind).context("$ext type must be between -128 and 127")?;
+            let data = base64_bytes(data).context("invalid base64 in $ext data")?;
+            Ok(Some((kind, data)))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// The tag number and tagged value of a `{"$tag": n, "value": v}` object.
+pub(crate) fn json_to_tag(map: &Map<String, Value>) -> Option<(u64, &Value)> {
+    match (
+        map.len(),
+        map.get(TAG).and_then(Value::as_u64),
+        map.get("value"),
+    ) {
+        (2, Some(tag), Some(value)) => Some((tag, value)),
+        _ => None,
+    }
+}
+
+/// An object key for a map key that is not a string.
+pub(crate) fn key_to_string(key: Value) -> String {
+    match key {
+        Value::String(s) => s,
+        other => other.to_string(),
+    }
+}
+
+/// A JSON number for an integer, or a decimal string when it does not fit.
+pub(crate) fn integer_to_json(n: i128) -> Value {
+    if let Ok(i) = i64::try_from(n) {
+        Value::from(i)
+    } else if let Ok(u) = u64::try_from(n) {
+        Value::from(u)
+    } else {
+        Value::String(n.to_string())
+    }
+}
+
+pub(crate) fn float_to_json(f: f64) -> Value {
+    serde_json::Number::from_f64(f).map_or(Value::Null, Value::Number)
+}
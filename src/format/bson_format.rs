@@ -0,0 +1,674 @@
+//! BSON document streams, as written by `mongodump`.
+//!
+//! Documents are decoded to canonical Extended JSON and then unwrapped into
+//! plain values as described in [`super::ejson_format`]; writing goes the
+//! other way, so BSON types survive a read, edit and write cycle.
+//!
+//! The codec is written out here rather than taken from the `bson` crate,
+//! which turns on serde_json's `preserve_order` feature. Cargo unifies
+//! features, so that would change the key order of every object jdx reads
+//! and writes (output, CSV columns, the tree view) from sorted to insertion
+//! order.
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::DateTime;
+use serde_json::{json, Map, Value};
+
+use super::ejson_format::{self, TypeHints};
+use crate::engine::decode::base64_bytes;
+use crate::engine::query::PathSegment;
+
+/// Parse a stream of BSON documents into an array of objects.
+pub fn parse(bytes: &[u8]) -> Result<Value> {
+    parse_typed(bytes).map(|(value, _)| value)
+}
+
+/// Like [`parse`], also returning the types of the unwrapped fields.
+pub fn parse_typed(bytes: &[u8]) -> Result<(Value, TypeHints)> {
+    let mut reader = Reader { bytes, pos: 0 };
+    let mut docs = Vec::new();
+    while reader.pos < bytes.len() {
+        let start = reader.pos;
+        let doc = reader
+            .document()
+            .with_context(|| format!("invalid BSON document at byte {start}"))?;
+        docs.push(Value::Object(doc));
+    }
+    let mut hints = TypeHints::default();
+    let value = ejson_format::unwrap(Value::Array(docs), &mut hints);
+    Ok((value, hints))
+}
+
+/// Serialize an object, or an array of objects, as a BSON document stream.
+/// `hints` are the field types of `value` as read.
+pub fn serialize(value: &Value, hints: &TypeHints) -> Result<Vec<u8>> {
+    // A single document is written as a stream of one
+    let (docs, hints) = match value {
+        Value::Array(items) => (items.as_slice(), hints.select(&[PathSegment::Index(0)])),
+        other => (std::slice::from_ref(other), hints.clone()),
+    };
+    let mut out = Vec::new();
+    for (i, doc) in docs.iter().enumerate() {
+        let wrapped = if doc.is_object() {
+            ejson_format::wrap(doc, true, &hints)
+        } else {
+            Value::Null
+        };
+        let Value::Object(map) = wrapped else {
+            bail!(
+                "BSON can only hold documents, but item {i} is not an object.\n\
+                 Hint: write an object or an array of objects"
+            );
+        };
+        write_document(&mut out, &map).with_context(|| format!("cannot write item {i} as BSON"))?;
+    }
+    Ok(out)
+}
+
+/// Whether `bytes` is a sequence of length-prefixed BSON documents.
+pub fn looks_like_bson(bytes: &[u8]) -> bool {
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let Some(len) = rest
+            .get(..4)
+            .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        else {
+            return false;
+        };
+        let Ok(len) = usize::try_from(len) else {
+            return false;
+        };
+        // Documents are at least 5 bytes, end with NUL and start with an
+        // element type (or the NUL of an empty document)
+        if len < 5 || len > rest.len() || rest[len - 1] != 0 {
+            return false;
+        }
+        if !matches!(rest[4], 0x00..=0x13 | 0x7f | 0xff) || (rest[4] == 0) != (len == 5) {
+            return false;
+        }
+        rest = &rest[len..];
+    }
+    !bytes.is_empty()
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|&end| end <= self.bytes.len());
+        let Some(end) = end else {
+            bail!("unexpected end of data at byte {}", self.pos);
+        };
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().expect("length checked"))
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.array()?))
+    }
+
+    fn i64(&mut self) -> Result<i64> {
+        Ok(i64::from_le_bytes(self.array()?))
+    }
+
+    fn cstring(&mut self) -> Result<String> {
+        let rest = &self.bytes[self.pos..];
+        let Some(nul) = rest.iter().position(|&b| b == 0) else {
+            bail!("unterminated string at byte {}", self.pos);
+        };
+        let s = std::str::from_utf8(&rest[..nul])
+            .map_err(|e| anyhow!("invalid UTF-8 at byte {}: {e}", self.pos))?;
+        self.pos += nul + 1;
+        Ok(s.to_string())
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = usize::try_from(self.i32()?).unwrap_or(0);
+        if len == 0 {
+            bail!("invalid string length at byte {}", self.pos - 4);
+        }
+        let bytes = self.take(len)?;
+        std::str::from_utf8(&bytes[..len - 1])
+            .map(str::to_string)
+            .map_err(|e| anyhow!("invalid UTF-8 string: {e}"))
+    }
+
+    fn document(&mut self) -> Result<Map<String, Value>> {
+        let start = self.pos;
+        let len = usize::try_from(self.i32()?).unwrap_or(0);
+        let end = start + len;
+        if len < 5 || end > self.bytes.len() {
+            bail!("invalid document length {len} at byte {start}");
+        }
+        let mut map = Map::new();
+        loop {
+            let kind = self.array::<1>()?[0];
+            if kind == 0 {
+                break;
+            }
+            let key = self.cstring()?;
+            let value = self.element(kind)?;
+            map.insert(key, value);
+        }
+        if self.pos != end {
+            bail!(
+                "document at byte {start} is {len} bytes but ends at byte {}",
+                self.pos
+            );
+        }
+        Ok(map)
+    }
+
+    fn element(&mut self, kind: u8) -> Result<Value> {
+        Ok(match kind {
+            0x01 => json!({ "$numberDouble": format_double(f64::from_le_bytes(self.array()?)) }),
+            0x02 => Value::String(self.string()?),
+            0x03 => Value::Object(self.document()?),
+            0x04 => Value::Array(self.document()?.into_iter().map(|(_, v)| v).collect()),
+            0x05 => {
+                let len = usize::try_from(self.i32()?).unwrap_or(usize::MAX);
+                let subtype = self.array::<1>()?[0];
+                let data = self.take(len)?;
+                json!({ "$binary": { "base64": STANDARD.encode(data), "subType": format!("{subtype:02x}") } })
+            }
+            0x06 => json!({ "$undefined": true }),
+            0x07 => json!({ "$oid": hex(&self.array::<12>()?) }),
+            0x08 => Value::Bool(self.array::<1>()?[0] != 0),
+            0x09 => json!({ "$date": { "$numberLong": self.i64()?.to_string() } }),
+            0x0a => Value::Null,
+            0x0b => {
+                let pattern = self.cstring()?;
+                let options = self.cstring()?;
+                json!({ "$regularExpression": { "pattern": pattern, "options": options } })
+            }
+            0x0c => {
+                let namespace = self.string()?;
+                let id = hex(&self.array::<12>()?);
+                json!({ "$dbPointer": { "$ref": namespace, "$id": { "$oid": id } } })
+            }
+            0x0d => json!({ "$code": self.string()? }),
+            0x0e => json!({ "$symbol": self.string()? }),
+            0x0f => {
+                self.i32()?;
+                let code = self.string()?;
+                let scope = self.document()?;
+                json!({ "$code": code, "$scope": scope })
+            }
+            0x10 => json!({ "$numberInt": self.i32()?.to_string() }),
+            0x11 => {
+                let increment = u32::from_le_bytes(self.array()?);
+                let seconds = u32::from_le_bytes(self.array()?);
+                json!({ "$timestamp": { "t": seconds, "i": increment } })
+            }
+            0x12 => json!({ "$numberLong": self.i64()?.to_string() }),
+            0x13 => json!({ "$numberDecimal": decimal128_to_string(self.array()?) }),
+            0x7f => json!({ "$maxKey": 1 }),
+            0xff => json!({ "$minKey": 1 }),
+            other => bail!(
+                "unknown element type 0x{other:02x} at byte {}",
+                self.pos - 1
+            ),
+        })
+    }
+}
+
+fn write_document(out: &mut Vec<u8>, map: &Map<String, Value>) -> Result<()> {
+    let start = out.len();
+    out.extend_from_slice(&[0; 4]);
+    for (key, value) in map {
+        write_element(out, key, value)?;
+    }
+    out.push(0);
+    let len = i32::try_from(out.len() - start).context("document too large")?;
+    out[start..start + 4].copy_from_slice(&len.to_le_bytes());
+    Ok(())
+}
+
+fn write_element(out: &mut Vec<u8>, key: &str, value: &Value) -> Result<()> {
+    let kind_at = out.len();
+    out.push(0);
+    write_cstring(out, key)?;
+    let kind = match value {
+        Value::Null => 0x0a,
+        Value::Bool(b) => {
+            out.push(u8::from(*b));
+            0x08
+        }
+        Value::Number(n) => match (n.as_i64(), n.as_f64()) {
+            (Some(i), _) if i32::try_from(i).is_ok() => {
+                out.extend_from_slice(&(i as i32).to_le_bytes());
+                0x10
+            }
+            (Some(i), _) => {
+                out.extend_from_slice(&i.to_le_bytes());
+                0x12
+            }
+            (None, f) => {
+                out.extend_from_slice(&f.unwrap_or(f64::NAN).to_le_bytes());
+                0x01
+            }
+        },
+        Value::String(s) => {
+            write_string(out, s)?;
+            0x02
+        }
+        Value::Array(items) => {
+            let map: Map<String, Value> = items
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (i.to_string(), v.clone()))
+                .collect();
+            write_document(out, &map)?;
+            0x04
+        }
+        Value::Object(map) => match write_wrapper(out, map)? {
+            Some(kind) => kind,
+            None => {
+                write_document(out, map)?;
+                0x03
+            }
+        },
+    };
+    out[kind_at] = kind;
+    Ok(())
+}
+
+/// Write an Extended JSON type wrapper, returning its element type.
+fn write_wrapper(out: &mut Vec<u8>, map: &Map<String, Value>) -> Result<Option<u8>> {
+    let str_of = |key: &str| map.get(key).and_then(Value::as_str);
+    let Some((key, inner)) = map.iter().next() else {
+        return Ok(None);
+    };
+    let kind = match (key.as_str(), map.len()) {
+        ("$oid", 1) => {
+            let id = str_of("$oid").and_then(unhex).filter(|b| b.len() == 12);
+            out.extend_from_slice(&id.context("$oid must be 24 hex digits")?);
+            0x07
+        }
+        ("$date", 1) => {
+            let ms = match inner {
+                Value::Object(long) => long
+                    .get("$numberLong")
+                    .and_then(Value::as_str)
+                    .and_then(|s| s.parse().ok()),
+                Value::String(iso) => DateTime::parse_from_rfc3339(iso)
+                    .ok()
+                    .map(|d| d.timestamp_millis()),
+                other => other.as_i64(),
+            };
+            out.extend_from_slice(&ms.context("invalid $date")?.to_le_bytes());
+            0x09
+        }
+        ("$numberInt", 1) => {
+            let n: i32 = str_of(key)
+                .and_then(|s| s.parse().ok())
+                .context("invalid $numberInt")?;
+            out.extend_from_slice(&n.to_le_bytes());
+            0x10
+        }
+        ("$numberLong", 1) => {
+            let n: i64 = str_of(key)
+                .and_then(|s| s.parse().ok())
+                .context("invalid $numberLong")?;
+            out.extend_from_slice(&n.to_le_bytes());
+            0x12
+        }
+        ("$numberDouble", 1) => {
+            let f = str_of(key)
+                .and_then(parse_double)
+                .context("invalid $numberDouble")?;
+            out.extend_from_slice(&f.to_le_bytes());
+            0x01
+        }
+        ("$numberDecimal", 1) => {
+            out.extend_from_slice(&parse_decimal128(
+                str_of(key).context("invalid $numberDecimal")?,
+            )?);
+            0x13
+        }
+        ("$binary", 1) => {
+            let data = inner
+                .get("base64")
+                .and_then(Value::as_str)
+                .context("invalid $binary")?;
+            let data = base64_bytes(data)?;
+            let subtype = inner
+                .get("subType")
+                .and_then(Value::as_str)
+                .and_then(|s| u8::from_str_radix(s, 16).ok())
+                .context("invalid $binary subType")?;
+            out.extend_from_slice(&i32::try_from(data.len())?.to_le_bytes());
+            out.push(subtype);
+            out.extend_from_slice(&data);
+            0x05
+        }
+        ("$regularExpression", 1) => {
+            let field = |name: &str| inner.get(name).and_then(Value::as_str).unwrap_or_default();
+            write_cstring(out, field("pattern"))?;
+            write_cstring(out, field("options"))?;
+            0x0b
+        }
+        ("$timestamp", 1) => {
+            let field = |name: &str| {
+                inner
+                    .get(name)
+                    .and_then(Value::as_u64)
+                    .and_then(|n| u32::try_from(n).ok())
+            };
+            let (t, i) = field("t").zip(field("i")).context("invalid $timestamp")?;
+            out.extend_from_slice(&i.to_le_bytes());
+            out.extend_from_slice(&t.to_le_bytes());
+            0x11
+        }
+        ("$dbPointer", 1) => {
+            let namespace = inner
+                .get("$ref")
+                .and_then(Value::as_str)
+                .context("invalid $dbPointer")?;
+            let id = inner
+                .pointer("/$id/$oid")
+                .and_then(Value::as_str)
+                .and_then(unhex)
+                .filter(|b| b.len() == 12)
+                .context("invalid $dbPointer")?;
+            write_string(out, namespace)?;
+            out.extend_from_slice(&id);
+            0x0c
+        }
+        ("$code", 1) => {
+            write_string(out, str_of(key).context("invalid $code")?)?;
+            0x0d
+        }
+        ("$code" | "$scope", 2) => {
+            let (Some(code), Some(Value::Object(scope))) = (str_of("$code"), map.get("$scope"))
+            else {
+                return Ok(None);
+            };
+            let start = out.len();
+            out.extend_from_slice(&[0; 4]);
+            write_string(out, code)?;
+            write_document(out, scope)?;
+            let len = i32::try_from(out.len() - start)?;
+            out[start..start + 4].copy_from_slice(&len.to_le_bytes());
+            0x0f
+        }
+        ("$symbol", 1) => {
+            write_string(out, str_of(key).context("invalid $symbol")?)?;
+            0x0e
+        }
+        ("$undefined", 1) => 0x06,
+        ("$minKey", 1) => 0xff,
+        ("$maxKey", 1) => 0x7f,
+        _ => return Ok(None),
+    };
+    Ok(Some(kind))
+}
+
+fn write_cstring(out: &mut Vec<u8>, s: &str) -> Result<()> {
+    if s.contains('\0') {
+        bail!("BSON keys and patterns cannot contain NUL characters: {s:?}");
+    }
+    out.extend_from_slice(s.as_bytes());
+    out.push(0);
+    Ok(())
+}
+
+fn write_string(out: &mut Vec<u8>, s: &str) -> Result<()> {
+    out.extend_from_slice(&i32::try_from(s.len() + 1)?.to_le_bytes());
+    out.extend_from_slice(s.as_bytes());
+    out.push(0);
+    Ok(())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn unhex(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 || !s.is_ascii() {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+        .collect()
+}
+
+fn format_double(f: f64) -> String {
+    if f.is_nan() {
+        "NaN".into()
+    } else if f.is_infinite() {
+        if f > 0.0 { "Infinity" } else { "-Infinity" }.into()
+    } else {
+        format!("{f:?}")
+    }
+}
+
+fn parse_double(s: &str) -> Option<f64> {
+    match s {
+        "NaN" => Some(f64::NAN),
+        "Infinity" => Some(f64::INFINITY),
+        "-Infinity" => Some(f64::NEG_INFINITY),
+        other => other.parse().ok(),
+    }
+}
+
+const DECIMAL_BIAS: i32 = 6176;
+const DECIMAL_MAX_EXPONENT: i32 = 12287;
+const DECIMAL_MAX_COEFFICIENT: u128 = 10u128.pow(34) - 1;
+
+/// Format an IEEE 754-2008 decimal128 (binary integer decimal encoding) as
+/// the string used by `$numberDecimal`.
+fn decimal128_to_string(bytes: [u8; 16]) -> String {
+    let low = u64::from_le_bytes(bytes[..8].try_into().expect("8 bytes"));
+    let high = u64::from_le_bytes(bytes[8..].try_into().expect("8 bytes"));
+    let sign = if high >> 63 == 1 { "-" } else { "" };
+    match (high >> 58) & 0x1f {
+        0x1f => return "NaN".into(),
+        0x1e => return format!("{sign}Infinity"),
+        _ => {}
+    }
+    let (biased, coefficient) = if (high >> 61) & 0b11 == 0b11 {
+        // Coefficients in this form exceed the maximum and count as zero
+        (((high >> 47) & 0x3fff) as i32, 0)
+    } else {
+        let coefficient = (u128::from(high & 0x1_ffff_ffff_ffff) << 64) | u128::from(low);
+        let coefficient = if coefficient > DECIMAL_MAX_COEFFICIENT {
+            0
+        } else {
+            coefficient
+        };
+        (((high >> 49) & 0x3fff) as i32, coefficient)
+    };
+    let exponent = biased - DECIMAL_BIAS;
+    let digits = coefficient.to_string();
+    let adjusted = exponent + digits.len() as i32 - 1;
+
+    if exponent <= 0 && adjusted >= -6 {
+        let point = digits.len() as i32 + exponent;
+        if exponent == 0 {
+            format!("{sign}{digits}")
+        } else if point > 0 {
+            let (int, frac) = digits.split_at(point as usize);
+            format!("{sign}{int}.{frac}")
+        } else {
+            format!("{sign}0.{}{digits}", "0".repeat((-point) as usize))
+        }
+    } else {
+        let (first, rest) = digits.split_at(1);
+        let dot = if rest.is_empty() { "" } else { "." };
+        let exp_sign = if adjusted >= 0 { "+" } else { "" };
+        format!("{sign}{first}{dot}{rest}E{exp_sign}{adjusted}")
+    }
+}
+
+/// Parse a `$numberDecimal` string into decimal128 bytes.
+fn parse_decimal128(s: &str) -> Result<[u8; 16]> {
+    let invalid = || anyhow!("invalid $numberDecimal {s:?}");
+    let (negative, body) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let sign_bit = u64::from(negative) << 63;
+    let special = match body.to_ascii_lowercase().as_str() {
+        "nan" => Some(0x7c00_0000_0000_0000),
+        "inf" | "infinity" => Some(0x7800_0000_0000_0000 | sign_bit),
+        _ => None,
+    };
+    if let Some(high) = special {
+        let mut bytes = [0; 16];
+        bytes[8..].copy_from_slice(&u64::to_le_bytes(high));
+        return Ok(bytes);
+    }
+
+    let (mantissa, exp) = match body.find(['e', 'E']) {
+        Some(i) => (
+            &body[..i],
+            body[i + 1..].parse::<i32>().map_err(|_| invalid())?,
+        ),
+        None => (body, 0),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if int.is_empty() && frac.is_empty()
+        || !(int.chars().chain(frac.chars())).all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    let mut digits = format!("{int}{frac}").trim_start_matches('0').to_string();
+    let mut exponent = exp - frac.len() as i32;
+    // Drop trailing zeros that do not fit, or that push the exponent too low
+    while digits.len() > 34 || (exponent < -DECIMAL_BIAS && digits.ends_with('0')) {
+        if !digits.ends_with('0') {
+            bail!("$numberDecimal {s:?} has more than 34 significant digits");
+        }
+        digits.pop();
+        exponent += 1;
+    }
+    let coefficient: u128 = if digits.is_empty() {
+        0
+    } else {
+        digits.parse().map_err(|_| invalid())?
+    };
+    let biased = exponent + DECIMAL_BIAS;
+    if !(0..=DECIMAL_MAX_EXPONENT).contains(&biased) {
+        bail!("$numberDecimal {s:?} is out of range");
+    }
+    let high = sign_bit | ((biased as u64) << 49) | (coefficient >> 64) as u64;
+    let mut bytes = [0; 16];
+    bytes[..8].copy_from_slice(&(coefficient as u64).to_le_bytes());
+    bytes[8..].copy_from_slice(&high.to_le_bytes());
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(elements: &[u8]) -> Vec<u8> {
+        let mut out = ((elements.len() + 5) as i32).to_le_bytes().to_vec();
+        out.extend_from_slice(elements);
+        out.push(0);
+        out
+    }
+
+    #[test]
+    fn test_parse_stream_unwraps_types() {
+        let mut first = vec![0x07];
+        first.extend_from_slice(b"id\0");
+        first.extend_from_slice(&[
+            0x65, 0xa1, 0xb2, 0xc3, 0xd4, 0xe5, 0xf6, 0x07, 0x18, 0x29, 0x3a, 0x4b,
+        ]);
+        first.push(0x09);
+        first.extend_from_slice(b"at\0");
+        first.extend_from_slice(&1_704_067_200_000_i64.to_le_bytes());
+        first.push(0x12);
+        first.extend_from_slice(b"n\0");
+        first.extend_from_slice(&5_i64.to_le_bytes());
+        let mut second = vec![0x01];
+        second.extend_from_slice(b"score\0");
+        second.extend_from_slice(&0.5_f64.to_le_bytes());
+
+        let mut bytes = doc(&first);
+        bytes.extend(doc(&second));
+        assert!(looks_like_bson(&bytes));
+        let (value, hints) = parse_typed(&bytes).unwrap();
+        assert_eq!(
+            value,
+            json!([
+                {"id": "65a1b2c3d4e5f60718293a4b", "at": "2024-01-01T00:00:00Z", "n": 5},
+                {"score": 0.5}
+            ])
+        );
+        assert_eq!(hints.get("/*/n"), Some(ejson_format::BsonType::Int64));
+    }
+
+    #[test]
+    fn test_roundtrip_preserves_types() {
+        let canonical = json!({
+            "id": {"$oid": "65a1b2c3d4e5f60718293a4b"},
+            "at": {"$date": {"$numberLong": "1704067200123"}},
+            "n": {"$numberLong": "5"},
+            "k": {"$numberInt": "1"},
+            "f": {"$numberDouble": "2.0"},
+            "price": {"$numberDecimal": "9.99"},
+            "blob": {"$binary": {"base64": "AP8=", "subType": "00"}},
+            "uuid": {"$binary": {"base64": "AAECAwQFBgcICQoLDA0ODw==", "subType": "04"}},
+            "re": {"$regularExpression": {"pattern": "^a", "options": "i"}},
+            "ts": {"$timestamp": {"t": 1700000000, "i": 3}},
+            "nested": {"list": [{"$numberInt": "1"}, "two", null, true]}
+        });
+        let mut bytes = Vec::new();
+        write_document(&mut bytes, canonical.as_object().unwrap()).unwrap();
+        let (value, hints) = parse_typed(&bytes).unwrap();
+        assert_eq!(value[0]["price"], json!("9.99"));
+        assert_eq!(value[0]["f"], json!(2.0));
+
+        let written = serialize(&value, &hints).unwrap();
+        assert_eq!(written, bytes);
+        let written = serialize(&value[0], &hints.select(&[PathSegment::Index(0)])).unwrap();
+        assert_eq!(written, bytes);
+    }
+
+    #[test]
+    fn test_decimal128_strings() {
+        for s in [
+            "9.99",
+            "-0.001",
+            "1E+3",
+            "0",
+            "12345678901234567890123456789012.34",
+            "1.5E-20",
+            "NaN",
+            "-Infinity",
+        ] {
+            assert_eq!(decimal128_to_string(parse_decimal128(s).unwrap()), s);
+        }
+        assert_eq!(
+            decimal128_to_string(parse_decimal128("1000").unwrap()),
+            "1000"
+        );
+        assert!(parse_decimal128("1.2.3").is_err());
+    }
+
+    #[test]
+    fn test_serialize_rejects_non_documents_and_bad_input() {
+        assert!(serialize(&json!([{"a": 1}, 2]), &TypeHints::default()).is_err());
+        assert!(!looks_like_bson(b"{\"a\": 1}"));
+        assert!(!looks_like_bson(&[]));
+        let err = parse(&[0x10, 0, 0, 0, 0x02, b'a', 0]).unwrap_err();
+        assert!(format!("{err:#}").contains("byte 0"), "{err:#}");
+    }
+}
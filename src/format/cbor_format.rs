@@ -0,0 +1,133 @@
+use anyhow::{Context, Result};
+use ciborium::Value as CborValue;
+use serde_json::{Map, Value};
+
+use super::binary::{
+    binary_to_json, float_to_json, integer_to_json, json_to_binary, json_to_tag, key_to_string,
+    tag_to_json,
+};
+
+/// Parse CBOR bytes into a serde_json::Value. Several concatenated data
+/// items (a CBOR sequence) become an array.
+pub fn parse(bytes: &[u8]) -> Result<Value> {
+    let mut rest = bytes;
+    let mut values = Vec::new();
+    while !rest.is_empty() {
+        let offset = bytes.len() - rest.len();
+        let value: CborValue = ciborium::de::from_reader(&mut rest)
+            .map_err(|e| anyhow::anyhow!("invalid CBOR at byte {offset}: {e}"))?;
+        values.push(to_json(value));
+    }
+    match values.len() {
+        0 => anyhow::bail!("empty CBOR input"),
+        1 => Ok(values.remove(0)),
+        _ => Ok(Value::Array(values)),
+    }
+}
+
+/// Serialize a JSON value to CBOR bytes.
+pub fn serialize(value: &Value) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    ciborium::ser::into_writer(&from_json(value)?, &mut out).context("failed to encode CBOR")?;
+    Ok(out)
+}
+
+fn to_json(value: CborValue) -> Value {
+    match value {
+        CborValue::Null => Value::Null,
+        CborValue::Bool(b) => Value::Bool(b),
+        CborValue::Integer(n) => integer_to_json(n.into()),
+        CborValue::Float(f) => float_to_json(f),
+        CborValue::Text(s) => Value::String(s),
+        CborValue::Bytes(bytes) => binary_to_json(&bytes),
+        CborValue::Array(items) => Value::Array(items.into_iter().map(to_json).collect()),
+        CborValue::Map(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(k, v)| (key_to_string(to_json(k)), to_json(v)))
+                .collect::<Map<_, _>>(),
+        ),
+        CborValue::Tag(tag, inner) => tag_to_json(tag, to_json(*inner)),
+        // Undefined and unassigned simple values
+        _ => Value::Null,
+    }
+}
+
+fn from_json(value: &Value) -> Result<CborValue> {
+    Ok(match value {
+        Value::Null => CborValue::Null,
+        Value::Bool(b) => CborValue::Bool(*b),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                CborValue::Integer(i.into())
+            } else if let Some(u) = n.as_u64() {
+                CborValue::Integer(u.into())
+            } else {
+                CborValue::Float(n.as_f64().unwrap_or(f64::NAN))
+            }
+        }
+        Value::String(s) => CborValue::Text(s.clone()),
+        Value::Array(items) => {
+            CborValue::Array(items.iter().map(from_json).collect::<Result<_>>()?)
+        }
+        Value::Object(map) => {
+            if let Some(bytes) = json_to_binary(map)? {
+                CborValue::Bytes(bytes)
+            } else if let Some((tag, inner)) = json_to_tag(map) {
+                CborValue::Tag(tag, Box::new(from_json(inner)?))
+            } else {
+                CborValue::Map(
+                    map.iter()
+                        .map(|(k, v)| Ok((CborValue::Text(k.clone()), from_json(v)?)))
+                        .collect::<Result<_>>()?,
+                )
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_map() {
+        // {"a": 1, "b": [true, null]}
+        let bytes = [0xa2, 0x61, b'a', 0x01, 0x61, b'b', 0x82, 0xf5, 0xf6];
+        assert_eq!(parse(&bytes).unwrap(), json!({"a": 1, "b": [true, null]}));
+    }
+
+    #[test]
+    fn test_bytes_tags_and_int_keys() {
+        let value = CborValue::Map(vec![
+            (CborValue::Integer(1.into()), CborValue::Bytes(vec![0, 255])),
+            (
+                CborValue::Text("t".into()),
+                CborValue::Tag(1, Box::new(CborValue::Integer(1_700_000_000.into()))),
+            ),
+        ]);
+        let mut bytes = Vec::new();
+        ciborium::ser::into_writer(&value, &mut bytes).unwrap();
+        assert_eq!(
+            parse(&bytes).unwrap(),
+            json!({"1": {"$binary": "AP8="}, "t": {"$tag": 1, "value": 1_700_000_000}})
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let value = json!({
+            "id": 7, "big": u64::MAX, "neg": -3, "pi": 3.5, "name": "x",
+            "raw": {"$binary": "AP8="}, "when": {"$tag": 0, "value": "2024-01-01T00:00:00Z"}
+        });
+        assert_eq!(parse(&serialize(&value).unwrap()).unwrap(), value);
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = parse(&[0x01, 0x82, 0x01]).unwrap_err();
+        assert!(err.to_string().contains("byte 1"), "{err}");
+        assert!(parse(&[]).is_err());
+    }
+}
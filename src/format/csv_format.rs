@@ -1,11 +1,201 @@
+//! CSV and other delimiter-separated values (TSV, PSV, `;`-separated).
+//!
+//! The dialect comes from [`CsvOptions`], usually built from the command
+//! line and used for both reading and writing. When no delimiter is given it
+//! is sniffed from the input.
+
+use std::collections::BTreeMap;
+
 use anyhow::{bail, Result};
 use serde_json::Value;
 
-/// Parse a CSV string into a JSON array of objects.
-/// The first row is treated as headers.
+use crate::engine::transform::flatten_keys;
+
+/// Delimiters tried when sniffing, in order of preference on a tie.
+const SNIFF_CANDIDATES: [u8; 4] = [b',', b'\t', b';', b'|'];
+/// How many lines sniffing looks at.
+const SNIFF_LINES: usize = 10;
+
+/// How the values of a column are read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    /// Numbers, `true`/`false` and empty cells (`null`) are recognised;
+    /// anything else, including numbers with leading zeros, is a string
+    Auto,
+    /// Always a string, even when empty
+    String,
+    /// A number; empty cells are `null`
+    Number,
+    /// `true`/`false`, `yes`/`no` or `1`/`0`; empty cells are `null`
+    Bool,
+}
+
+impl ColumnType {
+    fn from_name(name: &str) -> Result<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "string" | "str" | "text" => Ok(Self::String),
+            "number" | "num" | "int" | "float" => Ok(Self::Number),
+            "bool" | "boolean" => Ok(Self::Bool),
+            other => bail!(
+                "unknown CSV column type: {other}.\n\
+                 Hint: use auto, string, number or bool"
+            ),
+        }
+    }
+}
+
+/// A CSV dialect and how values are typed.
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    /// Field delimiter; `None` sniffs it when reading and writes commas
+    pub delimiter: Option<u8>,
+    /// Whether the first row names the columns. Without one, columns are
+    /// named `col0`, `col1`, … and output has no header row
+    pub has_headers: bool,
+    /// Quote character
+    pub quote: u8,
+    /// Escape character inside quoted fields; `None` means quotes are
+    /// escaped by doubling them
+    pub escape: Option<u8>,
+    /// Type of columns without an entry in `column_types`
+    pub default_type: ColumnType,
+    /// Per-column types, by column name
+    pub column_types: BTreeMap<String, ColumnType>,
+    /// Write a UTF-8 byte order mark before the output (for Excel)
+    pub bom: bool,
+    /// Columns written first, in this order; the others follow in key order
+    pub leading_columns: Vec<String>,
+    /// Flatten nested objects and arrays into dotted columns on output
+    /// instead of writing them as JSON text
+    pub flatten: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: None,
+            has_headers: true,
+            quote: b'"',
+            escape: None,
+            default_type: ColumnType::Auto,
+            column_types: BTreeMap::new(),
+            bom: false,
+            leading_columns: Vec::new(),
+            flatten: false,
+        }
+    }
+}
+
+impl CsvOptions {
+    /// The same options with a fixed delimiter (for TSV and PSV).
+    pub fn with_delimiter(&self, delimiter: u8) -> Self {
+        Self {
+            delimiter: Some(delimiter),
+            ..self.clone()
+        }
+    }
+
+    /// Apply a `--csv-types` spec: a bare type sets the default for every
+    /// column (`string` turns inference off) and `name=type` sets one column.
+    /// Entries are separated by commas: `string,price=number`.
+    pub fn set_types(&mut self, spec: &str) -> Result<()> {
+        for entry in spec.split(',').filter(|e| !e.trim().is_empty()) {
+            match entry.split_once('=') {
+                Some((column, kind)) => {
+                    self.column_types
+                        .insert(column.trim().to_string(), ColumnType::from_name(kind)?);
+                }
+                None => self.default_type = ColumnType::from_name(entry)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn column_type(&self, column: &str) -> ColumnType {
+        self.column_types
+            .get(column)
+            .copied()
+            .unwrap_or(self.default_type)
+    }
+}
+
+/// Parse a delimiter given on the command line: a single ASCII character, or
+/// `tab`, `\t`, `comma`, `semicolon`, `pipe` or `space`.
+pub fn parse_delimiter(s: &str) -> Result<u8> {
+    match s {
+        "tab" | "\\t" | "\t" => Ok(b'\t'),
+        "comma" => Ok(b','),
+        "semicolon" => Ok(b';'),
+        "pipe" => Ok(b'|'),
+        "space" => Ok(b' '),
+        _ if s.len() == 1 && s.is_ascii() => Ok(s.as_bytes()[0]),
+        _ => bail!(
+            "invalid CSV delimiter: {s:?}.\n\
+             Hint: use a single character, or tab, comma, semicolon, pipe or space"
+        ),
+    }
+}
+
+/// Guess the delimiter from the first lines of `content`: the candidate that
+/// appears the same number of times on every line, preferring the most
+/// frequent. Text inside `quote` characters is ignored. Returns `None` when
+/// nothing fits.
+pub fn sniff_delimiter(content: &str, quote: u8) -> Option<u8> {
+    let lines: Vec<&str> = content
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .take(SNIFF_LINES)
+        .collect();
+    let count = |line: &str, delimiter: u8| {
+        let mut in_quotes = false;
+        line.bytes()
+            .filter(|&b| {
+                if b == quote {
+                    in_quotes = !in_quotes;
+                }
+                b == delimiter && !in_quotes
+            })
+            .count()
+    };
+    SNIFF_CANDIDATES
+        .iter()
+        .filter_map(|&delimiter| {
+            let first = count(lines.first()?, delimiter);
+            let consistent = first > 0 && lines.iter().all(|l| count(l, delimiter) == first);
+            consistent.then_some((first, delimiter))
+        })
+        // max_by_key keeps the last maximum; reverse so earlier candidates win ties
+        .rev()
+        .max_by_key(|&(n, _)| n)
+        .map(|(_, delimiter)| delimiter)
+}
+
+/// Parse comma-separated CSV with a header row into a JSON array of objects.
 pub fn parse(content: &str) -> Result<Value> {
-    let mut reader = csv::Reader::from_reader(content.as_bytes());
-    let headers: Vec<String> = reader.headers()?.iter().map(|h| h.to_string()).collect();
+    parse_with(content, &CsvOptions::default())
+}
+
+/// Parse CSV into a JSON array of objects, one per row.
+pub fn parse_with(content: &str, options: &CsvOptions) -> Result<Value> {
+    let content = content.trim_start_matches('\u{feff}');
+    let delimiter = options
+        .delimiter
+        .or_else(|| sniff_delimiter(content, options.quote))
+        .unwrap_or(b',');
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(options.has_headers)
+        .quote(options.quote)
+        .escape(options.escape)
+        .flexible(true)
+        .from_reader(content.as_bytes());
+    let headers: Vec<String> = if options.has_headers {
+        reader.headers()?.iter().map(|h| h.to_string()).collect()
+    } else {
+        Vec::new()
+    };
 
     let mut rows = Vec::new();
     for record in reader.records() {
@@ -13,19 +203,17 @@ pub fn parse(content: &str) -> Result<Value> {
         let mut obj = serde_json::Map::new();
         for (i, field) in record.iter().enumerate() {
             let key = headers.get(i).cloned().unwrap_or_else(|| format!("col{i}"));
-            // Try to parse as number or bool, fall back to string
-            let value = if let Ok(n) = field.parse::<i64>() {
-                Value::Number(n.into())
-            } else if let Ok(f) = field.parse::<f64>() {
-                serde_json::Number::from_f64(f).map_or(Value::String(field.into()), Value::Number)
-            } else if field == "true" {
-                Value::Bool(true)
-            } else if field == "false" {
-                Value::Bool(false)
-            } else if field.is_empty() {
-                Value::Null
-            } else {
-                Value::String(field.to_string())
+            let value = match typed_value(field, options.column_type(&key)) {
+                Some(value) => value,
+                None => bail!(
+                    "CSV column {key:?} on line {}: {field:?} is not a {}.\n\
+                     Hint: change the column type with --csv-types {key}=string",
+                    record.position().map_or(0, |p| p.line()),
+                    match options.column_type(&key) {
+                        ColumnType::Bool => "boolean",
+                        _ => "number",
+                    }
+                ),
             };
             obj.insert(key, value);
         }
@@ -35,8 +223,61 @@ pub fn parse(content: &str) -> Result<Value> {
     Ok(Value::Array(rows))
 }
 
-/// Serialize a JSON array of objects to CSV format.
+/// Convert a field to a value of the given type; `None` if it does not fit.
+fn typed_value(field: &str, kind: ColumnType) -> Option<Value> {
+    let number = |field: &str| {
+        if let Ok(n) = field.parse::<i64>() {
+            Some(Value::Number(n.into()))
+        } else {
+            field
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(Value::Number)
+        }
+    };
+    match kind {
+        ColumnType::String => Some(Value::String(field.to_string())),
+        _ if field.is_empty() => Some(Value::Null),
+        ColumnType::Number => number(field.trim()),
+        ColumnType::Bool => match field.trim().to_lowercase().as_str() {
+            "true" | "yes" | "1" => Some(Value::Bool(true)),
+            "false" | "no" | "0" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        ColumnType::Auto => {
+            // Leading zeros are significant (zip codes, IDs), so keep those as text
+            let digits = field.strip_prefix('-').unwrap_or(field);
+            let leading_zero = digits.len() > 1
+                && digits.starts_with('0')
+                && digits.as_bytes()[1].is_ascii_digit();
+            let value = match field {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                _ if leading_zero => Value::String(field.to_string()),
+                _ => number(field).unwrap_or_else(|| Value::String(field.to_string())),
+            };
+            Some(value)
+        }
+    }
+}
+
+/// Type an unquoted text value the way CSV cells are typed by default:
+/// numbers and booleans are typed, empty text is `null`, and everything else
+/// (including numbers with leading zeros) stays a string.
+pub(crate) fn infer_value(field: &str) -> Value {
+    typed_value(field, ColumnType::Auto).unwrap_or_else(|| Value::String(field.to_string()))
+}
+
+/// Serialize a JSON array of objects to comma-separated CSV.
 pub fn serialize(value: &Value) -> Result<String> {
+    serialize_with(value, &CsvOptions::default())
+}
+
+/// Serialize a JSON array of objects to CSV. Nested objects and arrays are
+/// written as JSON text, or with `flatten` as dotted column names
+/// (`{"a":{"b":1}}` → column `a.b`), matching `:flatten_keys`.
+pub fn serialize_with(value: &Value, options: &CsvOptions) -> Result<String> {
     let arr = match value {
         Value::Array(arr) => arr,
         _ => bail!(
@@ -50,8 +291,21 @@ pub fn serialize(value: &Value) -> Result<String> {
         return Ok(String::new());
     }
 
-    // Collect all unique headers from all objects
-    let mut headers = Vec::new();
+    let flattened: Vec<Value>;
+    let arr = if options.flatten {
+        flattened = arr.iter().map(|item| flatten_keys(item, ".")).collect();
+        &flattened
+    } else {
+        arr
+    };
+
+    // Collect all unique headers from all objects, leading columns first
+    let mut headers: Vec<String> = options
+        .leading_columns
+        .iter()
+        .filter(|column| arr.iter().any(|item| item.get(column.as_str()).is_some()))
+        .cloned()
+        .collect();
     for item in arr {
         if let Value::Object(map) = item {
             for key in map.keys() {
@@ -62,8 +316,18 @@ pub fn serialize(value: &Value) -> Result<String> {
         }
     }
 
-    let mut wtr = csv::Writer::from_writer(Vec::new());
-    wtr.write_record(&headers)?;
+    let mut builder = csv::WriterBuilder::new();
+    builder
+        .delimiter(options.delimiter.unwrap_or(b','))
+        .quote(options.quote);
+    if let Some(escape) = options.escape {
+        builder.double_quote(false).escape(escape);
+    }
+    let prefix: &[u8] = if options.bom { b"\xef\xbb\xbf" } else { b"" };
+    let mut wtr = builder.from_writer(prefix.to_vec());
+    if options.has_headers {
+        wtr.write_record(&headers)?;
+    }
 
     for item in arr {
         if let Value::Object(map) = item {
@@ -125,6 +389,108 @@ mod tests {
         assert_eq!(parsed, data);
     }
 
+    #[test]
+    fn test_serialize_nested() {
+        let data = json!([
+            {"id": 1, "user": {"name": "Alice"}, "tags": ["a", "b"]},
+            {"id": 2, "user": {"name": "Bob"}, "tags": ["c"]}
+        ]);
+        assert_eq!(
+            serialize(&data).unwrap(),
+            "id,tags,user\n\
+             1,\"[\"\"a\"\",\"\"b\"\"]\",\"{\"\"name\"\":\"\"Alice\"\"}\"\n\
+             2,\"[\"\"c\"\"]\",\"{\"\"name\"\":\"\"Bob\"\"}\"\n"
+        );
+        let options = CsvOptions {
+            flatten: true,
+            ..CsvOptions::default()
+        };
+        assert_eq!(
+            serialize_with(&data, &options).unwrap(),
+            "id,tags.0,tags.1,user.name\n1,a,b,Alice\n2,c,,Bob\n"
+        );
+    }
+
+    #[test]
+    fn test_leading_zeros_stay_strings() {
+        let result = parse_with("zip,n\n01234,-07\n0,0.5\n", &CsvOptions::default()).unwrap();
+        assert_eq!(
+            result,
+            json!([{"zip": "01234", "n": "-07"}, {"zip": 0, "n": 0.5}])
+        );
+    }
+
+    #[test]
+    fn test_sniffs_delimiter() {
+        assert_eq!(sniff_delimiter("a\tb\n1\t2\n", b'"'), Some(b'\t'));
+        assert_eq!(sniff_delimiter("a;b,c\n1;\"2,5\"\n", b'"'), Some(b';'));
+        assert_eq!(sniff_delimiter("a|b\n1|2\n", b'"'), Some(b'|'));
+        assert_eq!(sniff_delimiter("plain text\n", b'"'), None);
+        // Delimiters inside the configured quote character do not count
+        assert_eq!(sniff_delimiter("a,b\n'1,5',2\n", b'\''), Some(b','));
+        assert_eq!(sniff_delimiter("a,b\n'1,5',2\n", b'"'), None);
+        let result = parse_with("\u{feff}name;age\nAda;36\n", &CsvOptions::default()).unwrap();
+        assert_eq!(result, json!([{"name": "Ada", "age": 36}]));
+    }
+
+    #[test]
+    fn test_column_types_and_headerless() {
+        let mut options = CsvOptions {
+            has_headers: false,
+            ..CsvOptions::default()
+        };
+        options.set_types("string,col1=number").unwrap();
+        let result = parse_with("007,12,\n", &options).unwrap();
+        assert_eq!(result, json!([{"col0": "007", "col1": 12, "col2": ""}]));
+
+        let mut options = CsvOptions::default();
+        options.set_types("ok=bool").unwrap();
+        let result = parse_with("ok\nyes\n\n0\n", &options).unwrap();
+        assert_eq!(result, json!([{"ok": true}, {"ok": false}]));
+        let err = parse_with("ok\nmaybe\n", &options).unwrap_err().to_string();
+        assert!(err.contains("\"ok\" on line 2"), "{err}");
+        assert!(options.set_types("x=date").is_err());
+    }
+
+    #[test]
+    fn test_serialize_dialect() {
+        let data = json!([{"a": "x\ty", "b": "say \"hi\""}]);
+        let mut options = CsvOptions::default().with_delimiter(b'\t');
+        options.escape = Some(b'\\');
+        options.has_headers = false;
+        assert_eq!(
+            serialize_with(&data, &options).unwrap(),
+            "\"x\ty\"\t\"say \\\"hi\\\"\"\n"
+        );
+        assert_eq!(
+            parse_with(&serialize_with(&data, &options).unwrap(), &options).unwrap()[0]["col1"],
+            json!("say \"hi\"")
+        );
+
+        let options = CsvOptions {
+            bom: true,
+            ..CsvOptions::default()
+        };
+        assert!(serialize_with(&data, &options)
+            .unwrap()
+            .starts_with('\u{feff}'));
+        assert_eq!(parse_delimiter("tab").unwrap(), b'\t');
+        assert!(parse_delimiter("::").is_err());
+    }
+
+    #[test]
+    fn test_serialize_leading_columns() {
+        let data = json!([{"clicks": 3, "date": "d1", "views": 10}]);
+        let options = CsvOptions {
+            leading_columns: vec!["date".into(), "missing".into()],
+            ..CsvOptions::default()
+        };
+        assert_eq!(
+            serialize_with(&data, &options).unwrap(),
+            "date,clicks,views\nd1,3,10\n"
+        );
+    }
+
     #[test]
     fn test_serialize_non_array() {
         let data = json!({"name": "Alice"});
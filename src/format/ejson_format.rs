@@ -0,0 +1,407 @@
+//! MongoDB Extended JSON.
+//!
+//! Reading unwraps the type wrappers into plain, queryable values:
+//!
+//! | Extended JSON                            | jdx value                    |
+//! |------------------------------------------|------------------------------|
+//! | `{"$oid": "65a1…"}`                      | `"65a1…"`                    |
+//! | `{"$date": "2024-01-01T00:00:00Z"}`      | `"2024-01-01T00:00:00Z"`     |
+//! | `{"$date": {"$numberLong": "1704067…"}}` | `"2024-01-01T00:00:00Z"`     |
+//! | `{"$numberLong": "42"}`, `$numberInt`    | `42`                         |
+//! | `{"$numberDouble": "1.5"}`               | `1.5` (NaN/Infinity stay wrapped) |
+//! | `{"$numberDecimal": "9.99"}`             | `"9.99"`                     |
+//! | `{"$binary": {"base64": …, "subType": "00"}}` | `{"$binary": "<base64>"}` |
+//!
+//! Other wrappers (`$timestamp`, `$regularExpression`, UUIDs, …) are kept
+//! as they are. The original type of each unwrapped field is returned as
+//! [`TypeHints`] keyed by the field's path, so writing Extended JSON or BSON
+//! wraps the same fields again even after the data was queried or
+//! transformed.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, SecondsFormat, Utc};
+use serde_json::{json, Map, Value};
+
+use crate::engine::query::PathSegment;
+
+/// A BSON type that has no direct JSON equivalent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BsonType {
+    ObjectId,
+    Date,
+    Int64,
+    Decimal,
+}
+
+/// The BSON types of the fields of a value read from Extended JSON or BSON.
+///
+/// Keys are JSON Pointer paths with `*` for any array index (`/*/_id`,
+/// `/tags/*/n`), so a type applies to every element of an array and still
+/// does after sorting or filtering it, but never to a field of the same name
+/// elsewhere. `None` marks a path that was seen with different types.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TypeHints(BTreeMap<String, Option<BsonType>>);
+
+impl TypeHints {
+    /// The type the value at `path` had when it was read, if unambiguous.
+    pub fn get(&self, path: &str) -> Option<BsonType> {
+        self.0.get(path).copied().flatten()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn record(&mut self, path: &str, ty: BsonType) {
+        match self.0.get(path) {
+            Some(Some(existing)) if *existing != ty => {
+                self.0.insert(path.to_string(), None);
+            }
+            Some(_) => {}
+            None => {
+                self.0.insert(path.to_string(), Some(ty));
+            }
+        }
+    }
+
+    /// The hints for the result of a query path, relative to that result.
+    /// Keys and indexes descend; slices and filters keep array elements
+    /// where they were. After a wildcard the shape is unknown, so there are
+    /// no hints.
+    pub fn select(&self, segments: &[PathSegment]) -> TypeHints {
+        let mut prefix = String::new();
+        for segment in segments {
+            match segment {
+                PathSegment::Key(key) => prefix = child(&prefix, key),
+                PathSegment::Index(_) => prefix.push_str("/*"),
+                PathSegment::Slice(..) | PathSegment::Filter(_) => {}
+                PathSegment::Wildcard => return TypeHints::default(),
+            }
+        }
+        if prefix.is_empty() {
+            return self.clone();
+        }
+        TypeHints(
+            self.0
+                .iter()
+                .filter_map(|(path, ty)| {
+                    let rest = path.strip_prefix(&prefix)?;
+                    (rest.is_empty() || rest.starts_with('/')).then(|| (rest.to_string(), *ty))
+                })
+                .collect(),
+        )
+    }
+}
+
+/// The path of `key` below `path`, escaped as in a JSON Pointer.
+fn child(path: &str, key: &str) -> String {
+    format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"))
+}
+
+/// Parse Extended JSON (a single value, or one document per line as written
+/// by `mongoexport`) and unwrap its type wrappers.
+pub fn parse(content: &str) -> Result<Value> {
+    parse_typed(content).map(|(value, _)| value)
+}
+
+/// Like [`parse`], also returning the types of the unwrapped fields.
+pub fn parse_typed(content: &str) -> Result<(Value, TypeHints)> {
+    let value = match super::json_fmt::parse(content) {
+        Ok(value) => value,
+        Err(err) => {
+            let lines: Vec<&str> = content.lines().filter(|l| !l.trim().is_empty()).collect();
+            if lines.len() < 2 {
+                return Err(err);
+            }
+            let docs: Result<Vec<Value>> =
+                lines.iter().map(|l| super::json_fmt::parse(l)).collect();
+            Value::Array(docs?)
+        }
+    };
+    let mut hints = TypeHints::default();
+    let value = unwrap(value, &mut hints);
+    Ok((value, hints))
+}
+
+/// Serialize as Extended JSON, canonical (every number typed) or relaxed.
+pub fn serialize(value: &Value, canonical: bool, hints: &TypeHints) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&wrap(
+        value, canonical, hints,
+    ))?)
+}
+
+/// Unwrap Extended JSON type wrappers, recording each field's type.
+pub fn unwrap(value: Value, hints: &mut TypeHints) -> Value {
+    unwrap_at(value, "", hints)
+}
+
+fn unwrap_at(value: Value, path: &str, hints: &mut TypeHints) -> Value {
+    match value {
+        Value::Object(map) => match unwrap_wrapper(&map) {
+            Some((value, ty)) => {
+                if let Some(ty) = ty {
+                    hints.record(path, ty);
+                }
+                value
+            }
+            // Wrappers jdx does not unwrap are kept whole
+            None if map.keys().any(|k| k.starts_with('$')) => Value::Object(map),
+            None => Value::Object(
+                map.into_iter()
+                    .map(|(k, v)| {
+                        let v = unwrap_at(v, &child(path, &k), hints);
+                        (k, v)
+                    })
+                    .collect(),
+            ),
+        },
+        Value::Array(items) => {
+            let path = format!("{path}/*");
+            Value::Array(
+                items
+                    .into_iter()
+                    .map(|item| unwrap_at(item, &path, hints))
+                    .collect(),
+            )
+        }
+        other => other,
+    }
+}
+
+/// The plain value of a type wrapper object, and the type to remember.
+fn unwrap_wrapper(map: &Map<String, Value>) -> Option<(Value, Option<BsonType>)> {
+    let (key, inner) = map.iter().next()?;
+    if map.len() == 2 && key == "$binary" {
+        // Legacy form: {"$binary": "<base64>", "$type": "00"}
+        let subtype = map.get("$type").and_then(Value::as_str)?;
+        return binary(inner.as_str()?, subtype);
+    }
+    if map.len() != 1 {
+        return None;
+    }
+    match (key.as_str(), inner) {
+        ("$oid", Value::String(hex)) => Some((json!(hex), Some(BsonType::ObjectId))),
+        ("$date", Value::String(iso)) => Some((json!(iso), Some(BsonType::Date))),
+        ("$date", Value::Number(ms)) => Some((date_string(ms.as_i64()?)?, Some(BsonType::Date))),
+        ("$date", Value::Object(long)) => {
+            let ms = long.get("$numberLong")?.as_str()?.parse().ok()?;
+            Some((date_string(ms)?, Some(BsonType::Date)))
+        }
+        ("$numberLong", Value::String(n)) => {
+            Some((json!(n.parse::<i64>().ok()?), Some(BsonType::Int64)))
+        }
+        ("$numberInt", Value::String(n)) => Some((json!(n.parse::<i32>().ok()?), None)),
+        ("$numberDouble", Value::String(n)) => {
+            let f: f64 = n.parse().ok()?;
+            let number = serde_json::Number::from_f64(f)?;
+            Some((Value::Number(number), None))
+        }
+        ("$numberDecimal", Value::String(d)) => Some((json!(d), Some(BsonType::Decimal))),
+        ("$binary", Value::Object(bin)) => {
+            binary(bin.get("base64")?.as_str()?, bin.get("subType")?.as_str()?)
+        }
+        _ => None,
+    }
+}
+
+/// Generic binary (subtype 0) in jdx's `{"$binary": "<base64>"}` form.
+fn binary(base64: &str, subtype: &str) -> Option<(Value, Option<BsonType>)> {
+    match u8::from_str_radix(subtype, 16) {
+        Ok(0) => Some((json!({ "$binary": base64 }), None)),
+        _ => None,
+    }
+}
+
+fn date_string(ms: i64) -> Option<Value> {
+    let date = DateTime::<Utc>::from_timestamp_millis(ms)?;
+    Some(json!(date.to_rfc3339_opts(SecondsFormat::AutoSi, true)))
+}
+
+/// Wrap values whose fields had a BSON type when read, producing Extended
+/// JSON. Canonical mode also types every number (`$numberInt` etc.).
+pub fn wrap(value: &Value, canonical: bool, hints: &TypeHints) -> Value {
+    wrap_at(value, "", canonical, hints)
+}
+
+fn wrap_at(value: &Value, path: &str, canonical: bool, hints: &TypeHints) -> Value {
+    match value {
+        Value::String(s) => match hints.get(path) {
+            Some(BsonType::ObjectId)
+                if s.len() == 24 && s.chars().all(|c| c.is_ascii_hexdigit()) =>
+            {
+                json!({ "$oid": s })
+            }
+            Some(BsonType::Date) => match DateTime::parse_from_rfc3339(s) {
+                Ok(date) => {
+                    let ms = date.timestamp_millis();
+                    // Relaxed mode uses ISO strings only for years 1970-9999
+                    let year = date.with_timezone(&Utc).year();
+                    if canonical || !(1970..=9999).contains(&year) {
+                        json!({ "$date": { "$numberLong": ms.to_string() } })
+                    } else {
+                        json!({ "$date": s })
+                    }
+                }
+                Err(_) => value.clone(),
+            },
+            Some(BsonType::Decimal) => json!({ "$numberDecimal": s }),
+            _ => value.clone(),
+        },
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                let long = hints.get(path) == Some(BsonType::Int64) || i32::try_from(i).is_err();
+                match (canonical, long) {
+                    (false, _) => value.clone(),
+                    (true, true) => json!({ "$numberLong": i.to_string() }),
+                    (true, false) => json!({ "$numberInt": i.to_string() }),
+                }
+            } else if canonical {
+                json!({ "$numberDouble": format!("{:?}", n.as_f64().unwrap_or(f64::NAN)) })
+            } else {
+                value.clone()
+            }
+        }
+        Value::Object(map) => {
+            if let (1, Some(Value::String(base64))) = (map.len(), map.get("$binary")) {
+                return json!({ "$binary": { "base64": base64, "subType": "00" } });
+            }
+            // Anything else with `$` keys is already Extended JSON
+            if map.keys().any(|k| k.starts_with('$')) {
+                return value.clone();
+            }
+            Value::Object(
+                map.iter()
+                    .map(|(k, v)| (k.clone(), wrap_at(v, &child(path, k), canonical, hints)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => {
+            let path = format!("{path}/*");
+            Value::Array(
+                items
+                    .iter()
+                    .map(|item| wrap_at(item, &path, canonical, hints))
+                    .collect(),
+            )
+        }
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::query::parse as parse_query;
+
+    #[test]
+    fn test_unwrap_canonical_and_relaxed() {
+        let mut hints = TypeHints::default();
+        let value = unwrap(
+            json!({
+                "id": {"$oid": "65a1b2c3d4e5f60718293a4b"},
+                "created": {"$date": {"$numberLong": "1704067200000"}},
+                "updated": {"$date": "2024-01-02T03:04:05.678Z"},
+                "views": {"$numberLong": "9007199254740993"},
+                "likes": {"$numberInt": "7"},
+                "ratio": {"$numberDouble": "0.25"},
+                "nan": {"$numberDouble": "NaN"},
+                "price": {"$numberDecimal": "9.99"},
+                "blob": {"$binary": {"base64": "AP8=", "subType": "00"}},
+                "ts": {"$timestamp": {"t": 1, "i": 2}}
+            }),
+            &mut hints,
+        );
+        assert_eq!(
+            value,
+            json!({
+                "id": "65a1b2c3d4e5f60718293a4b",
+                "created": "2024-01-01T00:00:00Z",
+                "updated": "2024-01-02T03:04:05.678Z",
+                "views": 9007199254740993i64,
+                "likes": 7,
+                "ratio": 0.25,
+                "nan": {"$numberDouble": "NaN"},
+                "price": "9.99",
+                "blob": {"$binary": "AP8="},
+                "ts": {"$timestamp": {"t": 1, "i": 2}}
+            })
+        );
+        assert_eq!(hints.get("/id"), Some(BsonType::ObjectId));
+        assert_eq!(hints.get("/views"), Some(BsonType::Int64));
+        assert_eq!(hints.get("/likes"), None);
+    }
+
+    #[test]
+    fn test_wrap_restores_types() {
+        let source = json!([{
+            "id": {"$oid": "65a1b2c3d4e5f60718293a4b"},
+            "at": {"$date": "2024-01-01T00:00:00Z"},
+            "n": {"$numberLong": "5"},
+            "tags": [{"$oid": "65a1b2c3d4e5f60718293a4c"}]
+        }]);
+        let mut hints = TypeHints::default();
+        let value = unwrap(source.clone(), &mut hints);
+        let mut relaxed = source.clone();
+        // Relaxed mode writes 64-bit integers that fit a double as plain numbers
+        relaxed[0]["n"] = json!(5);
+        assert_eq!(wrap(&value, false, &hints), relaxed);
+        assert_eq!(
+            wrap(&value, true, &hints)[0],
+            json!({
+                "id": {"$oid": "65a1b2c3d4e5f60718293a4b"},
+                "at": {"$date": {"$numberLong": "1704067200000"}},
+                "n": {"$numberLong": "5"},
+                "tags": [{"$oid": "65a1b2c3d4e5f60718293a4c"}]
+            })
+        );
+        // Values that no longer fit the remembered type stay as they are
+        assert_eq!(
+            wrap(&json!([{"id": "not-an-id"}]), false, &hints),
+            json!([{"id": "not-an-id"}])
+        );
+    }
+
+    #[test]
+    fn test_conflicting_types_are_not_rewrapped() {
+        let mut hints = TypeHints::default();
+        unwrap(
+            json!([{"x": {"$oid": "65a1b2c3d4e5f60718293a4b"}}, {"x": {"$numberDecimal": "1"}}]),
+            &mut hints,
+        );
+        assert_eq!(hints.get("/*/x"), None);
+    }
+
+    #[test]
+    fn test_hints_follow_paths_not_names() {
+        let (value, hints) =
+            parse_typed(r#"{"n": {"$numberLong": "5"}, "tags": [{"n": 1}]}"#).unwrap();
+        assert_eq!(
+            wrap(&value, true, &hints),
+            json!({"n": {"$numberLong": "5"}, "tags": [{"n": {"$numberInt": "1"}}]})
+        );
+
+        let (value, hints) = parse_typed(
+            r#"{"users": [{"_id": {"$oid": "65a1b2c3d4e5f60718293a4b"}, "name": "Ada"}]}"#,
+        )
+        .unwrap();
+        let user = hints.select(&parse_query(".users[0]").unwrap());
+        assert_eq!(
+            wrap(&value["users"][0], false, &user),
+            json!({"_id": {"$oid": "65a1b2c3d4e5f60718293a4b"}, "name": "Ada"})
+        );
+        let users = hints.select(&parse_query(".users[name == \"Ada\"]").unwrap());
+        assert_eq!(users.get("/*/_id"), Some(BsonType::ObjectId));
+        assert!(hints.select(&parse_query(".users[*]").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_parse_mongoexport_lines() {
+        let lines = "{\"id\":{\"$oid\":\"65a1b2c3d4e5f60718293a4b\"}}\n{\"id\":{\"$oid\":\"65a1b2c3d4e5f60718293a4c\"}}\n";
+        assert_eq!(
+            parse(lines).unwrap(),
+            json!([{"id": "65a1b2c3d4e5f60718293a4b"}, {"id": "65a1b2c3d4e5f60718293a4c"}])
+        );
+    }
+}
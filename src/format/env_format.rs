@@ -0,0 +1,237 @@
+//! `.env` files: `KEY=value` lines, read into a flat object of strings.
+//!
+//! Follows the common dotenv conventions: `#` comments, an optional
+//! `export` prefix, single-quoted values taken literally, and double-quoted
+//! values with `\n`, `\t`, `\"` and `\\` escapes; both kinds of quoted value
+//! may span lines. Unquoted values end at a ` #` comment. Variables are not
+//! expanded, so `${HOME}` stays as written.
+
+use anyhow::{bail, Result};
+use serde_json::{Map, Value};
+
+/// Parse a `.env` file into an object.
+pub fn parse(content: &str) -> Result<Value> {
+    let mut obj = Map::new();
+    let mut rest = content.trim_start_matches('\u{feff}');
+    let mut line_no = 1;
+    while !rest.is_empty() {
+        let (line, next) = rest.split_once('\n').unwrap_or((rest, ""));
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            rest = next;
+            line_no += 1;
+            continue;
+        }
+
+        let assignment = trimmed.strip_prefix("export ").unwrap_or(trimmed);
+        let Some((key, _)) = assignment.split_once('=') else {
+            bail!(
+                "line {line_no}: expected KEY=value, found {trimmed:?}.\n\
+                 Hint: .env lines look like `NAME=value`"
+            );
+        };
+        let key = key.trim();
+        if !is_key(key) {
+            bail!("line {line_no}: invalid variable name {key:?}");
+        }
+
+        // A quoted value may run over several lines
+        let value_start = line.find('=').unwrap_or_default() + 1;
+        let (value, after) = value(&rest[value_start..])
+            .map_err(|e| anyhow::anyhow!("line {line_no}: {e} for {key}"))?;
+        line_no += rest[..rest.len() - after.len()].matches('\n').count();
+        obj.insert(key.to_string(), Value::String(value));
+        rest = after;
+    }
+    Ok(Value::Object(obj))
+}
+
+/// Whether `key` is a valid variable name.
+fn is_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+/// Read a value from the text after `=`, returning it with the text that
+/// follows its line.
+fn value(s: &str) -> Result<(String, &str)> {
+    let s = s.trim_start_matches([' ', '\t']);
+    let Some(quote @ ('"' | '\'')) = s.chars().next() else {
+        let (line, next) = s.split_once('\n').unwrap_or((s, ""));
+        let line = line.trim_end_matches('\r');
+        let value = match line.find(" #").or_else(|| line.find("\t#")) {
+            Some(comment) => &line[..comment],
+            None => line,
+        };
+        return Ok((value.trim().to_string(), next));
+    };
+
+    let mut value = String::new();
+    let mut chars = s[1..].char_indices();
+    let end = loop {
+        match chars.next() {
+            Some((i, c)) if c == quote => break i + 2,
+            Some((_, '\\')) if quote == '"' => match chars.next() {
+                Some((_, 'n')) => value.push('\n'),
+                Some((_, 't')) => value.push('\t'),
+                Some((_, 'r')) => value.push('\r'),
+                Some((_, c @ ('"' | '\\'))) => value.push(c),
+                Some((_, c)) => value.extend(['\\', c]),
+                None => bail!("unterminated quoted value"),
+            },
+            Some((_, c)) => value.push(c),
+            None => bail!("unterminated quoted value"),
+        }
+    };
+    let after = &s[end..];
+    let (tail, next) = after.split_once('\n').unwrap_or((after, ""));
+    let tail = tail.trim();
+    if !tail.is_empty() && !tail.starts_with('#') {
+        bail!("unexpected text {tail:?} after the closing quote");
+    }
+    Ok((value, next))
+}
+
+/// Serialize a flat object as `.env` lines. Values that need it are quoted:
+/// single quotes when possible, double quotes with escapes otherwise.
+pub fn serialize(value: &Value) -> Result<String> {
+    let Value::Object(map) = value else {
+        bail!(".env output requires an object of variables");
+    };
+    let mut out = String::new();
+    for (key, value) in map {
+        if !is_key(key) {
+            bail!("{key:?} is not a valid environment variable name");
+        }
+        let text = match value {
+            Value::Null => String::new(),
+            Value::String(s) => s.clone(),
+            Value::Bool(_) | Value::Number(_) => value.to_string(),
+            Value::Array(_) | Value::Object(_) => bail!(
+                "cannot write {key} as an environment variable: it is not a scalar.\n\
+                 Hint: flatten nested values first, e.g. with :flatten_keys sep=_"
+            ),
+        };
+        out.push_str(key);
+        out.push('=');
+        out.push_str(&quote(&text));
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn quote(s: &str) -> String {
+    let plain = s
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "_-.,/:@+%".contains(c));
+    if plain {
+        s.to_string()
+    } else if !s.contains(['\'', '\n', '\r']) {
+        format!("'{s}'")
+    } else {
+        let mut out = String::from("\"");
+        for c in s.chars() {
+            match c {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+        out
+    }
+}
+
+/// Whether every meaningful line is a `KEY=value` assignment with a
+/// variable name, and at least one is.
+pub fn looks_like_env(content: &str) -> bool {
+    let mut lines = content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .peekable();
+    lines.peek().is_some()
+        && lines.all(|line| {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            line.split_once('=')
+                .is_some_and(|(key, _)| key == key.trim() && is_key(key) && !key.contains('.'))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_env() {
+        let content = "# database\n\
+                       export DB_HOST=localhost\n\
+                       DB_PORT = 5432   # default\n\
+                       EMPTY=\n\
+                       URL=http://x/#top\n\
+                       SINGLE='no $EXPANSION \\n here'\n\
+                       MULTI=\"line one\n\
+                       line \\\"two\\\"\"\n\
+                       AFTER=1\n";
+        assert_eq!(
+            parse(content).unwrap(),
+            json!({
+                "DB_HOST": "localhost",
+                "DB_PORT": "5432",
+                "EMPTY": "",
+                "URL": "http://x/#top",
+                "SINGLE": "no $EXPANSION \\n here",
+                "MULTI": "line one\nline \"two\"",
+                "AFTER": "1"
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_errors_name_the_line() {
+        let err = parse("A=1\nB=\"x\ny\n\nnot an assignment").unwrap_err();
+        assert!(err.to_string().contains("line 2: unterminated"), "{err}");
+        let err = parse("A=1\n\nnot an assignment\n").unwrap_err();
+        assert!(err.to_string().starts_with("line 3:"), "{err}");
+        let err = parse("A=\"x\"\nB=\"y\nz\"\n1X=2\n").unwrap_err();
+        assert!(err.to_string().starts_with("line 4:"), "{err}");
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let value = json!({
+            "A": "plain-value",
+            "B": "has space",
+            "C": "it's \"quoted\"\nand multi-line",
+            "D": 3,
+            "E": null
+        });
+        let text = serialize(&value).unwrap();
+        assert!(text.contains("B='has space'\n"), "{text}");
+        assert_eq!(
+            parse(&text).unwrap(),
+            json!({
+                "A": "plain-value",
+                "B": "has space",
+                "C": "it's \"quoted\"\nand multi-line",
+                "D": "3",
+                "E": ""
+            })
+        );
+        assert!(serialize(&json!({"A": {"b": 1}})).is_err());
+    }
+
+    #[test]
+    fn test_looks_like_env() {
+        assert!(looks_like_env("# c\nexport A=1\nB_2='x y'\n"));
+        assert!(!looks_like_env("a.b=1\n"));
+        assert!(!looks_like_env("a = 1\n"));
+        assert!(!looks_like_env("[section]\na=1\n"));
+    }
+}
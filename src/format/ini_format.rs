@@ -0,0 +1,230 @@
+//! INI files: `[section]` headers followed by `key = value` (or `key: value`)
+//! lines. Each section becomes a nested object; keys before the first
+//! section stay at the top level.
+//!
+//! Values are strings, with one pair of surrounding quotes removed. A key
+//! with no value is `null`. Lines starting with `;` or `#` are comments, as is
+//! the rest of an unquoted value after ` ;` or ` #`. A section that appears
+//! twice is merged, and a repeated key keeps its last value.
+
+use anyhow::{bail, Result};
+use serde_json::{Map, Value};
+
+/// Parse an INI file into an object of sections.
+pub fn parse(content: &str) -> Result<Value> {
+    let mut root = Map::new();
+    let mut section: Option<String> = None;
+    for (i, line) in content.trim_start_matches('\u{feff}').lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with([';', '#']) {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[') {
+            let Some(name) = header.strip_suffix(']') else {
+                bail!("line {}: unclosed section header {line:?}", i + 1);
+            };
+            let name = name.trim().to_string();
+            match root
+                .entry(name.clone())
+                .or_insert_with(|| Value::Object(Map::new()))
+            {
+                Value::Object(_) => {}
+                _ => bail!(
+                    "line {}: section [{name}] has the same name as a key above it",
+                    i + 1
+                ),
+            }
+            section = Some(name);
+            continue;
+        }
+
+        let (key, value) = match line.find(['=', ':']) {
+            Some(at) => (line[..at].trim(), Some(value(&line[at + 1..]))),
+            None => (line, None),
+        };
+        if key.is_empty() {
+            bail!("line {}: missing key before {line:?}", i + 1);
+        }
+        let target = match &section {
+            Some(name) => match root.get_mut(name) {
+                Some(Value::Object(map)) => map,
+                _ => unreachable!("sections are inserted as objects"),
+            },
+            None => &mut root,
+        };
+        target.insert(key.to_string(), value.map_or(Value::Null, Value::String));
+    }
+    Ok(Value::Object(root))
+}
+
+fn value(raw: &str) -> String {
+    let raw = raw.trim();
+    for quote in ['"', '\''] {
+        if let Some(inner) = raw
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return inner.to_string();
+        }
+    }
+    let end = [" ;", " #", "\t;", "\t#"]
+        .iter()
+        .filter_map(|marker| raw.find(marker))
+        .min()
+        .unwrap_or(raw.len());
+    raw[..end].trim_end().to_string()
+}
+
+/// Serialize an object as INI: scalar entries first, then one section per
+/// nested object. Sections may only hold scalars.
+pub fn serialize(value: &Value) -> Result<String> {
+    let Value::Object(map) = value else {
+        bail!("INI output requires an object of sections");
+    };
+    let mut out = String::new();
+    let mut sections = Vec::new();
+    for (key, value) in map {
+        match value {
+            Value::Object(entries) => sections.push((key, entries)),
+            _ => write_entry(&mut out, key, value)?,
+        }
+    }
+    for (name, entries) in sections {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("[{name}]\n"));
+        for (key, value) in entries {
+            if value.is_object() {
+                bail!(
+                    "cannot write [{name}] {key}: INI sections cannot be nested.\n\
+                     Hint: flatten it first, e.g. with `.{name} :flatten_keys`"
+                );
+            }
+            write_entry(&mut out, key, value)?;
+        }
+    }
+    Ok(out)
+}
+
+fn write_entry(out: &mut String, key: &str, value: &Value) -> Result<()> {
+    if key.is_empty() || key.contains(['=', ':', '[', '\n']) || key.starts_with([';', '#']) {
+        bail!("{key:?} cannot be written as an INI key");
+    }
+    let text = match value {
+        Value::Null => {
+            out.push_str(&format!("{key}\n"));
+            return Ok(());
+        }
+        Value::String(s) => s.clone(),
+        Value::Array(_) => bail!(
+            "cannot write {key}: INI has no arrays.\n\
+             Hint: join the items first, e.g. with :join \",\""
+        ),
+        other => other.to_string(),
+    };
+    if text.contains(['\n', '\r']) {
+        bail!("cannot write {key}: INI values cannot span lines");
+    }
+    // Quote values the parser would otherwise trim, unquote or cut at a comment
+    let needs_quotes = text.is_empty()
+        || text != text.trim()
+        || text.starts_with(['"', '\''])
+        || text.contains([';', '#']);
+    if needs_quotes {
+        out.push_str(&format!("{key} = \"{text}\"\n"));
+    } else {
+        out.push_str(&format!("{key} = {text}\n"));
+    }
+    Ok(())
+}
+
+/// Whether the content is laid out as INI: at least one `[section]` header,
+/// and every other meaningful line a comment or a key, with or without a
+/// value.
+pub fn looks_like_ini(content: &str) -> bool {
+    let mut has_section = false;
+    let all_ini = content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with([';', '#']))
+        .all(|line| {
+            if line.starts_with('[') {
+                has_section = true;
+                return line.ends_with(']') && !line.starts_with("[[") && !line.contains(',');
+            }
+            let key = line.split(['=', ':']).next().unwrap_or_default().trim();
+            !key.is_empty() && !key.contains([' ', '"', '{', '[', ','])
+        });
+    all_ini && has_section
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_ini() {
+        let content = "; global settings\n\
+                       name = demo\n\
+                       \n\
+                       [server]\n\
+                       host=0.0.0.0\n\
+                       port: 8080 ; http\n\
+                       banner = \"  hi ; there  \"\n\
+                       debug\n\
+                       [paths]\n\
+                       \tdata = /var/lib/demo#1\n\
+                       [server]\n\
+                       port = 9090\n";
+        assert_eq!(
+            parse(content).unwrap(),
+            json!({
+                "name": "demo",
+                "server": {
+                    "host": "0.0.0.0",
+                    "port": "9090",
+                    "banner": "  hi ; there  ",
+                    "debug": null
+                },
+                "paths": {"data": "/var/lib/demo#1"}
+            })
+        );
+        assert!(parse("[open\na=1").is_err());
+        assert!(parse("server=1\n[server]\n").is_err());
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let value = json!({
+            "title": "x",
+            "db": {"user": "admin", "pass": "p;w", "port": 5432, "ssl": true, "note": null, "empty": ""}
+        });
+        let text = serialize(&value).unwrap();
+        assert_eq!(
+            text,
+            "title = x\n\n[db]\nempty = \"\"\nnote\npass = \"p;w\"\nport = 5432\nssl = true\nuser = admin\n"
+        );
+        assert_eq!(
+            parse(&text).unwrap(),
+            json!({
+                "title": "x",
+                "db": {"user": "admin", "pass": "p;w", "port": "5432", "ssl": "true", "note": null, "empty": ""}
+            })
+        );
+        assert!(serialize(&json!({"a": {"b": {"c": 1}}})).is_err());
+        assert!(serialize(&json!({"a": [1]})).is_err());
+    }
+
+    #[test]
+    fn test_looks_like_ini() {
+        assert!(looks_like_ini("; c\n[a]\nx = hello world\n"));
+        assert!(looks_like_ini(
+            "[core]\n\tbare = true\n[remote]\nurl = git@x:y\n"
+        ));
+        assert!(!looks_like_ini("a = 1\n"));
+        assert!(!looks_like_ini("[[items]]\nname = \"x\"\n"));
+        assert!(!looks_like_ini("[1, 2, 3]"));
+    }
+}
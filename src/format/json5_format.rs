@@ -0,0 +1,68 @@
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+
+use super::json_fmt::error_snippet;
+
+/// Parse JSON5 (a superset of JSONC) into a serde_json::Value: comments,
+/// trailing commas, unquoted keys, single-quoted strings, hex numbers.
+/// `NaN` and `Infinity` have no JSON equivalent and become `null`.
+pub fn parse(content: &str) -> Result<Value> {
+    json5::from_str(content).map_err(|e| {
+        let json5::Error::Message { msg, location } = e;
+        // pest renders its own multi-line report; keep only the "= expected …" part
+        let reason = msg
+            .lines()
+            .find_map(|l| l.trim_start().strip_prefix("= "))
+            .unwrap_or(&msg)
+            .to_string();
+        match location {
+            Some(loc) => anyhow!(
+                "invalid JSON5 at line {}, column {}: {reason}\n{}",
+                loc.line,
+                loc.column,
+                error_snippet(content, loc.line, loc.column)
+            ),
+            None => anyhow!("invalid JSON5: {reason}"),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_jsonc() {
+        let content = r#"{
+  // editor settings
+  "editor.tabSize": 2,
+  /* block
+     comment */
+  "files.exclude": {"**/.git": true,},
+}"#;
+        assert_eq!(
+            parse(content).unwrap(),
+            json!({"editor.tabSize": 2, "files.exclude": {"**/.git": true}})
+        );
+    }
+
+    #[test]
+    fn test_parse_json5_syntax() {
+        let content = "{unquoted: 'single', hex: 0x1F, half: .5, list: [1, 2,], inf: Infinity}";
+        assert_eq!(
+            parse(content).unwrap(),
+            json!({"unquoted": "single", "hex": 31, "half": 0.5, "list": [1, 2], "inf": null})
+        );
+    }
+
+    #[test]
+    fn test_parse_error_location() {
+        let err = parse("{\n  a: 1,\n  b: ,\n}").unwrap_err().to_string();
+        assert!(
+            err.starts_with("invalid JSON5 at line 3, column 6"),
+            "{err}"
+        );
+        assert!(err.contains("  3 |   b: ,\n    |      ^"), "{err}");
+    }
+}
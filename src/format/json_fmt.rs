@@ -1,10 +1,55 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use serde_json::Value;
 
 /// Parse a JSON string into a serde_json::Value.
 pub fn parse(content: &str) -> Result<Value> {
-    let value: Value = serde_json::from_str(content)?;
-    Ok(value)
+    serde_json::from_str(content).map_err(|e| {
+        let reason = e.to_string();
+        // serde_json appends " at line L column C"; the snippet shows that
+        let reason = reason
+            .rsplit_once(" at line ")
+            .map_or(reason.as_str(), |(r, _)| r);
+        anyhow!(
+            "invalid JSON at line {}, column {}: {reason}\n{}",
+            e.line(),
+            e.column(),
+            error_snippet(content, e.line(), e.column())
+        )
+    })
+}
+
+/// Render the source line at a one-based `line`/`column` with a caret under
+/// the column, for parse error messages:
+///
+/// ```text
+///   3 |   "b": ,
+///     |        ^
+/// ```
+pub(crate) fn error_snippet(content: &str, line: usize, column: usize) -> String {
+    let Some(source) = content.lines().nth(line.saturating_sub(1)) else {
+        return String::new();
+    };
+    // Keep long (e.g. minified) lines readable: show up to 60 chars around the column
+    let chars: Vec<char> = source.chars().collect();
+    let col = column.saturating_sub(1).min(chars.len());
+    let start = col.saturating_sub(40);
+    let end = (col + 20).min(chars.len());
+    let mut shown: String = chars[start..end].iter().collect();
+    let mut caret = col - start;
+    if start > 0 {
+        shown.insert(0, '…');
+        caret += 1;
+    }
+    if end < chars.len() {
+        shown.push('…');
+    }
+    let gutter = line.to_string().len();
+    format!(
+        "{line:>width$} | {shown}\n{:>width$} | {}^",
+        "",
+        " ".repeat(caret),
+        width = gutter + 2
+    )
 }
 
 #[cfg(test)]
@@ -29,4 +74,13 @@ mod tests {
         let result = parse("{invalid}");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_parse_error_shows_line_column_and_snippet() {
+        let err = parse("{\n  \"a\": 1,\n  \"b\": ,\n}")
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("invalid JSON at line 3, column 8"), "{err}");
+        assert!(err.contains("  3 |   \"b\": ,\n    |        ^"), "{err}");
+    }
 }
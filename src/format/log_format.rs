@@ -0,0 +1,536 @@
+//! Line-oriented log formats. Every non-empty line is one record, so these
+//! inputs can be streamed from stdin like NDJSON.
+//!
+//! - **logfmt** (`level=info msg="x" dur=3ms`): one key per pair. Unquoted
+//!   numbers and booleans are typed, quoted values stay strings, and a key
+//!   without `=` is `true`.
+//! - **Access logs** in the Apache/nginx common and combined formats: the
+//!   time becomes ISO 8601, the request line is split into `method`, `path`
+//!   and `protocol`, and `-` fields are `null`. Fields after the user agent
+//!   are kept in `extra`.
+//! - **Syslog** (RFC 5424): the header fields, the priority split into
+//!   `facility` and `severity` names, structured data as
+//!   `{sd_id: {param: value}}` and the message.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::DateTime;
+use serde_json::{Map, Value};
+
+use super::csv_format::infer_value;
+
+/// How many lines are checked when detecting a log format.
+const DETECT_LINES: usize = 5;
+
+const FACILITIES: [&str; 24] = [
+    "kern",
+    "user",
+    "mail",
+    "daemon",
+    "auth",
+    "syslog",
+    "lpr",
+    "news",
+    "uucp",
+    "cron",
+    "authpriv",
+    "ftp",
+    "ntp",
+    "security",
+    "console",
+    "solaris-cron",
+    "local0",
+    "local1",
+    "local2",
+    "local3",
+    "local4",
+    "local5",
+    "local6",
+    "local7",
+];
+
+const SEVERITIES: [&str; 8] = [
+    "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+/// Parse every non-empty line with `parse_line` into an array of records.
+pub fn parse_lines(content: &str, parse_line: fn(&str) -> Result<Value>) -> Result<Value> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| parse_line(line).map_err(|e| anyhow!("line {}: {e}", i + 1)))
+        .collect::<Result<Vec<_>>>()
+        .map(Value::Array)
+}
+
+/// Whether the first lines all pass `check`.
+pub fn all_lines(content: &str, check: fn(&str) -> bool) -> bool {
+    let mut lines = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .take(DETECT_LINES)
+        .peekable();
+    lines.peek().is_some() && lines.all(check)
+}
+
+// ---------------------------------------------------------------------------
+// logfmt
+// ---------------------------------------------------------------------------
+
+/// A logfmt pair: the key, and the value with whether it was quoted.
+type Pair = (String, Option<(String, bool)>);
+
+/// Parse one logfmt line into an object.
+pub fn parse_logfmt_line(line: &str) -> Result<Value> {
+    let mut obj = Map::new();
+    for (key, value) in logfmt_pairs(line)? {
+        let value = match value {
+            None => Value::Bool(true),
+            Some((text, true)) => Value::String(text),
+            Some((text, false)) => infer_value(&text),
+        };
+        obj.insert(key, value);
+    }
+    Ok(Value::Object(obj))
+}
+
+/// Whether a line looks like logfmt: at least two `key=value` pairs with
+/// plain keys. A single pair per line is more likely a `.env` or TOML file.
+pub fn is_logfmt_line(line: &str) -> bool {
+    let plain_key = |key: &str| {
+        key.chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | '@'))
+    };
+    logfmt_pairs(line).is_ok_and(|pairs| {
+        pairs.len() >= 2
+            && pairs
+                .iter()
+                .all(|(key, value)| value.is_some() && plain_key(key))
+    })
+}
+
+fn logfmt_pairs(line: &str) -> Result<Vec<Pair>> {
+    let mut pairs = Vec::new();
+    let mut chars = line.trim().chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            return Ok(pairs);
+        }
+
+        let mut key = String::new();
+        while let Some(c) = chars.next_if(|&c| !c.is_whitespace() && c != '=') {
+            if c == '"' {
+                bail!("unexpected quote in logfmt key {key:?}");
+            }
+            key.push(c);
+        }
+        if key.is_empty() {
+            bail!("expected a logfmt key before '='");
+        }
+        if chars.next_if_eq(&'=').is_none() {
+            pairs.push((key, None));
+            continue;
+        }
+
+        if chars.next_if_eq(&'"').is_none() {
+            let value: String =
+                std::iter::from_fn(|| chars.next_if(|c| !c.is_whitespace())).collect();
+            pairs.push((key, Some((value, false))));
+            continue;
+        }
+        let mut value = String::new();
+        loop {
+            match chars.next() {
+                Some('"') => break,
+                Some('\\') => match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some('r') => value.push('\r'),
+                    Some(c) => value.push(c),
+                    None => bail!("unterminated quoted value for logfmt key {key:?}"),
+                },
+                Some(c) => value.push(c),
+                None => bail!("unterminated quoted value for logfmt key {key:?}"),
+            }
+        }
+        if chars.peek().is_some_and(|c| !c.is_whitespace()) {
+            bail!("expected a space after the quoted value of logfmt key {key:?}");
+        }
+        pairs.push((key, Some((value, true))));
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Access logs
+// ---------------------------------------------------------------------------
+
+/// A field of an access log line.
+enum Field {
+    Bare(String),
+    Bracketed(String),
+    Quoted(String),
+}
+
+impl Field {
+    fn into_value(self) -> Value {
+        match self {
+            Field::Bare(s) | Field::Bracketed(s) | Field::Quoted(s) => dash_null(s),
+        }
+    }
+}
+
+/// Parse one common or combined access log line into an object.
+pub fn parse_access_line(line: &str) -> Result<Value> {
+    let invalid = || {
+        anyhow!(
+            "not a common or combined access log line.\n\
+             Hint: expected `host ident user [time] \"request\" status bytes`"
+        )
+    };
+    let mut fields = access_fields(line.trim()).ok_or_else(invalid)?.into_iter();
+    let (
+        Some(host),
+        Some(ident),
+        Some(user),
+        Some(Field::Bracketed(time)),
+        Some(Field::Quoted(request)),
+        Some(Field::Bare(status)),
+        Some(Field::Bare(bytes)),
+    ) = (
+        fields.next(),
+        fields.next(),
+        fields.next(),
+        fields.next(),
+        fields.next(),
+        fields.next(),
+        fields.next(),
+    )
+    else {
+        return Err(invalid());
+    };
+    let status: u16 = status.parse().map_err(|_| invalid())?;
+    let bytes = match bytes.as_str() {
+        "-" => Value::Null,
+        n => Value::from(n.parse::<u64>().map_err(|_| invalid())?),
+    };
+
+    let mut obj = Map::new();
+    obj.insert("host".into(), host.into_value());
+    obj.insert("ident".into(), ident.into_value());
+    obj.insert("user".into(), user.into_value());
+    let time =
+        DateTime::parse_from_str(&time, "%d/%b/%Y:%H:%M:%S %z").map_or(time, |t| t.to_rfc3339());
+    obj.insert("time".into(), Value::String(time));
+
+    let parts: Vec<&str> = request.split(' ').collect();
+    let (method, path, protocol) = match parts.as_slice() {
+        [method, path, protocol] => (Some(*method), Some(*path), Some(*protocol)),
+        // HTTP/0.9 requests have no protocol
+        [method, path] => (Some(*method), Some(*path), None),
+        _ => {
+            obj.insert("request".into(), dash_null(request.clone()));
+            (None, None, None)
+        }
+    };
+    obj.insert("method".into(), method.into());
+    obj.insert("path".into(), path.into());
+    obj.insert("protocol".into(), protocol.into());
+    obj.insert("status".into(), Value::from(status));
+    obj.insert("bytes".into(), bytes);
+
+    // The combined format adds the referer and user agent
+    let rest: Vec<Field> = fields.collect();
+    let mut rest = rest.into_iter().peekable();
+    if let Some(Field::Quoted(_)) = rest.peek() {
+        obj.insert("referer".into(), rest.next().unwrap().into_value());
+        if let Some(Field::Quoted(_)) = rest.peek() {
+            obj.insert("user_agent".into(), rest.next().unwrap().into_value());
+        }
+    }
+    let extra: Vec<Value> = rest.map(Field::into_value).collect();
+    if !extra.is_empty() {
+        obj.insert("extra".into(), Value::Array(extra));
+    }
+    Ok(Value::Object(obj))
+}
+
+/// Whether a line looks like a common or combined access log line.
+pub fn is_access_line(line: &str) -> bool {
+    parse_access_line(line).is_ok()
+}
+
+/// Split an access log line into bare, `[bracketed]` and `"quoted"` fields.
+/// Quoted fields may escape characters with `\`, including nginx's `\xHH`.
+fn access_fields(line: &str) -> Option<Vec<Field>> {
+    let mut fields = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars.next_if_eq(&' ').is_some() {}
+        match chars.next() {
+            None => return Some(fields),
+            Some('[') => {
+                let text: String = std::iter::from_fn(|| chars.next_if(|&c| c != ']')).collect();
+                chars.next()?;
+                fields.push(Field::Bracketed(text));
+            }
+            Some('"') => {
+                let mut text = String::new();
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => match chars.next()? {
+                            'x' => {
+                                let hex: String = chars.by_ref().take(2).collect();
+                                let byte = u8::from_str_radix(&hex, 16).ok()?;
+                                text.push(char::from(byte));
+                            }
+                            c => text.push(c),
+                        },
+                        c => text.push(c),
+                    }
+                }
+                fields.push(Field::Quoted(text));
+            }
+            Some(first) => {
+                let rest = std::iter::from_fn(|| chars.next_if(|&c| c != ' '));
+                fields.push(Field::Bare(std::iter::once(first).chain(rest).collect()));
+            }
+        }
+    }
+}
+
+fn dash_null(s: String) -> Value {
+    if s == "-" {
+        Value::Null
+    } else {
+        Value::String(s)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Syslog
+// ---------------------------------------------------------------------------
+
+/// Parse one RFC 5424 syslog line into an object.
+pub fn parse_syslog_line(line: &str) -> Result<Value> {
+    let invalid = || {
+        anyhow!(
+            "not an RFC 5424 syslog line.\n\
+             Hint: expected `<PRI>1 TIMESTAMP HOST APP PROCID MSGID [SD] MSG`"
+        )
+    };
+    let rest = line.trim_end().strip_prefix('<').ok_or_else(invalid)?;
+    let (priority, rest) = rest.split_once('>').ok_or_else(invalid)?;
+    let priority: u8 = priority
+        .parse()
+        .ok()
+        .filter(|&p| p < 192 && priority.len() <= 3)
+        .ok_or_else(invalid)?;
+
+    let mut header = rest.splitn(7, ' ');
+    let mut field = || header.next().filter(|f| !f.is_empty()).ok_or_else(invalid);
+    let version: u8 = field()?.parse().map_err(|_| invalid())?;
+    let timestamp = field()?;
+    let hostname = field()?;
+    let app_name = field()?;
+    let procid = field()?;
+    let msgid = field()?;
+    let rest = header.next().ok_or_else(invalid)?;
+    if version == 0 {
+        return Err(invalid());
+    }
+
+    let (structured_data, rest) = structured_data(rest)?;
+    let message = match rest {
+        "" => Value::Null,
+        // The message may be marked as UTF-8 with a byte order mark
+        _ => match rest.strip_prefix(' ') {
+            Some(msg) => Value::String(msg.trim_start_matches('\u{feff}').to_string()),
+            None => return Err(invalid()),
+        },
+    };
+
+    let mut obj = Map::new();
+    obj.insert("priority".into(), Value::from(priority));
+    obj.insert(
+        "facility".into(),
+        Value::from(FACILITIES[usize::from(priority / 8)]),
+    );
+    obj.insert(
+        "severity".into(),
+        Value::from(SEVERITIES[usize::from(priority % 8)]),
+    );
+    obj.insert("version".into(), Value::from(version));
+    for (key, value) in [
+        ("timestamp", timestamp),
+        ("hostname", hostname),
+        ("app_name", app_name),
+        ("procid", procid),
+        ("msgid", msgid),
+    ] {
+        obj.insert(key.into(), dash_null(value.to_string()));
+    }
+    obj.insert("structured_data".into(), structured_data);
+    obj.insert("message".into(), message);
+    Ok(Value::Object(obj))
+}
+
+/// Whether a line looks like an RFC 5424 syslog line.
+pub fn is_syslog_line(line: &str) -> bool {
+    parse_syslog_line(line).is_ok()
+}
+
+/// Parse the structured data element(s) at the start of `s`, returning them
+/// with the rest of the line. `-` means there is none.
+fn structured_data(s: &str) -> Result<(Value, &str)> {
+    if let Some(rest) = s.strip_prefix('-') {
+        return Ok((Value::Null, rest));
+    }
+    let invalid = || anyhow!("invalid syslog structured data in {s:?}");
+    let mut elements = Map::new();
+    let mut rest = s;
+    while let Some(element) = rest.strip_prefix('[') {
+        let end = element.find([' ', ']']).ok_or_else(invalid)?;
+        let (id, mut params_text) = element.split_at(end);
+        let mut params = Map::new();
+        while let Some(param) = params_text.strip_prefix(' ') {
+            let (name, value) = param.split_once("=\"").ok_or_else(invalid)?;
+            let mut text = String::new();
+            let mut chars = value.char_indices();
+            let close = loop {
+                match chars.next().ok_or_else(invalid)? {
+                    (i, '"') => break i,
+                    (_, '\\') => match chars.next().ok_or_else(invalid)?.1 {
+                        c @ ('"' | '\\' | ']') => text.push(c),
+                        c => text.extend(['\\', c]),
+                    },
+                    (_, c) => text.push(c),
+                }
+            };
+            params.insert(name.to_string(), Value::String(text));
+            params_text = &value[close + 1..];
+        }
+        rest = params_text.strip_prefix(']').ok_or_else(invalid)?;
+        elements.insert(id.to_string(), Value::Object(params));
+    }
+    if elements.is_empty() {
+        return Err(invalid());
+    }
+    Ok((Value::Object(elements), rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_logfmt() {
+        let line = r#"level=info msg="request done" status=200 dur=3ms id=0042 cached"#;
+        assert_eq!(
+            parse_logfmt_line(line).unwrap(),
+            json!({
+                "level": "info",
+                "msg": "request done",
+                "status": 200,
+                "dur": "3ms",
+                "id": "0042",
+                "cached": true
+            })
+        );
+        assert_eq!(
+            parse_logfmt_line(r#"msg="say \"hi\"\n" n="1""#).unwrap(),
+            json!({"msg": "say \"hi\"\n", "n": "1"})
+        );
+        assert!(parse_logfmt_line(r#"msg="open"#).is_err());
+        assert!(parse_logfmt_line("a = 1").is_err());
+
+        assert!(!is_logfmt_line(line), "bare keys are not detected");
+        assert!(is_logfmt_line("ts=1 level=warn"));
+        assert!(!is_logfmt_line("PORT=8080"));
+        assert!(!is_logfmt_line("just some words"));
+    }
+
+    #[test]
+    fn test_access_log() {
+        let common = r#"127.0.0.1 - frank [10/Oct/2000:13:55:36 -0700] "GET /apache_pb.gif HTTP/1.0" 200 2326"#;
+        assert_eq!(
+            parse_access_line(common).unwrap(),
+            json!({
+                "host": "127.0.0.1",
+                "ident": null,
+                "user": "frank",
+                "time": "2000-10-10T13:55:36-07:00",
+                "method": "GET",
+                "path": "/apache_pb.gif",
+                "protocol": "HTTP/1.0",
+                "status": 200,
+                "bytes": 2326
+            })
+        );
+
+        let combined = r#"10.0.0.2 - - [01/Mar/2024:08:00:01 +0000] "POST /api?q=\x22a\x22 HTTP/1.1" 201 - "-" "curl/8.4.0" 0.012"#;
+        let record = parse_access_line(combined).unwrap();
+        assert_eq!(record["path"], "/api?q=\"a\"");
+        assert_eq!(record["bytes"], Value::Null);
+        assert_eq!(record["referer"], Value::Null);
+        assert_eq!(record["user_agent"], "curl/8.4.0");
+        assert_eq!(record["extra"], json!(["0.012"]));
+
+        let bad_request = r#"1.2.3.4 - - [01/Mar/2024:08:00:01 +0000] "\x16\x03" 400 0"#;
+        let record = parse_access_line(bad_request).unwrap();
+        assert_eq!(record["method"], Value::Null);
+        assert_eq!(record["request"], "\u{16}\u{3}");
+
+        assert!(!is_access_line("level=info msg=x"));
+        assert!(!is_access_line(r#"host - - [time] "GET /" ok 1"#));
+    }
+
+    #[test]
+    fn test_syslog() {
+        let line = "<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 \
+                    [exampleSDID@32473 iut=\"3\" eventSource=\"App\\]lication\"][meta x=\"1\"] \
+                    \u{feff}An application event";
+        assert_eq!(
+            parse_syslog_line(line).unwrap(),
+            json!({
+                "priority": 165,
+                "facility": "local4",
+                "severity": "notice",
+                "version": 1,
+                "timestamp": "2003-10-11T22:14:15.003Z",
+                "hostname": "mymachine.example.com",
+                "app_name": "evntslog",
+                "procid": null,
+                "msgid": "ID47",
+                "structured_data": {
+                    "exampleSDID@32473": {"iut": "3", "eventSource": "App]lication"},
+                    "meta": {"x": "1"}
+                },
+                "message": "An application event"
+            })
+        );
+
+        let bare = parse_syslog_line("<34>1 - host su 42 - -").unwrap();
+        assert_eq!(bare["facility"], "auth");
+        assert_eq!(bare["severity"], "crit");
+        assert_eq!(bare["timestamp"], Value::Null);
+        assert_eq!(bare["structured_data"], Value::Null);
+        assert_eq!(bare["message"], Value::Null);
+
+        assert!(!is_syslog_line(
+            "<34>Oct 11 22:14:15 host su: old BSD format"
+        ));
+        assert!(!is_syslog_line("<root>text</root>"));
+        assert!(parse_syslog_line("<34>1 - host su 42 - [broken").is_err());
+    }
+
+    #[test]
+    fn test_parse_lines_names_the_line() {
+        let err = parse_lines("a=1 b=2\n\nc=\"open\n", parse_logfmt_line).unwrap_err();
+        assert!(err.to_string().starts_with("line 3:"), "{err}");
+        assert!(all_lines("a=1 b=2\n\nc=3 d=4\n", is_logfmt_line));
+        assert!(!all_lines("\n", is_logfmt_line));
+    }
+}
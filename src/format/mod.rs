@@ -1,19 +1,71 @@
+pub mod arrow_format;
+pub(crate) mod binary;
+pub mod bson_format;
+pub mod cbor_format;
 pub mod csv_format;
+pub mod ejson_format;
+pub mod env_format;
+pub mod ini_format;
+pub mod json5_format;
 pub mod json_fmt;
+pub mod log_format;
+pub mod msgpack_format;
+pub mod parquet_format;
+pub mod properties_format;
+pub mod spreadsheet_format;
 pub mod toml_format;
+pub mod xml_format;
 pub mod yaml_format;
 
+use std::borrow::Cow;
+use std::fs::File;
+
 use anyhow::{bail, Result};
+use arrow_schema::Schema;
 use serde_json::Value;
 
+use csv_format::CsvOptions;
+use ejson_format::TypeHints;
+
 /// Supported input/output formats.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DataFormat {
     Json,
+    Json5,
     Yaml,
     Toml,
     Csv,
+    /// Tab-separated values
+    Tsv,
+    /// Pipe-separated values
+    Psv,
     Ndjson,
+    Xml,
+    MsgPack,
+    Cbor,
+    Bson,
+    /// MongoDB Extended JSON, written in relaxed form
+    Ejson,
+    /// MongoDB Extended JSON, written in canonical form
+    EjsonCanonical,
+    /// Apache Parquet (read only)
+    Parquet,
+    /// Arrow IPC file or stream (read only)
+    Arrow,
+    /// XLSX, XLSM, XLSB, XLS or ODS workbook (read only)
+    Spreadsheet,
+    /// `key=value` log lines (read only)
+    Logfmt,
+    /// Apache/nginx common or combined access log (read only)
+    AccessLog,
+    /// RFC 5424 syslog (read only)
+    Syslog,
+    /// `.env` file of `KEY=value` lines
+    Env,
+    /// INI file with `[section]` headers
+    Ini,
+    /// Java `.properties` file
+    Properties,
 }
 
 impl DataFormat {
@@ -21,18 +73,318 @@ impl DataFormat {
     pub fn from_str_name(s: &str) -> Result<Self> {
         match s.to_lowercase().as_str() {
             "json" => Ok(Self::Json),
+            "json5" | "jsonc" => Ok(Self::Json5),
             "yaml" | "yml" => Ok(Self::Yaml),
             "toml" => Ok(Self::Toml),
             "csv" => Ok(Self::Csv),
+            "tsv" | "tab" => Ok(Self::Tsv),
+            "psv" => Ok(Self::Psv),
             "ndjson" | "jsonl" => Ok(Self::Ndjson),
+            "xml" => Ok(Self::Xml),
+            "msgpack" | "messagepack" | "mp" => Ok(Self::MsgPack),
+            "cbor" => Ok(Self::Cbor),
+            "bson" => Ok(Self::Bson),
+            "ejson" | "extjson" | "ejson-relaxed" => Ok(Self::Ejson),
+            "ejson-canonical" => Ok(Self::EjsonCanonical),
+            "parquet" => Ok(Self::Parquet),
+            "arrow" | "ipc" | "feather" => Ok(Self::Arrow),
+            "xlsx" | "xlsm" | "xlsb" | "xls" | "ods" | "spreadsheet" => Ok(Self::Spreadsheet),
+            "logfmt" => Ok(Self::Logfmt),
+            "access-log" | "accesslog" | "clf" | "combined" | "apache" | "nginx" => {
+                Ok(Self::AccessLog)
+            }
+            "syslog" | "rfc5424" => Ok(Self::Syslog),
+            "env" | "dotenv" => Ok(Self::Env),
+            "ini" => Ok(Self::Ini),
+            "properties" | "props" | "java-properties" => Ok(Self::Properties),
             _ => bail!("unknown format: {s}"),
         }
     }
+
+    /// Whether the format is binary rather than text.
+    pub fn is_binary(self) -> bool {
+        matches!(
+            self,
+            Self::MsgPack
+                | Self::Cbor
+                | Self::Bson
+                | Self::Parquet
+                | Self::Arrow
+                | Self::Spreadsheet
+        )
+    }
+
+    /// Whether the format is columnar and read with [`read_table_file`].
+    pub fn is_table(self) -> bool {
+        matches!(self, Self::Parquet | Self::Arrow)
+    }
+
+    /// Whether the format is made for secrets, so its values must not leave
+    /// the machine (the AI panel only sees them redacted).
+    pub fn holds_secrets(self) -> bool {
+        matches!(self, Self::Env)
+    }
+
+    /// Whether each line is a record of its own, so that the input can be
+    /// streamed with [`parse_line`].
+    pub fn is_line_oriented(self) -> bool {
+        matches!(
+            self,
+            Self::Ndjson | Self::Logfmt | Self::AccessLog | Self::Syslog
+        )
+    }
+}
+
+/// How text formats are read and written, usually built from CLI flags.
+#[derive(Debug, Clone, Default)]
+pub struct FormatOptions {
+    /// Dialect of CSV, TSV and PSV
+    pub csv: CsvOptions,
+    /// BSON types of the value's fields, from Extended JSON or BSON input,
+    /// so that writing either format wraps them again
+    pub type_hints: TypeHints,
+}
+
+impl FormatOptions {
+    /// The CSV options for a delimiter-separated format, with the delimiter
+    /// fixed for TSV and PSV.
+    fn csv_for(&self, format: DataFormat) -> Cow<'_, CsvOptions> {
+        match format {
+            DataFormat::Tsv => Cow::Owned(self.csv.with_delimiter(b'\t')),
+            DataFormat::Psv => Cow::Owned(self.csv.with_delimiter(b'|')),
+            _ => Cow::Borrowed(&self.csv),
+        }
+    }
+}
+
+/// What to read from a columnar (Parquet or Arrow) input.
+#[derive(Debug, Clone, Default)]
+pub struct TableOptions {
+    /// Only read these top-level columns; unknown names are ignored
+    pub columns: Option<Vec<String>>,
+    /// Stop after this many rows
+    pub limit: Option<usize>,
+}
+
+impl TableOptions {
+    /// Indices of the requested columns in `schema`, or `None` for all.
+    fn projection(&self, schema: &Schema) -> Option<Vec<usize>> {
+        let columns = self.columns.as_ref()?;
+        Some(
+            schema
+                .fields()
+                .iter()
+                .enumerate()
+                .filter(|(_, field)| columns.contains(field.name()))
+                .map(|(i, _)| i)
+                .collect(),
+        )
+    }
+}
+
+/// Rows read from a columnar input, with the schema declared by the file.
+#[derive(Debug, Clone)]
+pub struct Table {
+    /// An array of row objects
+    pub rows: Value,
+    /// The file's schema, rendered for the schema view
+    pub schema: String,
+}
+
+/// Read a Parquet or Arrow file from disk, loading only what `options` asks for.
+pub fn read_table_file(file: File, format: DataFormat, options: &TableOptions) -> Result<Table> {
+    match format {
+        DataFormat::Parquet => parquet_format::read(file, options),
+        DataFormat::Arrow => arrow_format::read(std::io::BufReader::new(file), options),
+        other => bail!("{other:?} is not a columnar format"),
+    }
+}
+
+/// Read a Parquet or Arrow file that is already in memory.
+pub fn read_table_bytes(bytes: &[u8], format: DataFormat, options: &TableOptions) -> Result<Table> {
+    match format {
+        DataFormat::Parquet => parquet_format::read(bytes::Bytes::copy_from_slice(bytes), options),
+        DataFormat::Arrow => arrow_format::read(std::io::Cursor::new(bytes), options),
+        other => bail!("{other:?} is not a columnar format"),
+    }
+}
+
+/// Recognise Parquet and Arrow IPC from their leading bytes.
+pub fn detect_table_format(bytes: &[u8]) -> Option<DataFormat> {
+    if bytes.starts_with(parquet_format::MAGIC) {
+        Some(DataFormat::Parquet)
+    } else if bytes.starts_with(arrow_format::FILE_MAGIC)
+        || bytes.starts_with(arrow_format::STREAM_MAGIC)
+    {
+        Some(DataFormat::Arrow)
+    } else {
+        None
+    }
+}
+
+/// Auto-detect the format of raw input bytes. Binary formats are recognised
+/// by their leading bytes; anything else is detected as text.
+pub fn detect_format_bytes(bytes: &[u8]) -> DataFormat {
+    if let Some(format) = detect_table_format(bytes) {
+        return format;
+    }
+    if bytes.starts_with(spreadsheet_format::ZIP_MAGIC)
+        || bytes.starts_with(spreadsheet_format::OLE_MAGIC)
+    {
+        return DataFormat::Spreadsheet;
+    }
+    // CBOR self-describe tag 55799
+    if bytes.starts_with(&[0xd9, 0xd9, 0xf7]) {
+        return DataFormat::Cbor;
+    }
+    // Length-prefixed BSON documents can be valid UTF-8, so check first
+    if bson_format::looks_like_bson(bytes) {
+        return DataFormat::Bson;
+    }
+    if let Ok(text) = decode_text(bytes) {
+        return detect_format(&text);
+    }
+    // A document must open with a map or array header and parse in full;
+    // the header ranges overlap, so MessagePack is tried first
+    match bytes.first() {
+        // fixmap / fixarray, array16/32 and map16/32
+        Some(0x80..=0x9f | 0xdc..=0xdf) if msgpack_format::parse(bytes).is_ok() => {
+            DataFormat::MsgPack
+        }
+        // Arrays and maps, definite or indefinite length
+        Some(0x80..=0x9b | 0x9f | 0xa0..=0xbb | 0xbf) if cbor_format::parse(bytes).is_ok() => {
+            DataFormat::Cbor
+        }
+        // Neither: treat it as text so parsing reports the encoding error
+        _ => DataFormat::Json,
+    }
+}
+
+/// Parse raw input bytes with default options. Text formats must be valid
+/// UTF-8.
+pub fn parse_input_bytes(bytes: &[u8], format: DataFormat) -> Result<Value> {
+    parse_input_bytes_with(bytes, format, &FormatOptions::default())
+}
+
+/// Parse raw input bytes. Text formats must be valid UTF-8.
+pub fn parse_input_bytes_with(
+    bytes: &[u8],
+    format: DataFormat,
+    options: &FormatOptions,
+) -> Result<Value> {
+    match format {
+        DataFormat::MsgPack => msgpack_format::parse(bytes),
+        DataFormat::Cbor => cbor_format::parse(bytes),
+        DataFormat::Bson => bson_format::parse(bytes),
+        DataFormat::Parquet | DataFormat::Arrow => {
+            read_table_bytes(bytes, format, &TableOptions::default()).map(|table| table.rows)
+        }
+        DataFormat::Spreadsheet => spreadsheet_format::parse(bytes, &Default::default()),
+        text => match decode_text(bytes) {
+            Ok(content) => parse_input_with(&content, text, options),
+            Err(e) => bail!(
+                "{e}.\n\
+                 Hint: for binary data use --input msgpack, --input cbor or --input bson"
+            ),
+        },
+    }
+}
+
+/// Parse input whose format was detected rather than given with `--input`.
+///
+/// Detection only recognizes JSON5 by a leading comment, so a `{`-document
+/// with comments or trailing commas is detected as JSON; it is retried as
+/// JSON5. If that fails too, the JSON error is kept: the input is far more
+/// likely to be broken JSON than JSON5.
+pub fn parse_detected_bytes_with(
+    bytes: &[u8],
+    format: DataFormat,
+    options: &FormatOptions,
+) -> Result<Value> {
+    match parse_input_bytes_with(bytes, format, options) {
+        Err(e) if format == DataFormat::Json => {
+            parse_input_bytes_with(bytes, DataFormat::Json5, options).map_err(|_| e)
+        }
+        result => result,
+    }
+}
+
+/// Decode text input, honouring a byte order mark: UTF-8 (the mark is
+/// dropped) or UTF-16 in either byte order. Without a mark the input must be
+/// UTF-8.
+pub fn decode_text(bytes: &[u8]) -> Result<Cow<'_, str>> {
+    let utf16 = |rest: &[u8], from: fn([u8; 2]) -> u16| -> Result<Cow<'_, str>> {
+        if rest.len() % 2 != 0 {
+            bail!("input is not valid UTF-16 (odd number of bytes)");
+        }
+        let units: Vec<u16> = rest.chunks_exact(2).map(|c| from([c[0], c[1]])).collect();
+        String::from_utf16(&units)
+            .map(Cow::Owned)
+            .map_err(|e| anyhow::anyhow!("input is not valid UTF-16 ({e})"))
+    };
+    match bytes {
+        [0xef, 0xbb, 0xbf, rest @ ..] => std::str::from_utf8(rest),
+        [0xff, 0xfe, rest @ ..] => return utf16(rest, u16::from_le_bytes),
+        [0xfe, 0xff, rest @ ..] => return utf16(rest, u16::from_be_bytes),
+        _ => std::str::from_utf8(bytes),
+    }
+    .map(Cow::Borrowed)
+    .map_err(|e| anyhow::anyhow!("input is not valid UTF-8 ({e})"))
+}
+
+/// Serialize a value to bytes with default options.
+pub fn format_output_bytes(value: &Value, format: DataFormat) -> Result<Vec<u8>> {
+    format_output_bytes_with(value, format, &FormatOptions::default())
+}
+
+/// Serialize a value to bytes; binary formats are encoded as-is, text
+/// formats as UTF-8.
+pub fn format_output_bytes_with(
+    value: &Value,
+    format: DataFormat,
+    options: &FormatOptions,
+) -> Result<Vec<u8>> {
+    match format {
+        DataFormat::MsgPack => msgpack_format::serialize(value),
+        DataFormat::Cbor => cbor_format::serialize(value),
+        DataFormat::Bson => bson_format::serialize(value, &options.type_hints),
+        DataFormat::Parquet
+        | DataFormat::Arrow
+        | DataFormat::Spreadsheet
+        | DataFormat::Logfmt
+        | DataFormat::AccessLog
+        | DataFormat::Syslog => bail!(
+            "writing {format:?} is not supported.\n\
+             Hint: choose another --output format, such as json or csv"
+        ),
+        text => format_output_with(value, text, options).map(String::into_bytes),
+    }
+}
+
+/// The format implied by a file name, for formats whose content overlaps
+/// with TOML: `.env` (also `.env.local`, `prod.env`), `.ini` and
+/// `.properties`, and for binary formats that have no magic bytes:
+/// `.msgpack`/`.mpk`, `.cbor` and `.bson`. Other formats are detected from
+/// their content.
+pub fn detect_format_from_name(path: &str) -> Option<DataFormat> {
+    let name = std::path::Path::new(path).file_name()?.to_str()?;
+    let extension = name.rsplit_once('.').map(|(_, ext)| ext.to_lowercase());
+    if name == ".env" || name.starts_with(".env.") || extension.as_deref() == Some("env") {
+        return Some(DataFormat::Env);
+    }
+    match extension.as_deref() {
+        Some("ini") => Some(DataFormat::Ini),
+        Some("properties") => Some(DataFormat::Properties),
+        Some("msgpack" | "mpk") => Some(DataFormat::MsgPack),
+        Some("cbor") => Some(DataFormat::Cbor),
+        Some("bson") => Some(DataFormat::Bson),
+        _ => None,
+    }
 }
 
 /// Auto-detect the format of input data by inspecting content.
 pub fn detect_format(content: &str) -> DataFormat {
-    let trimmed = content.trim();
+    let trimmed = content.trim_start_matches('\u{feff}').trim();
 
     // NDJSON: multiple lines each starting with { (check before JSON)
     let lines: Vec<&str> = trimmed.lines().collect();
@@ -40,6 +392,32 @@ pub fn detect_format(content: &str) -> DataFormat {
         return DataFormat::Ndjson;
     }
 
+    // Log lines, before XML (syslog opens with `<PRI>`) and TOML/YAML
+    // (messages often contain ` = ` or `: `)
+    if log_format::all_lines(trimmed, log_format::is_syslog_line) {
+        return DataFormat::Syslog;
+    }
+    if log_format::all_lines(trimmed, log_format::is_access_line) {
+        return DataFormat::AccessLog;
+    }
+    if log_format::all_lines(trimmed, log_format::is_logfmt_line) {
+        return DataFormat::Logfmt;
+    }
+
+    // XML: a declaration, comment, doctype or start tag
+    if trimmed.starts_with("<?xml")
+        || trimmed.starts_with("<!")
+        || (trimmed.starts_with('<')
+            && trimmed[1..].starts_with(|c: char| c.is_alphabetic() || c == '_'))
+    {
+        return DataFormat::Xml;
+    }
+
+    // JSONC/JSON5 files often open with a comment
+    if trimmed.starts_with("//") || trimmed.starts_with("/*") {
+        return DataFormat::Json5;
+    }
+
     // JSON starts with { or [, but only if it looks like a single JSON value
     // (TOML sections also start with [ but contain `=` signs)
     if trimmed.starts_with('{') {
@@ -49,11 +427,41 @@ pub fn detect_format(content: &str) -> DataFormat {
         return DataFormat::Json;
     }
 
+    // INI and properties files are `key=value` lines too, which TOML accepts
+    // when every value is quoted or typed; TOML wins when it parses. `.env`
+    // lines always stay `.env` so their values are redacted from the AI panel.
+    let parses_as_toml = std::cell::OnceCell::new();
+    let is_toml = || *parses_as_toml.get_or_init(|| toml_format::parse(trimmed).is_ok());
+    let ini = ini_format::looks_like_ini(trimmed);
+    if ini && (lines.iter().any(|l| l.trim_start().starts_with(';')) || !is_toml()) {
+        return DataFormat::Ini;
+    }
+    if env_format::looks_like_env(trimmed) {
+        return DataFormat::Env;
+    }
+    let properties = properties_format::looks_like_properties(trimmed);
+    if properties && !is_toml() {
+        return DataFormat::Properties;
+    }
+    if ini || properties {
+        // Key-value lines that parsed as TOML, like `a.b="x"`
+        return DataFormat::Toml;
+    }
+
     // TOML: contains `key = value` or `[section]` with `=`
     if trimmed.contains(" = ") {
         return DataFormat::Toml;
     }
 
+    // TSV: YAML forbids tabs for indentation, so a consistent tab count
+    // per line is a strong signal (check before YAML)
+    let delimiter = (lines.len() > 1)
+        .then(|| csv_format::sniff_delimiter(trimmed, b'"'))
+        .flatten();
+    if delimiter == Some(b'\t') {
+        return DataFormat::Tsv;
+    }
+
     // YAML: contains `---` or `key:` patterns
     if trimmed.starts_with("---") || trimmed.contains(": ") {
         return DataFormat::Yaml;
@@ -63,18 +471,50 @@ pub fn detect_format(content: &str) -> DataFormat {
     if trimmed.contains(',') && lines.len() > 1 {
         return DataFormat::Csv;
     }
+    match delimiter {
+        Some(b'|') => return DataFormat::Psv,
+        // Other delimiters (`;`) are sniffed again when parsing
+        Some(_) => return DataFormat::Csv,
+        None => {}
+    }
 
     // Default to JSON
     DataFormat::Json
 }
 
-/// Parse input content to a JSON Value based on format.
+/// Parse input content to a JSON Value based on format, with default options.
 pub fn parse_input(content: &str, format: DataFormat) -> Result<Value> {
+    parse_input_with(content, format, &FormatOptions::default())
+}
+
+/// Parse input content to a JSON Value based on format.
+pub fn parse_input_with(
+    content: &str,
+    format: DataFormat,
+    options: &FormatOptions,
+) -> Result<Value> {
     match format {
         DataFormat::Json => json_fmt::parse(content),
+        DataFormat::Json5 => json5_format::parse(content),
         DataFormat::Yaml => yaml_format::parse(content),
         DataFormat::Toml => toml_format::parse(content),
-        DataFormat::Csv => csv_format::parse(content),
+        DataFormat::Csv | DataFormat::Tsv | DataFormat::Psv => {
+            csv_format::parse_with(content, &options.csv_for(format))
+        }
+        DataFormat::Xml => xml_format::parse(content),
+        DataFormat::Ejson | DataFormat::EjsonCanonical => ejson_format::parse(content),
+        DataFormat::Logfmt => log_format::parse_lines(content, log_format::parse_logfmt_line),
+        DataFormat::AccessLog => log_format::parse_lines(content, log_format::parse_access_line),
+        DataFormat::Syslog => log_format::parse_lines(content, log_format::parse_syslog_line),
+        DataFormat::Env => env_format::parse(content),
+        DataFormat::Ini => ini_format::parse(content),
+        DataFormat::Properties => properties_format::parse(content, false),
+        DataFormat::MsgPack
+        | DataFormat::Cbor
+        | DataFormat::Bson
+        | DataFormat::Parquet
+        | DataFormat::Arrow
+        | DataFormat::Spreadsheet => parse_input_bytes_with(content.as_bytes(), format, options),
         DataFormat::Ndjson => {
             let values: Result<Vec<Value>> = content
                 .lines()
@@ -86,13 +526,75 @@ pub fn parse_input(content: &str, format: DataFormat) -> Result<Value> {
     }
 }
 
-/// Serialize a JSON Value to a specific output format.
+/// Parse one line of a line-oriented format (see
+/// [`DataFormat::is_line_oriented`]) into a record.
+pub fn parse_line(line: &str, format: DataFormat) -> Result<Value> {
+    match format {
+        DataFormat::Ndjson => Ok(serde_json::from_str(line)?),
+        DataFormat::Logfmt => log_format::parse_logfmt_line(line),
+        DataFormat::AccessLog => log_format::parse_access_line(line),
+        DataFormat::Syslog => log_format::parse_syslog_line(line),
+        other => bail!("{other:?} is not a line-oriented format"),
+    }
+}
+
+/// Render a value as raw text (like `jq -r`): a string is printed without
+/// quotes, and an array of scalars prints one element per line. Returns
+/// `None` for values with no raw form (objects, nested arrays).
+pub fn format_raw(value: &Value) -> Option<String> {
+    let scalar = |v: &Value| match v {
+        Value::String(s) => Some(s.clone()),
+        Value::Array(_) | Value::Object(_) => None,
+        other => Some(other.to_string()),
+    };
+    match value {
+        Value::Array(arr) => arr
+            .iter()
+            .map(scalar)
+            .collect::<Option<Vec<_>>>()
+            .map(|lines| lines.join("\n")),
+        other => scalar(other),
+    }
+}
+
+/// Serialize a JSON Value to a specific output format, with default options.
 pub fn format_output(value: &Value, format: DataFormat) -> Result<String> {
+    format_output_with(value, format, &FormatOptions::default())
+}
+
+/// Serialize a JSON Value to a specific output format.
+pub fn format_output_with(
+    value: &Value,
+    format: DataFormat,
+    options: &FormatOptions,
+) -> Result<String> {
     match format {
-        DataFormat::Json => Ok(serde_json::to_string_pretty(value)?),
+        // JSON is valid JSON5; comments from the input are not preserved
+        DataFormat::Json | DataFormat::Json5 => Ok(serde_json::to_string_pretty(value)?),
         DataFormat::Yaml => Ok(serde_yaml::to_string(value)?),
         DataFormat::Toml => toml_format::serialize(value),
-        DataFormat::Csv => csv_format::serialize(value),
+        DataFormat::Csv | DataFormat::Tsv | DataFormat::Psv => {
+            csv_format::serialize_with(value, &options.csv_for(format))
+        }
+        DataFormat::Xml => xml_format::serialize(value),
+        DataFormat::Ejson => ejson_format::serialize(value, false, &options.type_hints),
+        DataFormat::EjsonCanonical => ejson_format::serialize(value, true, &options.type_hints),
+        DataFormat::Env => env_format::serialize(value),
+        DataFormat::Ini => ini_format::serialize(value),
+        DataFormat::Properties => properties_format::serialize(value),
+        DataFormat::MsgPack
+        | DataFormat::Cbor
+        | DataFormat::Bson
+        | DataFormat::Parquet
+        | DataFormat::Arrow
+        | DataFormat::Spreadsheet => bail!(
+            "{format:?} is a binary format and cannot be written as text.\n\
+             Hint: use format_output_bytes"
+        ),
+        DataFormat::Logfmt | DataFormat::AccessLog | DataFormat::Syslog => bail!(
+            "writing {format:?} is not supported.\n\
+             Hint: choose another --output format, such as json or ndjson"
+        ),
         DataFormat::Ndjson => {
             if let Value::Array(arr) = value {
                 let lines: Result<Vec<String>> = arr
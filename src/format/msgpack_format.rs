@@ -0,0 +1,136 @@
+use anyhow::{Context, Result};
+use rmpv::Value as MsgValue;
+use serde_json::{Map, Value};
+
+use super::binary::{
+    binary_to_json, ext_to_json, float_to_json, integer_to_json, json_to_binary, json_to_ext,
+    key_to_string,
+};
+
+/// Parse MessagePack bytes into a serde_json::Value. Several concatenated
+/// values (a MessagePack stream) become an array.
+pub fn parse(bytes: &[u8]) -> Result<Value> {
+    let mut rest = bytes;
+    let mut values = Vec::new();
+    while !rest.is_empty() {
+        let offset = bytes.len() - rest.len();
+        let value = rmpv::decode::read_value(&mut rest)
+            .with_context(|| format!("invalid MessagePack at byte {offset}"))?;
+        values.push(to_json(value));
+    }
+    match values.len() {
+        0 => anyhow::bail!("empty MessagePack input"),
+        1 => Ok(values.remove(0)),
+        _ => Ok(Value::Array(values)),
+    }
+}
+
+/// Serialize a JSON value to MessagePack bytes.
+pub fn serialize(value: &Value) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    rmpv::encode::write_value(&mut out, &from_json(value)?)?;
+    Ok(out)
+}
+
+fn to_json(value: MsgValue) -> Value {
+    match value {
+        MsgValue::Nil => Value::Null,
+        MsgValue::Boolean(b) => Value::Bool(b),
+        MsgValue::Integer(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => integer_to_json(i.into()),
+            (None, Some(u)) => integer_to_json(u.into()),
+            _ => Value::Null,
+        },
+        MsgValue::F32(f) => float_to_json(f.into()),
+        MsgValue::F64(f) => float_to_json(f),
+        MsgValue::String(s) => match s.as_str() {
+            Some(text) => Value::String(text.to_string()),
+            // A str that is not valid UTF-8 is kept as its bytes
+            None => binary_to_json(&s.into_bytes()),
+        },
+        MsgValue::Binary(bytes) => binary_to_json(&bytes),
+        MsgValue::Array(items) => Value::Array(items.into_iter().map(to_json).collect()),
+        MsgValue::Map(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(k, v)| (key_to_string(to_json(k)), to_json(v)))
+                .collect::<Map<_, _>>(),
+        ),
+        MsgValue::Ext(kind, data) => ext_to_json(kind, &data),
+    }
+}
+
+fn from_json(value: &Value) -> Result<MsgValue> {
+    Ok(match value {
+        Value::Null => MsgValue::Nil,
+        Value::Bool(b) => MsgValue::Boolean(*b),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                MsgValue::from(i)
+            } else if let Some(u) = n.as_u64() {
+                MsgValue::from(u)
+            } else {
+                MsgValue::F64(n.as_f64().unwrap_or(f64::NAN))
+            }
+        }
+        Value::String(s) => MsgValue::from(s.as_str()),
+        Value::Array(items) => MsgValue::Array(items.iter().map(from_json).collect::<Result<_>>()?),
+        Value::Object(map) => {
+            if let Some(bytes) = json_to_binary(map)? {
+                MsgValue::Binary(bytes)
+            } else if let Some((kind, data)) = json_to_ext(map)? {
+                MsgValue::Ext(kind, data)
+            } else {
+                MsgValue::Map(
+                    map.iter()
+                        .map(|(k, v)| Ok((MsgValue::from(k.as_str()), from_json(v)?)))
+                        .collect::<Result<_>>()?,
+                )
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_map() {
+        // {"a": 1, "b": [true, nil]}
+        let bytes = [0x82, 0xa1, b'a', 0x01, 0xa1, b'b', 0x92, 0xc3, 0xc0];
+        assert_eq!(parse(&bytes).unwrap(), json!({"a": 1, "b": [true, null]}));
+    }
+
+    #[test]
+    fn test_binary_ext_and_int_keys() {
+        let value = MsgValue::Map(vec![
+            (MsgValue::from(1), MsgValue::Binary(vec![0, 255])),
+            (MsgValue::from("t"), MsgValue::Ext(-1, vec![0, 0, 0, 1])),
+        ]);
+        let mut bytes = Vec::new();
+        rmpv::encode::write_value(&mut bytes, &value).unwrap();
+        assert_eq!(
+            parse(&bytes).unwrap(),
+            json!({"1": {"$binary": "AP8="}, "t": {"$ext": {"type": -1, "data": "AAAAAQ=="}}})
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let value = json!({
+            "id": 7, "big": u64::MAX, "neg": -3, "pi": 3.5, "name": "x",
+            "raw": {"$binary": "AP8="}, "ts": {"$ext": {"type": -1, "data": "AAAAAQ=="}}
+        });
+        assert_eq!(parse(&serialize(&value).unwrap()).unwrap(), value);
+    }
+
+    #[test]
+    fn test_stream_and_errors() {
+        assert_eq!(parse(&[0x01, 0x02]).unwrap(), json!([1, 2]));
+        let err = parse(&[0x01, 0x92, 0x01]).unwrap_err();
+        assert!(err.to_string().contains("byte 1"), "{err}");
+        assert!(parse(&[]).is_err());
+    }
+}
@@ -0,0 +1,271 @@
+//! Java `.properties` files, following `java.util.Properties`: `key=value`,
+//! `key: value` or `key value` entries, `#` and `!` comments, lines continued
+//! with a trailing `\`, and `\t`, `\n`, `\uXXXX`-style escapes.
+//!
+//! Keys stay flat (`db.url`) unless read with `nested`, which turns dotted
+//! keys into nested objects like `:unflatten_keys`. Output flattens nested
+//! objects back into dotted keys and escapes non-ASCII text as `\uXXXX`.
+
+use anyhow::{anyhow, bail, Result};
+use serde_json::{Map, Value};
+
+use crate::engine::transform::{flatten_keys, unflatten_keys};
+
+/// Parse a properties file into an object of strings, with dotted keys
+/// nested when `nested` is set.
+pub fn parse(content: &str, nested: bool) -> Result<Value> {
+    let mut obj = Map::new();
+    let mut lines = content.trim_start_matches('\u{feff}').lines();
+    while let Some(first) = lines.next() {
+        let first = first.trim_start();
+        if first.is_empty() || first.starts_with(['#', '!']) {
+            continue;
+        }
+        // Join continuation lines, dropping the leading whitespace of each
+        let mut logical = first.to_string();
+        while ends_with_continuation(&logical) {
+            logical.pop();
+            match lines.next() {
+                Some(next) => logical.push_str(next.trim_start()),
+                None => break,
+            }
+        }
+        let (key, value) = split_entry(&logical)?;
+        obj.insert(key, Value::String(value));
+    }
+    let value = Value::Object(obj);
+    if nested {
+        return unflatten_keys(&value, ".").map_err(|e| {
+            anyhow!(
+                "cannot nest the property keys ({e}).\n\
+                 Hint: read the file without --nest-keys to keep the keys flat"
+            )
+        });
+    }
+    Ok(value)
+}
+
+/// Whether a line ends with an odd number of backslashes.
+fn ends_with_continuation(line: &str) -> bool {
+    line.bytes().rev().take_while(|&b| b == b'\\').count() % 2 == 1
+}
+
+/// Split a logical line into its unescaped key and value.
+fn split_entry(line: &str) -> Result<(String, String)> {
+    let mut chars = line.chars().peekable();
+    let mut key = String::new();
+    while let Some(c) = chars.next_if(|&c| !matches!(c, '=' | ':') && !c.is_whitespace()) {
+        if c == '\\' {
+            unescape_into(&mut key, &mut chars)?;
+        } else {
+            key.push(c);
+        }
+    }
+    // The separator is whitespace, `=` or `:`, or whitespace around either
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+    if chars.next_if(|&c| c == '=' || c == ':').is_some() {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+    let mut value = String::new();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            unescape_into(&mut value, &mut chars)?;
+        } else {
+            value.push(c);
+        }
+    }
+    Ok((key, value))
+}
+
+/// Decode the escape after a `\`.
+fn unescape_into(
+    out: &mut String,
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+) -> Result<()> {
+    match chars.next() {
+        Some('t') => out.push('\t'),
+        Some('n') => out.push('\n'),
+        Some('r') => out.push('\r'),
+        Some('f') => out.push('\u{c}'),
+        Some('u') => {
+            let hex: String = chars.by_ref().take(4).collect();
+            let unit = u16::from_str_radix(&hex, 16)
+                .ok()
+                .filter(|_| hex.len() == 4)
+                .ok_or_else(|| anyhow!("invalid \\u escape \\u{hex} in properties"))?;
+            // Characters outside the BMP are written as a surrogate pair
+            if (0xd800..0xdc00).contains(&unit) {
+                let rest: String = chars.by_ref().take(6).collect();
+                let low = rest
+                    .strip_prefix("\\u")
+                    .and_then(|h| u16::from_str_radix(h, 16).ok())
+                    .ok_or_else(|| anyhow!("unpaired surrogate \\u{hex} in properties"))?;
+                out.push_str(
+                    &String::from_utf16(&[unit, low])
+                        .map_err(|_| anyhow!("invalid surrogate pair \\u{hex}{rest}"))?,
+                );
+            } else {
+                out.push(
+                    char::from_u32(u32::from(unit))
+                        .ok_or_else(|| anyhow!("unpaired surrogate \\u{hex} in properties"))?,
+                );
+            }
+        }
+        Some(c) => out.push(c),
+        None => {}
+    }
+    Ok(())
+}
+
+/// Serialize an object as properties. Nested objects and arrays become
+/// dotted keys (`db.url`, `hosts.0`).
+pub fn serialize(value: &Value) -> Result<String> {
+    let Value::Object(_) = value else {
+        bail!("properties output requires an object");
+    };
+    let Value::Object(flat) = flatten_keys(value, ".") else {
+        unreachable!("flatten_keys keeps objects as objects");
+    };
+    let mut out = String::new();
+    for (key, value) in &flat {
+        let text = match value {
+            Value::Null => String::new(),
+            Value::String(s) => s.clone(),
+            // Empty objects and arrays are kept as leaves by flatten_keys
+            Value::Object(_) | Value::Array(_) => continue,
+            other => other.to_string(),
+        };
+        out.push_str(&escape(key, true));
+        out.push('=');
+        out.push_str(&escape(&text, false));
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn escape(s: &str, is_key: bool) -> String {
+    let mut out = String::new();
+    for (i, c) in s.chars().enumerate() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\u{c}' => out.push_str("\\f"),
+            '=' | ':' if is_key => out.extend(['\\', c]),
+            ' ' if is_key || i == 0 => out.push_str("\\ "),
+            '#' | '!' if i == 0 => out.extend(['\\', c]),
+            c if c.is_ascii() && !c.is_ascii_control() => out.push(c),
+            c => {
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    out.push_str(&format!("\\u{unit:04X}"));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Whether the content looks like a properties file: every meaningful line
+/// is an unindented `key=value` entry (or a continuation), and at least one
+/// key is dotted.
+pub fn looks_like_properties(content: &str) -> bool {
+    let mut dotted = false;
+    let mut continued = false;
+    let mut entries = 0;
+    for line in content.lines() {
+        let was_continued = std::mem::replace(&mut continued, ends_with_continuation(line));
+        let trimmed = line.trim();
+        if was_continued || trimmed.is_empty() || trimmed.starts_with(['#', '!']) {
+            continue;
+        }
+        let Some((key, _)) = line.split_once('=') else {
+            return false;
+        };
+        let key = key.trim_end();
+        if key.is_empty() || key.starts_with(char::is_whitespace) || key.contains([' ', '"', '[']) {
+            return false;
+        }
+        dotted |= key.contains('.');
+        entries += 1;
+    }
+    entries > 0 && dotted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_properties() {
+        let content = "# Spring settings\n\
+                       ! legacy comment\n\
+                       spring.datasource.url = jdbc:postgresql://db:5432/app\n\
+                       spring.datasource.username:admin\n\
+                       greeting Hello, \\\n\
+                       \x20   World\n\
+                       key\\ with\\ spaces=caf\\u00e9 \\u2603\n\
+                       empty=\n\
+                       path=C:\\\\temp\n";
+        assert_eq!(
+            parse(content, false).unwrap(),
+            json!({
+                "spring.datasource.url": "jdbc:postgresql://db:5432/app",
+                "spring.datasource.username": "admin",
+                "greeting": "Hello, World",
+                "key with spaces": "café ☃",
+                "empty": "",
+                "path": "C:\\temp"
+            })
+        );
+    }
+
+    #[test]
+    fn test_nested_keys() {
+        let content = "app.name=demo\napp.hosts.0=a\napp.hosts.1=b\n";
+        assert_eq!(
+            parse(content, true).unwrap(),
+            json!({"app": {"name": "demo", "hosts": ["a", "b"]}})
+        );
+        let err = parse("a=1\na.b=2\n", true).unwrap_err().to_string();
+        assert!(err.contains("--nest-keys"), "{err}");
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let value = json!({
+            "app": {"name": "démo 🚀", "port": 8080, "tags": ["x", "y"]},
+            "odd key=": " leading space",
+            "#hash": "!bang"
+        });
+        let text = serialize(&value).unwrap();
+        assert!(
+            text.contains("app.name=d\\u00E9mo \\uD83D\\uDE80\n"),
+            "{text}"
+        );
+        assert!(text.contains("odd\\ key\\==\\ leading space\n"), "{text}");
+        assert_eq!(
+            parse(&text, false).unwrap(),
+            json!({
+                "app.name": "démo 🚀",
+                "app.port": "8080",
+                "app.tags.0": "x",
+                "app.tags.1": "y",
+                "odd key=": " leading space",
+                "#hash": "!bang"
+            })
+        );
+    }
+
+    #[test]
+    fn test_looks_like_properties() {
+        assert!(looks_like_properties(
+            "# c\nserver.port=8080\nlong=a \\\n  b\n"
+        ));
+        assert!(!looks_like_properties("PORT=8080\n"));
+        assert!(!looks_like_properties("a.b: c\n"));
+        assert!(!looks_like_properties("[s]\na.b=1\n"));
+    }
+}
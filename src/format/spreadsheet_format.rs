@@ -0,0 +1,302 @@
+//! Spreadsheet input (XLSX, XLSM, XLSB, XLS and ODS), read with calamine.
+//!
+//! A workbook is an object with one key per sheet, each an array of rows.
+//! As with CSV, the first row names the columns, but only when it is all
+//! text; otherwise columns are named `col0`, `col1`, … and the first row is
+//! data. Blank rows are skipped.
+//!
+//! Cells keep their type: numbers (whole numbers as integers), booleans, and
+//! dates and times as ISO 8601 strings. Formula errors such as `#DIV/0!`
+//! become strings and empty cells become `null`.
+
+use std::io::Cursor;
+
+use anyhow::{anyhow, bail, Result};
+use calamine::{open_workbook_auto_from_rs, Data, Range, Reader};
+use chrono::NaiveTime;
+use serde_json::{Map, Value};
+
+/// Leading bytes of the ZIP container used by XLSX, XLSB and ODS.
+pub const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+/// Leading bytes of the OLE2 container used by legacy XLS.
+pub const OLE_MAGIC: &[u8] = &[0xd0, 0xcf, 0x11, 0xe0, 0xa1, 0xb1, 0x1a, 0xe1];
+
+/// Which part of a workbook to read.
+#[derive(Debug, Clone, Default)]
+pub struct SheetOptions {
+    /// Read only this sheet (by name, or 1-based position) and return its
+    /// rows directly instead of an object of sheets
+    pub sheet: Option<String>,
+    /// Read only this A1-style range (`B2:F40`) of each sheet; its first row
+    /// is the header row
+    pub range: Option<String>,
+}
+
+/// Parse a workbook into `{sheet_name: [rows]}`, or the rows of one sheet.
+pub fn parse(bytes: &[u8], options: &SheetOptions) -> Result<Value> {
+    let mut workbook = open_workbook_auto_from_rs(Cursor::new(bytes))
+        .map_err(|e| anyhow!("invalid spreadsheet: {e}"))?;
+    let bounds = options.range.as_deref().map(parse_range).transpose()?;
+    let names = workbook.sheet_names();
+
+    let mut read_sheet = |name: &str| -> Result<Value> {
+        let range = workbook
+            .worksheet_range(name)
+            .map_err(|e| anyhow!("cannot read sheet {name:?}: {e}"))?;
+        let range = match bounds {
+            // calamine's `Range::range` panics on a blank sheet
+            Some(_) if range.is_empty() => return Ok(Value::Array(Vec::new())),
+            Some((start, end)) => range.range(start, end),
+            None => range,
+        };
+        Ok(sheet_rows(&range))
+    };
+
+    if let Some(wanted) = &options.sheet {
+        let name = find_sheet(&names, wanted)?;
+        return read_sheet(&name);
+    }
+    let mut sheets = Map::new();
+    for name in &names {
+        sheets.insert(name.clone(), read_sheet(name)?);
+    }
+    Ok(Value::Object(sheets))
+}
+
+/// Find a sheet by exact name, then case-insensitively, then by 1-based position.
+fn find_sheet(names: &[String], wanted: &str) -> Result<String> {
+    let found = names
+        .iter()
+        .find(|n| *n == wanted)
+        .or_else(|| names.iter().find(|n| n.eq_ignore_ascii_case(wanted)))
+        .or_else(|| {
+            let index: usize = wanted.parse().ok()?;
+            names.get(index.checked_sub(1)?)
+        });
+    match found {
+        Some(name) => Ok(name.clone()),
+        None => bail!(
+            "no sheet named {wanted:?}.\nHint: the workbook has {}",
+            names
+                .iter()
+                .map(|n| format!("{n:?}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Parse an A1-style range (`B2:F40`, or a single cell) into zero-based
+/// `(row, column)` corners.
+fn parse_range(s: &str) -> Result<((u32, u32), (u32, u32))> {
+    let invalid = || anyhow!("invalid range {s:?}.\nHint: use A1 notation such as A1:D20");
+    let (first, last) = s.split_once(':').unwrap_or((s, s));
+    let start = parse_cell(first.trim()).ok_or_else(invalid)?;
+    let end = parse_cell(last.trim()).ok_or_else(invalid)?;
+    if start.0 > end.0 || start.1 > end.1 {
+        return Err(invalid());
+    }
+    Ok((start, end))
+}
+
+/// `C12` → `(11, 2)`. Column letters are case-insensitive; `$` is ignored.
+fn parse_cell(cell: &str) -> Option<(u32, u32)> {
+    let cell = cell.replace('$', "");
+    let split = cell.find(|c: char| c.is_ascii_digit())?;
+    let (letters, digits) = cell.split_at(split);
+    if letters.is_empty() || letters.len() > 3 || !letters.chars().all(|c| c.is_ascii_alphabetic())
+    {
+        return None;
+    }
+    let column = letters
+        .to_ascii_uppercase()
+        .bytes()
+        .fold(0u32, |acc, b| acc * 26 + u32::from(b - b'A' + 1));
+    let row: u32 = digits.parse().ok().filter(|&r| r > 0)?;
+    Some((row - 1, column - 1))
+}
+
+fn sheet_rows(range: &Range<Data>) -> Value {
+    let mut rows = range
+        .rows()
+        .filter(|row| row.iter().any(|cell| *cell != Data::Empty));
+    let Some(first) = rows.next() else {
+        return Value::Array(Vec::new());
+    };
+
+    let is_header = first
+        .iter()
+        .all(|cell| matches!(cell, Data::String(_) | Data::Empty));
+    let width = range.width();
+    let headers: Vec<Option<String>> = (0..width)
+        .map(|i| match first.get(i) {
+            Some(Data::String(s)) if is_header && !s.trim().is_empty() => {
+                Some(s.trim().to_string())
+            }
+            _ => None,
+        })
+        .collect();
+
+    let to_object = |row: &[Data]| {
+        let mut obj = Map::new();
+        for (i, cell) in row.iter().enumerate() {
+            match &headers[i] {
+                Some(name) => {
+                    obj.insert(name.clone(), cell_value(cell));
+                }
+                // Unnamed columns only appear where they hold something
+                None if *cell != Data::Empty => {
+                    obj.insert(format!("col{i}"), cell_value(cell));
+                }
+                None => {}
+            }
+        }
+        Value::Object(obj)
+    };
+
+    let mut out = Vec::new();
+    if !is_header {
+        out.push(to_object(first));
+    }
+    out.extend(rows.map(to_object));
+    Value::Array(out)
+}
+
+fn cell_value(cell: &Data) -> Value {
+    match cell {
+        Data::Empty => Value::Null,
+        Data::Int(i) => Value::from(*i),
+        // Spreadsheets store every number as a float
+        Data::Float(f) if f.fract() == 0.0 && f.abs() < 9_007_199_254_740_992.0 => {
+            Value::from(*f as i64)
+        }
+        Data::Float(f) => serde_json::Number::from_f64(*f).map_or(Value::Null, Value::Number),
+        Data::String(s) => Value::String(s.clone()),
+        Data::Bool(b) => Value::Bool(*b),
+        Data::DateTime(dt) if dt.is_duration() => match dt.as_duration() {
+            Some(d) => {
+                let secs = d.num_seconds();
+                Value::String(format!(
+                    "{:02}:{:02}:{:02}",
+                    secs / 3600,
+                    secs % 3600 / 60,
+                    secs % 60
+                ))
+            }
+            None => Value::from(dt.as_f64()),
+        },
+        Data::DateTime(dt) => match dt.as_datetime() {
+            // Times of day are stored as a fraction of a day
+            Some(t) if dt.as_f64() < 1.0 => Value::String(t.format("%H:%M:%S").to_string()),
+            Some(t) if t.time() == NaiveTime::MIN => {
+                Value::String(t.format("%Y-%m-%d").to_string())
+            }
+            Some(t) => Value::String(t.format("%Y-%m-%dT%H:%M:%S").to_string()),
+            None => Value::from(dt.as_f64()),
+        },
+        Data::DateTimeIso(s) | Data::DurationIso(s) => Value::String(s.clone()),
+        Data::Error(e) => Value::String(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_xlsxwriter::{ExcelDateTime, Format, Workbook};
+    use serde_json::json;
+
+    fn workbook() -> Vec<u8> {
+        let mut workbook = Workbook::new();
+        let date = Format::new().set_num_format("yyyy-mm-dd");
+
+        let sales = workbook.add_worksheet().set_name("Sales").unwrap();
+        sales.write(0, 0, "region").unwrap();
+        sales.write(0, 1, "units").unwrap();
+        sales.write(0, 2, "price").unwrap();
+        sales.write(0, 3, "closed").unwrap();
+        sales.write(0, 4, "shipped").unwrap();
+        sales.write(1, 0, "North").unwrap();
+        sales.write(1, 1, 12).unwrap();
+        sales.write(1, 2, 9.5).unwrap();
+        sales.write(1, 3, true).unwrap();
+        let day = ExcelDateTime::from_ymd(2024, 3, 15).unwrap();
+        sales.write_datetime_with_format(1, 4, &day, &date).unwrap();
+        sales.write(3, 0, "South").unwrap();
+        sales.write(3, 1, 7).unwrap();
+
+        let raw = workbook.add_worksheet().set_name("Raw").unwrap();
+        raw.write(0, 0, 1).unwrap();
+        raw.write(0, 1, "a").unwrap();
+        raw.write(1, 0, 2).unwrap();
+
+        workbook.save_to_buffer().unwrap()
+    }
+
+    #[test]
+    fn test_parse_workbook() {
+        let value = parse(&workbook(), &SheetOptions::default()).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "Sales": [
+                    {"region": "North", "units": 12, "price": 9.5, "closed": true, "shipped": "2024-03-15"},
+                    {"region": "South", "units": 7, "price": null, "closed": null, "shipped": null}
+                ],
+                "Raw": [{"col0": 1, "col1": "a"}, {"col0": 2}]
+            })
+        );
+    }
+
+    #[test]
+    fn test_sheet_and_range() {
+        let options = SheetOptions {
+            sheet: Some("sales".into()),
+            range: Some("A1:B2".into()),
+        };
+        assert_eq!(
+            parse(&workbook(), &options).unwrap(),
+            json!([{"region": "North", "units": 12}])
+        );
+
+        let options = SheetOptions {
+            sheet: Some("2".into()),
+            range: None,
+        };
+        assert_eq!(parse(&workbook(), &options).unwrap()[1], json!({"col0": 2}));
+
+        let options = SheetOptions {
+            sheet: Some("Missing".into()),
+            range: None,
+        };
+        let err = parse(&workbook(), &options).unwrap_err().to_string();
+        assert!(err.contains("\"Sales\", \"Raw\""), "{err}");
+    }
+
+    #[test]
+    fn test_range_with_blank_sheet() {
+        let mut workbook = Workbook::new();
+        let data = workbook.add_worksheet().set_name("Data").unwrap();
+        data.write(0, 0, "id").unwrap();
+        data.write(1, 0, 1).unwrap();
+        workbook.add_worksheet().set_name("Empty").unwrap();
+        let bytes = workbook.save_to_buffer().unwrap();
+
+        let options = SheetOptions {
+            sheet: None,
+            range: Some("A1:B2".into()),
+        };
+        assert_eq!(
+            parse(&bytes, &options).unwrap(),
+            json!({"Data": [{"id": 1}], "Empty": []})
+        );
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("B2:D10").unwrap(), ((1, 1), (9, 3)));
+        assert_eq!(parse_range("$aa$3").unwrap(), ((2, 26), (2, 26)));
+        assert!(parse_range("D10:B2").is_err());
+        assert!(parse_range("B0").is_err());
+        assert!(parse_range("12").is_err());
+    }
+}
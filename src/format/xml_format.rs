@@ -0,0 +1,325 @@
+//! XML support.
+//!
+//! The mapping between XML and JSON is reversible:
+//!
+//! - The document is an object with the root element's name as its only key.
+//! - Attributes become `@name` keys; text content becomes `#text`.
+//! - An element with neither attributes nor children is its text as a string
+//!   (or `null` when empty); `<port>8080</port>` is `"port": "8080"`.
+//! - Repeated child elements become an array.
+//!
+//! Text is never type-converted, so values read from XML are strings.
+//! Comments and processing instructions are dropped, CDATA is read as text,
+//! and namespace prefixes are kept as part of the name (`soap:Body`).
+//!
+//! When writing, an array field becomes repeated elements, a value that is
+//! not a single-key object is wrapped in `<root>`, and keys that are not valid
+//! XML names have invalid characters replaced by `_`.
+
+use anyhow::{bail, Context, Result};
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use serde_json::{Map, Value};
+
+/// Element name used when a value has no single root key.
+const ROOT: &str = "root";
+/// Element name for the items of a nested array.
+const ITEM: &str = "item";
+
+/// An element being built while parsing.
+struct Element {
+    name: String,
+    fields: Map<String, Value>,
+    text: String,
+}
+
+impl Element {
+    fn new(start: &BytesStart) -> Result<Self> {
+        let name = String::from_utf8_lossy(start.name().as_ref()).into_owned();
+        let mut fields = Map::new();
+        for attr in start.attributes() {
+            let attr = attr.with_context(|| format!("invalid attribute on <{name}>"))?;
+            let key = String::from_utf8_lossy(attr.key.as_ref()).into_owned();
+            let value = attr
+                .unescape_value()
+                .with_context(|| format!("invalid value for attribute {key} on <{name}>"))?;
+            fields.insert(format!("@{key}"), Value::String(value.into_owned()));
+        }
+        Ok(Self {
+            name,
+            fields,
+            text: String::new(),
+        })
+    }
+
+    fn push_text(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        if !self.text.is_empty() {
+            self.text.push(' ');
+        }
+        self.text.push_str(text);
+    }
+
+    fn add_child(&mut self, name: String, value: Value) {
+        match self.fields.get_mut(&name) {
+            Some(Value::Array(items)) => items.push(value),
+            Some(existing) => {
+                let first = existing.take();
+                *existing = Value::Array(vec![first, value]);
+            }
+            None => {
+                self.fields.insert(name, value);
+            }
+        }
+    }
+
+    fn finish(self) -> (String, Value) {
+        let Element {
+            name,
+            mut fields,
+            text,
+        } = self;
+        let value = if fields.is_empty() {
+            if text.is_empty() {
+                Value::Null
+            } else {
+                Value::String(text)
+            }
+        } else {
+            if !text.is_empty() {
+                fields.insert("#text".into(), Value::String(text));
+            }
+            Value::Object(fields)
+        };
+        (name, value)
+    }
+}
+
+/// Parse an XML document into a serde_json::Value.
+pub fn parse(content: &str) -> Result<Value> {
+    let mut reader = Reader::from_str(content);
+    reader.config_mut().trim_text(true);
+
+    let mut stack: Vec<Element> = Vec::new();
+    let mut root: Option<(String, Value)> = None;
+
+    loop {
+        let position = reader.buffer_position();
+        let event = reader
+            .read_event()
+            .map_err(|e| anyhow::anyhow!("invalid XML at byte {position}: {e}"))?;
+        let finished = match event {
+            Event::Start(start) => {
+                stack.push(Element::new(&start)?);
+                None
+            }
+            Event::Empty(start) => Some(Element::new(&start)?.finish()),
+            Event::End(_) => stack.pop().map(Element::finish),
+            Event::Text(text) => {
+                let text = text
+                    .unescape()
+                    .map_err(|e| anyhow::anyhow!("invalid XML text at byte {position}: {e}"))?;
+                if let Some(element) = stack.last_mut() {
+                    element.push_text(text.trim());
+                }
+                None
+            }
+            Event::CData(data) => {
+                if let Some(element) = stack.last_mut() {
+                    element.push_text(&String::from_utf8_lossy(&data));
+                }
+                None
+            }
+            Event::Eof => break,
+            _ => None,
+        };
+        if let Some((name, value)) = finished {
+            match stack.last_mut() {
+                Some(parent) => parent.add_child(name, value),
+                None if root.is_some() => {
+                    bail!("invalid XML: more than one root element (found <{name}>)")
+                }
+                None => root = Some((name, value)),
+            }
+        }
+    }
+
+    if let Some(open) = stack.last() {
+        bail!("invalid XML: <{}> is never closed", open.name);
+    }
+    let Some((name, value)) = root else {
+        bail!("invalid XML: no root element");
+    };
+    let mut doc = Map::new();
+    doc.insert(name, value);
+    Ok(Value::Object(doc))
+}
+
+/// Serialize a JSON value to an XML document.
+pub fn serialize(value: &Value) -> Result<String> {
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    match value {
+        Value::Object(map) if map.len() == 1 => {
+            let (name, value) = map.iter().next().expect("one key");
+            if value.is_array() {
+                let mut wrapper = Map::new();
+                wrapper.insert(name.clone(), value.clone());
+                write_element(&mut writer, ROOT, &Value::Object(wrapper))?;
+            } else {
+                write_element(&mut writer, name, value)?;
+            }
+        }
+        Value::Array(_) => {
+            let mut wrapper = Map::new();
+            wrapper.insert(ITEM.into(), value.clone());
+            write_element(&mut writer, ROOT, &Value::Object(wrapper))?;
+        }
+        other => write_element(&mut writer, ROOT, other)?,
+    }
+    let mut out = String::from_utf8(writer.into_inner())?;
+    out.push('\n');
+    Ok(out)
+}
+
+fn write_element(writer: &mut Writer<Vec<u8>>, name: &str, value: &Value) -> Result<()> {
+    let name = xml_name(name);
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                if item.is_array() {
+                    let mut wrapper = Map::new();
+                    wrapper.insert(ITEM.into(), item.clone());
+                    write_element(writer, &name, &Value::Object(wrapper))?;
+                } else {
+                    write_element(writer, &name, item)?;
+                }
+            }
+        }
+        Value::Null => {
+            writer.write_event(Event::Empty(BytesStart::new(name.as_str())))?;
+        }
+        Value::Object(map) => {
+            let mut start = BytesStart::new(name.as_str());
+            let mut text = None;
+            let mut children = Vec::new();
+            for (key, child) in map {
+                if let Some(attr) = key.strip_prefix('@') {
+                    start.push_attribute((xml_name(attr).as_str(), scalar_text(child).as_str()));
+                } else if key == "#text" {
+                    text = Some(scalar_text(child));
+                } else {
+                    children.push((key, child));
+                }
+            }
+            if text.is_none() && children.is_empty() {
+                writer.write_event(Event::Empty(start))?;
+                return Ok(());
+            }
+            writer.write_event(Event::Start(start))?;
+            if let Some(text) = text {
+                writer.write_event(Event::Text(BytesText::new(&text)))?;
+            }
+            for (key, child) in children {
+                write_element(writer, key, child)?;
+            }
+            writer.write_event(Event::End(BytesEnd::new(name.as_str())))?;
+        }
+        scalar => {
+            writer
+                .create_element(name.as_str())
+                .write_text_content(BytesText::new(&scalar_text(scalar)))?;
+        }
+    }
+    Ok(())
+}
+
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Make `key` a valid XML name: letters, digits, `_`, `-`, `.` and `:`,
+/// not starting with a digit, `-` or `.`.
+fn xml_name(key: &str) -> String {
+    let mut name: String = key
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || c == ':')
+    {
+        name.insert(0, '_');
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_attributes_text_and_repeats() {
+        let xml = r#"<?xml version="1.0"?>
+<catalog updated="2024-01-01">
+  <!-- a comment -->
+  <book id="1"><title>Rust &amp; You</title><tag>a</tag><tag>b</tag></book>
+  <book id="2"><title><![CDATA[<XML>]]></title></book>
+  <note lang="en">Hello</note>
+  <empty/>
+</catalog>"#;
+        let value = parse(xml).unwrap();
+        assert_eq!(
+            value,
+            json!({"catalog": {
+                "@updated": "2024-01-01",
+                "book": [
+                    {"@id": "1", "title": "Rust & You", "tag": ["a", "b"]},
+                    {"@id": "2", "title": "<XML>"}
+                ],
+                "note": {"@lang": "en", "#text": "Hello"},
+                "empty": null
+            }})
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("<a><b></a>").is_err());
+        assert!(parse("<a>").is_err());
+        assert!(parse("<a/><b/>").is_err());
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn test_serialize_wraps_and_sanitizes() {
+        let out = serialize(&json!([{"full name": "Ann", "1st": true}])).unwrap();
+        assert!(out.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>"));
+        assert!(out.contains("<root>"), "{out}");
+        assert!(out.contains("<full_name>Ann</full_name>"), "{out}");
+        assert!(out.contains("<_1st>true</_1st>"), "{out}");
+    }
+
+    #[test]
+    fn test_serialize_escapes() {
+        let out = serialize(&json!({"a": {"@q": "\"<>\"", "#text": "x & y"}})).unwrap();
+        assert!(
+            out.contains(r#"<a q="&quot;&lt;&gt;&quot;">x &amp; y</a>"#),
+            "{out}"
+        );
+    }
+}
@@ -1,10 +1,52 @@
 use anyhow::Result;
+use serde::Deserialize;
 use serde_json::Value;
 
-/// Parse a YAML string into a serde_json::Value.
+/// Parse a YAML string into a serde_json::Value. A stream of several
+/// `---`-separated documents becomes an array of the documents.
 pub fn parse(content: &str) -> Result<Value> {
-    let value: Value = serde_yaml::from_str(content)?;
-    Ok(value)
+    parse_documents(content).map(join_documents)
+}
+
+/// Combine documents from [`parse_documents`] the way [`parse`] does: one
+/// document as itself, several as an array.
+pub fn join_documents(mut docs: Vec<Value>) -> Value {
+    if docs.len() == 1 {
+        docs.remove(0)
+    } else {
+        Value::Array(docs)
+    }
+}
+
+/// Parse every document in a YAML stream. Empty documents (such as a
+/// separator followed only by comments, common in Helm output) are skipped
+/// when there is more than one document.
+pub fn parse_documents(content: &str) -> Result<Vec<Value>> {
+    let mut docs = Vec::new();
+    for (i, document) in serde_yaml::Deserializer::from_str(content).enumerate() {
+        let value = Value::deserialize(document).map_err(|e| match i {
+            0 => anyhow::Error::from(e),
+            _ => anyhow::anyhow!("YAML document {}: {e}", i + 1),
+        })?;
+        docs.push(value);
+    }
+    if docs.len() > 1 {
+        docs.retain(|doc| !doc.is_null());
+    }
+    if docs.is_empty() {
+        docs.push(Value::Null);
+    }
+    Ok(docs)
+}
+
+/// Serialize documents as a `---`-separated multi-document YAML stream.
+pub fn serialize_documents(docs: &[Value]) -> Result<String> {
+    let mut out = String::new();
+    for doc in docs {
+        out.push_str("---\n");
+        out.push_str(&serde_yaml::to_string(doc)?);
+    }
+    Ok(out)
 }
 
 #[cfg(test)]
@@ -35,4 +77,29 @@ mod tests {
             json!({"users": [{"name": "Alice"}, {"name": "Bob"}]})
         );
     }
+
+    #[test]
+    fn test_parse_multi_document() {
+        let yaml = "---\nkind: Service\n---\n# Source: chart/empty.yaml\n---\nkind: Deployment\n";
+        assert_eq!(
+            parse(yaml).unwrap(),
+            json!([{"kind": "Service"}, {"kind": "Deployment"}])
+        );
+        assert_eq!(parse_documents(yaml).unwrap().len(), 2);
+        assert_eq!(parse_documents("---\nkind: Service\n").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_parse_multi_document_error_names_document() {
+        let err = parse("a: 1\n---\nb: [\n").unwrap_err().to_string();
+        assert!(err.starts_with("YAML document 2:"), "{err}");
+    }
+
+    #[test]
+    fn test_serialize_documents_roundtrip() {
+        let docs = vec![json!({"a": 1}), json!({"b": [2]})];
+        let out = serialize_documents(&docs).unwrap();
+        assert_eq!(out, "---\na: 1\n---\nb:\n- 2\n");
+        assert_eq!(parse_documents(&out).unwrap(), docs);
+    }
 }
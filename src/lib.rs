@@ -7,5 +7,6 @@ pub mod format;
 pub mod history;
 pub mod init;
 pub mod keys;
+pub mod mcp;
 pub mod modes;
 pub mod widgets;
@@ -1,4 +1,4 @@
-use std::io::{self, BufRead, IsTerminal, Read};
+use std::io::{self, BufRead, IsTerminal, Read, Write};
 use std::sync::mpsc;
 use std::time::{Duration, Instant};
 
@@ -14,7 +14,16 @@ use serde_json::Value;
 
 use jdx::app::App;
 use jdx::engine;
-use jdx::format::{detect_format, format_output, parse_input, DataFormat};
+use jdx::engine::transform::Documents;
+use jdx::format::csv_format::{self, CsvOptions};
+use jdx::format::ejson_format::{self, TypeHints};
+use jdx::format::{
+    bson_format, decode_text, detect_format_bytes, detect_format_from_name, detect_table_format,
+    format_output_bytes_with, format_output_with, format_raw, parse_detected_bytes_with,
+    parse_input, parse_input_bytes_with, parse_line, properties_format, read_table_bytes,
+    read_table_file, spreadsheet_format, yaml_format, DataFormat, FormatOptions, Table,
+    TableOptions,
+};
 
 /// Reopen `/dev/tty` as stdin (fd 0) so that both crossterm's event reader
 /// and `enable_raw_mode()` can access the real terminal after data was piped
@@ -77,10 +86,10 @@ fn dup_stdin_fd() -> Result<std::fs::File> {
     Ok(unsafe { std::fs::File::from_raw_fd(new_fd) })
 }
 
-/// Read NDJSON lines from `reader` until `deadline` or EOF, returning the
-/// collected content. This gives a quick initial batch without blocking forever
-/// on a streaming source.
-fn read_initial_ndjson(
+/// Read lines from `reader` until `deadline` or EOF, returning the collected
+/// content. This gives a quick initial batch without blocking forever on a
+/// streaming source.
+fn read_initial_lines(
     reader: &mut io::BufReader<std::fs::File>,
     deadline: Instant,
 ) -> (String, bool) {
@@ -109,9 +118,14 @@ fn read_initial_ndjson(
     (content, hit_eof)
 }
 
-/// Background thread that reads remaining NDJSON lines from the pipe and sends
-/// parsed values over the channel. Exits on EOF or channel disconnect.
-fn stdin_reader_thread(reader: io::BufReader<std::fs::File>, tx: mpsc::Sender<Value>) {
+/// Background thread that reads remaining lines of a line-oriented format
+/// (NDJSON or logs) from the pipe and sends parsed records over the channel.
+/// Exits on EOF or channel disconnect.
+fn stdin_reader_thread(
+    reader: io::BufReader<std::fs::File>,
+    format: DataFormat,
+    tx: mpsc::Sender<Value>,
+) {
     for line in reader.lines() {
         match line {
             Ok(l) => {
@@ -119,7 +133,7 @@ fn stdin_reader_thread(reader: io::BufReader<std::fs::File>, tx: mpsc::Sender<Va
                 if trimmed.is_empty() {
                     continue;
                 }
-                match serde_json::from_str::<Value>(&trimmed) {
+                match parse_line(&trimmed, format) {
                     Ok(val) => {
                         if tx.send(val).is_err() {
                             return; // receiver dropped
@@ -143,6 +157,10 @@ struct Cli {
     #[command(subcommand)]
     command: Option<Command>,
 
+    /// Run as an MCP server on stdio, offering the convert and query tools
+    #[arg(long = "mcp")]
+    mcp: bool,
+
     #[command(flatten)]
     viewer: ViewerArgs,
 }
@@ -167,14 +185,21 @@ struct ViewerArgs {
     #[arg(short = 'q', long = "query-output")]
     query_output: bool,
 
-    /// Input format (auto-detected if omitted): json, yaml, toml, csv, ndjson
+    /// Input format (auto-detected if omitted): json, json5, yaml, toml, csv, tsv, psv, ndjson,
+    /// xml, msgpack, cbor, bson, ejson, ejson-canonical, parquet, arrow, xlsx, ods, logfmt,
+    /// access-log, syslog, env, ini, properties
     #[arg(short = 'i', long = "input")]
     input_format: Option<String>,
 
-    /// Output format (default: json): json, yaml, toml, csv, ndjson
+    /// Output format (default: json): json, json5, yaml, toml, csv, tsv, psv, ndjson,
+    /// xml, msgpack, cbor, bson, ejson, ejson-canonical, env, ini, properties
     #[arg(short = 'o', long = "output")]
     output_format: Option<String>,
 
+    /// Raw output: print strings without quotes, one line per array element
+    #[arg(short = 'r', long = "raw-output")]
+    raw_output: bool,
+
     /// Monochrome output (no colors)
     #[arg(short = 'M', long = "monochrome")]
     monochrome: bool,
@@ -186,6 +211,66 @@ struct ViewerArgs {
     /// Non-interactive mode: evaluate query and print result
     #[arg(long = "non-interactive")]
     non_interactive: bool,
+
+    /// Load a secondary document, available as `$NAME` in queries and
+    /// transforms (e.g. `--with users=users.json`). Repeatable.
+    #[arg(long = "with", value_name = "NAME=FILE")]
+    with: Vec<String>,
+
+    /// Write the result back to FILE at the query's path instead of printing
+    /// it, in FILE's own format unless --output is given
+    /// (e.g. `-w -Q '. :set .version 2'`)
+    #[arg(short = 'w', long = "in-place", requires = "file", conflicts_with_all = ["query_output", "raw_output"])]
+    in_place: bool,
+
+    /// Read at most N rows from Parquet or Arrow input
+    #[arg(long = "limit", value_name = "N")]
+    limit: Option<usize>,
+
+    /// Read only this sheet of a spreadsheet, by name or 1-based position
+    #[arg(long = "sheet", value_name = "NAME")]
+    sheet: Option<String>,
+
+    /// Read only this cell range of each sheet, in A1 notation (e.g. B2:F40);
+    /// its first row is the header row
+    #[arg(long = "range", value_name = "A1:B2")]
+    range: Option<String>,
+
+    /// CSV field delimiter for input and output: a character, or tab, comma,
+    /// semicolon, pipe or space (sniffed from the input if omitted)
+    #[arg(long = "delimiter", value_name = "CHAR")]
+    delimiter: Option<String>,
+
+    /// CSV has no header row: columns are named col0, col1, … and output
+    /// omits the header
+    #[arg(long = "no-header")]
+    no_header: bool,
+
+    /// CSV column types: `string` turns type inference off, `zip=string`
+    /// sets one column (auto, string, number or bool; comma-separated)
+    #[arg(long = "csv-types", value_name = "SPEC")]
+    csv_types: Option<String>,
+
+    /// CSV quote character (default: ")
+    #[arg(long = "csv-quote", value_name = "CHAR")]
+    csv_quote: Option<String>,
+
+    /// CSV escape character inside quoted fields (default: quotes are doubled)
+    #[arg(long = "csv-escape", value_name = "CHAR")]
+    csv_escape: Option<String>,
+
+    /// Start CSV output with a UTF-8 byte order mark, for Excel
+    #[arg(long = "csv-bom")]
+    csv_bom: bool,
+
+    /// Write nested CSV values as dotted columns (`user.name`, `tags.0`)
+    /// instead of JSON text
+    #[arg(long = "csv-flatten")]
+    csv_flatten: bool,
+
+    /// Nest dotted Java properties keys (`db.url=x` → {"db": {"url": "x"}})
+    #[arg(long = "nest-keys")]
+    nest_keys: bool,
 }
 
 fn main() -> Result<()> {
@@ -194,19 +279,33 @@ fn main() -> Result<()> {
     if let Some(Command::Init) = cli.command {
         return jdx::init::run_wizard();
     }
+    if cli.mcp {
+        return jdx::mcp::run_mcp_server();
+    }
 
     let viewer = &cli.viewer;
 
-    // Check if we should use the streaming NDJSON path:
-    // stdin is piped + format is explicitly NDJSON + not non-interactive
-    let is_stdin_piped = !io::stdin().is_terminal() && viewer.file.is_none();
-    let is_ndjson = matches!(
-        viewer.input_format.as_deref(),
-        Some("ndjson") | Some("jsonl")
-    );
-    let use_streaming = is_stdin_piped && is_ndjson && !viewer.non_interactive;
+    let (config, _) = jdx::config::load_config();
+    let exec_policy = engine::exec::ExecPolicy::from(&config.exec);
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let (aliases, alias_warning) = jdx::config::load_aliases(&config, &cwd);
+    engine::alias::configure(aliases);
+    let mut format_options = FormatOptions {
+        csv: csv_options(viewer)?,
+        ..FormatOptions::default()
+    };
 
-    if use_streaming {
+    // Check if we should use the streaming path: stdin is piped + format is
+    // explicitly line-oriented (NDJSON or logs) + not non-interactive
+    let is_stdin_piped = !io::stdin().is_terminal() && viewer.file.is_none();
+    let stream_format = viewer
+        .input_format
+        .as_deref()
+        .map(DataFormat::from_str_name)
+        .transpose()?
+        .filter(|format| format.is_line_oriented());
+
+    if let (Some(format), true) = (stream_format, is_stdin_piped && !viewer.non_interactive) {
         #[cfg(unix)]
         {
             // Dup the pipe fd BEFORE reopen_tty_stdin replaces fd 0
@@ -215,25 +314,30 @@ fn main() -> Result<()> {
 
             // Read initial batch with a short deadline
             let deadline = Instant::now() + Duration::from_millis(500);
-            let (initial_content, hit_eof) = read_initial_ndjson(&mut reader, deadline);
+            let (initial_content, hit_eof) = read_initial_lines(&mut reader, deadline);
 
             if initial_content.trim().is_empty() {
-                bail!("No NDJSON data received from stdin within the initial timeout.");
+                bail!("No {format:?} data received from stdin within the initial timeout.");
             }
 
-            let data = parse_input(&initial_content, DataFormat::Ndjson)
-                .context("Failed to parse initial NDJSON data")?;
+            let data = parse_input(&initial_content, format)
+                .with_context(|| format!("Failed to parse initial {format:?} data"))?;
 
             // Now reopen /dev/tty so crossterm can read key events
             reopen_tty_stdin()?;
 
             let mut app = App::new(data, viewer.query_output, viewer.monochrome);
+            app.set_documents(load_documents(&viewer.with, &format_options)?);
+            app.ndjson_input = true;
+            if let Some(warning) = alias_warning {
+                app.status_message.get_or_insert(warning);
+            }
 
             // If the pipe hasn't ended, spawn background reader thread
             if !hit_eof {
                 let (tx, rx) = mpsc::channel();
                 app.set_stdin_rx(rx);
-                std::thread::spawn(move || stdin_reader_thread(reader, tx));
+                std::thread::spawn(move || stdin_reader_thread(reader, format, tx));
             }
 
             if let Some(ref q) = viewer.initial_query {
@@ -257,7 +361,7 @@ fn main() -> Result<()> {
             result?;
 
             if app.confirmed {
-                print_output(&app, viewer)?;
+                print_output(&app, viewer, &format_options)?;
             }
 
             return Ok(());
@@ -266,30 +370,51 @@ fn main() -> Result<()> {
         #[cfg(not(unix))]
         {
             bail!(
-                "Streaming NDJSON from stdin is not supported on this platform.\n\
+                "Streaming {format:?} from stdin is not supported on this platform.\n\
                  Use --non-interactive or pass a file argument instead."
             );
         }
     }
 
     // Non-streaming path (original behavior)
-    let content = read_input(viewer)?;
-
-    let input_format = match &viewer.input_format {
-        Some(fmt) => DataFormat::from_str_name(fmt)?,
-        None => detect_format(&content),
-    };
-
-    let data = parse_input(&content, input_format).context("Failed to parse input data")?;
+    let Input {
+        data,
+        format: input_format,
+        multi_document,
+        table_schema,
+        type_hints,
+    } = load_input(viewer, &format_options)?;
+    format_options.type_hints = type_hints;
+    let documents = load_documents(&viewer.with, &format_options)?;
 
     if viewer.non_interactive {
+        if let Some(warning) = &alias_warning {
+            eprintln!("warning: {warning}");
+        }
         let query_str = viewer.initial_query.as_deref().unwrap_or(".");
-        let segments = engine::query::parse(query_str)?;
-        let result = engine::json::traverse(&data, &segments);
-        match result.value {
+        let format_options = output_options(&format_options, query_str, viewer.in_place);
+        let stream = input_format.is_line_oriented();
+        match evaluate_query(&data, query_str, &documents, stream, &exec_policy)? {
+            Some(val) if viewer.in_place => {
+                let doc = in_place_document(&data, query_str, val)?;
+                let multi_document = multi_document && keeps_documents(query_str, true);
+                return write_in_place(&doc, viewer, input_format, multi_document, &format_options);
+            }
+            Some(val) if is_binary_output(viewer)? => {
+                return write_binary_output(&val, viewer, &format_options);
+            }
             Some(val) => {
-                let output = format_output_value(&val, viewer)?;
-                print!("{output}");
+                let multi_document = multi_document && keeps_documents(query_str, false);
+                let output = format_output_value(&val, viewer, multi_document, &format_options)?;
+                if viewer.raw_output {
+                    // Raw lines are meant for line-oriented tools like `wc -l`,
+                    // so an empty array prints no lines at all
+                    if !output.is_empty() {
+                        println!("{output}");
+                    }
+                } else {
+                    print!("{output}");
+                }
                 return Ok(());
             }
             None => {
@@ -303,6 +428,17 @@ fn main() -> Result<()> {
     }
 
     let mut app = App::new(data, viewer.query_output, viewer.monochrome);
+    app.set_documents(documents);
+    app.ndjson_input = input_format.is_line_oriented();
+    app.multi_document = multi_document;
+    app.redact_ai = input_format.holds_secrets();
+    app.schema.source = table_schema;
+    if let Some(warning) = alias_warning {
+        app.status_message.get_or_insert(warning);
+    }
+    if let Some(note) = table_note(viewer, input_format) {
+        app.status_message.get_or_insert(note);
+    }
 
     if let Some(ref q) = viewer.initial_query {
         app.query.text = q.clone();
@@ -323,8 +459,16 @@ fn main() -> Result<()> {
 
     result?;
 
-    if app.confirmed {
-        print_output(&app, viewer)?;
+    if app.confirmed && viewer.in_place {
+        let query = &app.query.text;
+        if let Some(val) = evaluate_app_query(&app)? {
+            let options = output_options(&format_options, query, true);
+            let doc = in_place_document(&app.data, query, val)?;
+            let multi_document = multi_document && keeps_documents(query, true);
+            write_in_place(&doc, viewer, input_format, multi_document, &options)?;
+        }
+    } else if app.confirmed {
+        print_output(&app, viewer, &format_options)?;
     }
 
     Ok(())
@@ -341,7 +485,7 @@ fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App)
         // Check for AI results from background thread
         app.poll_ai_result();
 
-        // Check for new streaming NDJSON lines
+        // Check for new records streamed from stdin
         app.poll_stdin();
 
         // Poll for events with a small timeout for responsive rendering
@@ -358,13 +502,203 @@ fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App)
     Ok(())
 }
 
-fn read_input(viewer: &ViewerArgs) -> Result<String> {
+/// The parsed main input.
+struct Input {
+    data: Value,
+    format: DataFormat,
+    /// Whether the data is an array of the documents of a YAML stream
+    multi_document: bool,
+    /// Schema declared by a Parquet or Arrow file
+    table_schema: Option<String>,
+    /// BSON types of the fields of Extended JSON or BSON input
+    type_hints: TypeHints,
+}
+
+/// Read and parse the input file or stdin. Parquet and Arrow files are read
+/// from disk directly so that `--limit` and a leading `:pick` avoid loading
+/// rows and columns that are not needed.
+fn load_input(viewer: &ViewerArgs, format_options: &FormatOptions) -> Result<Input> {
+    let requested = viewer
+        .input_format
+        .as_deref()
+        .map(DataFormat::from_str_name)
+        .transpose()?;
+    let options = TableOptions {
+        columns: pushed_down_columns(viewer),
+        limit: viewer.limit,
+    };
+    let table_input = |table: Table, format| Input {
+        data: table.rows,
+        format,
+        multi_document: false,
+        table_schema: Some(table.schema),
+        type_hints: TypeHints::default(),
+    };
+
+    if let Some(path) = &viewer.file {
+        let format = match requested {
+            Some(format) => Some(format),
+            None => sniff_table_format(path)?,
+        };
+        if let Some(format) = format.filter(|f| f.is_table()) {
+            check_reader_flags(viewer, format)?;
+            let file = std::fs::File::open(path).context(format!("Failed to read file: {path}"))?;
+            let table =
+                read_table_file(file, format, &options).context("Failed to parse input data")?;
+            return Ok(table_input(table, format));
+        }
+    }
+
+    let content = read_input(viewer)?;
+    let format = requested
+        .or_else(|| viewer.file.as_deref().and_then(detect_format_from_name))
+        .unwrap_or_else(|| detect_format_bytes(&content));
+    check_reader_flags(viewer, format)?;
+    if format.is_table() {
+        let table =
+            read_table_bytes(&content, format, &options).context("Failed to parse input data")?;
+        return Ok(table_input(table, format));
+    }
+    let mut multi_document = false;
+    let mut type_hints = TypeHints::default();
+    let data = match format {
+        DataFormat::Spreadsheet => {
+            let options = spreadsheet_format::SheetOptions {
+                sheet: viewer.sheet.clone(),
+                range: viewer.range.clone(),
+            };
+            spreadsheet_format::parse(&content, &options)
+        }
+        DataFormat::Properties if viewer.nest_keys => {
+            decode_text(&content).and_then(|text| properties_format::parse(&text, true))
+        }
+        DataFormat::Yaml => decode_text(&content)
+            .and_then(|text| yaml_format::parse_documents(&text))
+            .map(|docs| {
+                multi_document = docs.len() > 1;
+                yaml_format::join_documents(docs)
+            }),
+        DataFormat::Bson => bson_format::parse_typed(&content).map(|(data, hints)| {
+            type_hints = hints;
+            data
+        }),
+        DataFormat::Ejson | DataFormat::EjsonCanonical => decode_text(&content)
+            .and_then(|text| ejson_format::parse_typed(&text))
+            .map(|(data, hints)| {
+                type_hints = hints;
+                data
+            }),
+        _ if requested.is_some() => parse_input_bytes_with(&content, format, format_options),
+        _ => parse_detected_bytes_with(&content, format, format_options),
+    }
+    .context("Failed to parse input data")?;
+    Ok(Input {
+        data,
+        format,
+        multi_document,
+        table_schema: None,
+        type_hints,
+    })
+}
+
+/// The CSV dialect given by the `--delimiter`, `--no-header` and `--csv-*` flags.
+fn csv_options(viewer: &ViewerArgs) -> Result<CsvOptions> {
+    let single_char = |flag: &str, s: &str| match s.as_bytes() {
+        [c] => Ok(*c),
+        _ => bail!("--{flag} must be a single ASCII character, got {s:?}"),
+    };
+    let mut options = CsvOptions {
+        delimiter: viewer
+            .delimiter
+            .as_deref()
+            .map(csv_format::parse_delimiter)
+            .transpose()?,
+        has_headers: !viewer.no_header,
+        bom: viewer.csv_bom,
+        flatten: viewer.csv_flatten,
+        ..CsvOptions::default()
+    };
+    if let Some(quote) = &viewer.csv_quote {
+        options.quote = single_char("csv-quote", quote)?;
+    }
+    if let Some(escape) = &viewer.csv_escape {
+        options.escape = Some(single_char("csv-escape", escape)?);
+    }
+    if let Some(spec) = &viewer.csv_types {
+        options.set_types(spec)?;
+    }
+    Ok(options)
+}
+
+/// Reject reader flags that do not apply to the input format.
+fn check_reader_flags(viewer: &ViewerArgs, format: DataFormat) -> Result<()> {
+    if viewer.limit.is_some() && !format.is_table() {
+        bail!(
+            "--limit only applies to Parquet and Arrow input, not {format:?}.\n\
+             Hint: use a slice such as `.[:100]` instead"
+        );
+    }
+    if (viewer.sheet.is_some() || viewer.range.is_some()) && format != DataFormat::Spreadsheet {
+        bail!(
+            "--sheet and --range only apply to spreadsheet input, not {format:?}.\n\
+             Hint: pass --input xlsx if the file was not recognised"
+        );
+    }
+    if viewer.nest_keys && format != DataFormat::Properties {
+        bail!(
+            "--nest-keys only applies to Java properties input, not {format:?}.\n\
+             Hint: use :unflatten_keys to nest dotted keys of other formats"
+        );
+    }
+    Ok(())
+}
+
+/// Detect Parquet or Arrow from the first bytes of a file.
+fn sniff_table_format(path: &str) -> Result<Option<DataFormat>> {
+    let mut header = Vec::with_capacity(8);
+    std::fs::File::open(path)
+        .and_then(|file| file.take(8).read_to_end(&mut header))
+        .context(format!("Failed to read file: {path}"))?;
+    Ok(detect_table_format(&header))
+}
+
+/// The fields of a `:pick` that opens the transform chain of a query on the
+/// whole document (`. :pick a,b`); those are the only columns it can see.
+fn picked_columns(query: &str) -> Option<Vec<String>> {
+    let start = engine::lexer::find_transform_start(query)?;
+    let path = query[..start].trim_end_matches([' ', '|']).trim();
+    if !matches!(path, "" | "." | ".[]" | ".[*]") {
+        return None;
+    }
+    let commands = engine::lexer::parse_chain(&query[start..]).ok()?;
+    let first = commands.first().filter(|c| c.name == ":pick")?;
+    first.args.list().ok()
+}
+
+/// The only columns to read from a columnar file: those picked by a
+/// --non-interactive query. The TUI reads them all, since the query can be
+/// edited to use other columns once it is open.
+fn pushed_down_columns(viewer: &ViewerArgs) -> Option<Vec<String>> {
+    if !viewer.non_interactive {
+        return None;
+    }
+    viewer.initial_query.as_deref().and_then(picked_columns)
+}
+
+/// Status-bar note saying that only part of a columnar file was loaded.
+fn table_note(viewer: &ViewerArgs, format: DataFormat) -> Option<String> {
+    let limit = viewer.limit.filter(|_| format.is_table())?;
+    Some(format!("Loaded only the first {limit} rows"))
+}
+
+/// Read the input file or stdin as raw bytes, so binary formats survive.
+fn read_input(viewer: &ViewerArgs) -> Result<Vec<u8>> {
     if let Some(ref path) = viewer.file {
-        std::fs::read_to_string(path).context(format!("Failed to read file: {path}"))
+        std::fs::read(path).context(format!("Failed to read file: {path}"))
     } else if !io::stdin().is_terminal() {
-        let mut buf = String::new();
+        let mut buf = Vec::new();
         io::stdin()
-            .read_to_string(&mut buf)
+            .read_to_end(&mut buf)
             .context("Failed to read from stdin")?;
         Ok(buf)
     } else {
@@ -376,22 +710,26 @@ fn read_input(viewer: &ViewerArgs) -> Result<String> {
     }
 }
 
-fn print_output(app: &App, viewer: &ViewerArgs) -> Result<()> {
+fn print_output(app: &App, viewer: &ViewerArgs, options: &FormatOptions) -> Result<()> {
     let output = if app.query_output_mode {
         app.query.text.clone()
     } else {
-        let value = match engine::query::parse(&app.query.text) {
-            Ok(segments) => {
-                let result = engine::json::traverse(&app.data, &segments);
-                result.value
-            }
+        let options = &output_options(options, &app.query.text, false);
+        let value = match evaluate_app_query(app) {
+            Ok(value) => value,
             Err(e) => {
-                eprintln!("Query parse error: {e}");
+                eprintln!("{e}");
                 None
             }
         };
         match value {
-            Some(val) => format_output_value(&val, viewer)?,
+            Some(val) if is_binary_output(viewer)? => {
+                return write_binary_output(&val, viewer, options)
+            }
+            Some(val) => {
+                let multi_document = app.multi_document && keeps_documents(&app.query.text, false);
+                format_output_value(&val, viewer, multi_document, options)?
+            }
             None => String::new(),
         }
     };
@@ -401,10 +739,206 @@ fn print_output(app: &App, viewer: &ViewerArgs) -> Result<()> {
     Ok(())
 }
 
-fn format_output_value(value: &serde_json::Value, viewer: &ViewerArgs) -> Result<String> {
+/// Evaluate a query with optional trailing transforms (e.g. `.users :sort age`).
+/// A query starting with `$name` reads from a `--with` document instead.
+/// `stream` marks NDJSON input, which `:exec` forwards one record per line.
+fn evaluate_query(
+    data: &Value,
+    query: &str,
+    documents: &Documents,
+    stream: bool,
+    exec: &engine::exec::ExecPolicy,
+) -> Result<Option<Value>> {
+    let (path, transforms) = engine::lexer::split_query(query);
+    let value = if path.starts_with('$') {
+        Some(engine::transform::resolve_document(path, documents)?)
+    } else {
+        let segments = engine::query::parse(path)?;
+        engine::json::traverse(data, &segments).value
+    };
+    match (value, transforms) {
+        (Some(val), Some(t)) => {
+            let ctx = engine::registry::TransformContext {
+                docs: documents,
+                stream,
+                exec,
+            };
+            Ok(Some(engine::transform::apply_transform_in(&val, t, &ctx)?))
+        }
+        (value, _) => Ok(value),
+    }
+}
+
+/// The document `--in-place` writes: the query result put back where the
+/// query's path points, so `.spec :set .replicas 3` keeps the rest of the
+/// file. Paths that don't name one place (`[*]`, slices, filters) are refused.
+fn in_place_document(data: &Value, query: &str, result: Value) -> Result<Value> {
+    let (path, _) = engine::lexer::split_query(query);
+    if path.starts_with('$') {
+        bail!("--in-place cannot write back a `{path}` document; query the input instead");
+    }
+    let segments = engine::query::parse(path)?;
+    engine::json::splice(data, &segments, result).with_context(|| {
+        format!(
+            "--in-place needs a path of plain keys and indexes to write back to, \
+             not `{path}`; use `. :set …` to edit the whole document"
+        )
+    })
+}
+
+/// Whether the output of `query` is still the list of documents read from a
+/// multi-document YAML stream, and so is written as one: the untransformed
+/// root, or with --in-place a result spliced back in below the root.
+fn keeps_documents(query: &str, in_place: bool) -> bool {
+    let is_root = |path: &str| engine::query::parse(path).is_ok_and(|s| s.is_empty());
+    match engine::lexer::split_query(query) {
+        (path, None) if is_root(path) => true,
+        (path, _) => in_place && !is_root(path),
+    }
+}
+
+/// Evaluate the query the TUI was confirmed with.
+fn evaluate_app_query(app: &App) -> Result<Option<Value>> {
+    evaluate_query(
+        &app.data,
+        &app.query.text,
+        &app.documents,
+        app.ndjson_input,
+        app.exec_policy(),
+    )
+}
+
+/// The format options for writing the result of `query`: table output puts
+/// the index columns of a `:pivot` first, and BSON types are looked up
+/// below the query's path (with --in-place the whole input is written, so
+/// they stay as read).
+fn output_options(options: &FormatOptions, query: &str, in_place: bool) -> FormatOptions {
+    let mut options = options.clone();
+    let (path, transforms) = engine::lexer::split_query(query);
+    if let Some(index) = transforms.and_then(engine::transform::pivot_index) {
+        options.csv.leading_columns = index;
+    }
+    if !in_place {
+        options.type_hints = match engine::query::parse(path) {
+            Ok(segments) if !path.starts_with('$') => options.type_hints.select(&segments),
+            _ => TypeHints::default(),
+        };
+    }
+    options
+}
+
+/// Load `--with NAME=FILE` documents, auto-detecting each file's format.
+fn load_documents(specs: &[String], options: &FormatOptions) -> Result<Documents> {
+    let mut documents = Documents::new();
+    for spec in specs {
+        let (name, path) = match spec.split_once('=') {
+            Some((name, path)) if !name.is_empty() && !path.is_empty() => (name, path),
+            _ => bail!("Invalid --with value '{spec}' (expected NAME=FILE)"),
+        };
+        let content = std::fs::read(path).context(format!("Failed to read file: {path}"))?;
+        let format = detect_format_from_name(path).unwrap_or_else(|| detect_format_bytes(&content));
+        let value = parse_detected_bytes_with(&content, format, options)
+            .context(format!("Failed to parse document: {path}"))?;
+        documents.insert(name.to_string(), value);
+    }
+    Ok(documents)
+}
+
+/// Write `value` back to the input file for `--in-place`, through a temporary
+/// file in the same directory so a failed write never truncates the original.
+fn write_in_place(
+    value: &Value,
+    viewer: &ViewerArgs,
+    input_format: DataFormat,
+    multi_document: bool,
+    options: &FormatOptions,
+) -> Result<()> {
+    let Some(path) = viewer.file.as_deref() else {
+        bail!("--in-place requires a FILE argument");
+    };
+    let format = match &viewer.output_format {
+        Some(fmt) => DataFormat::from_str_name(fmt)?,
+        None => input_format,
+    };
+    let output = if format.is_binary() {
+        format_output_bytes_with(value, format, options)?
+    } else {
+        let mut output = serialize_value(value, format, multi_document, options)?;
+        if !output.ends_with('\n') {
+            output.push('\n');
+        }
+        output.into_bytes()
+    };
+
+    let path = std::path::Path::new(path);
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => std::path::Path::new("."),
+    };
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = dir.join(format!(".{name}.jdx-tmp"));
+    std::fs::write(&tmp, output).context(format!("Failed to write {}", tmp.display()))?;
+    if let Ok(meta) = std::fs::metadata(path) {
+        let _ = std::fs::set_permissions(&tmp, meta.permissions());
+    }
+    std::fs::rename(&tmp, path).context(format!("Failed to replace {}", path.display()))
+}
+
+/// Serialize for output. An array read from a multi-document YAML stream is
+/// written back as one YAML document per element.
+fn serialize_value(
+    value: &Value,
+    format: DataFormat,
+    multi_document: bool,
+    options: &FormatOptions,
+) -> Result<String> {
+    match value {
+        Value::Array(docs) if multi_document && format == DataFormat::Yaml => {
+            yaml_format::serialize_documents(docs)
+        }
+        _ => format_output_with(value, format, options),
+    }
+}
+
+/// Whether `--output` names a binary format (and `-r` is not in effect).
+fn is_binary_output(viewer: &ViewerArgs) -> Result<bool> {
+    match &viewer.output_format {
+        Some(fmt) if !viewer.raw_output => Ok(DataFormat::from_str_name(fmt)?.is_binary()),
+        _ => Ok(false),
+    }
+}
+
+/// Write `value` to stdout in the binary `--output` format. Binary data is
+/// never written to a terminal.
+fn write_binary_output(value: &Value, viewer: &ViewerArgs, options: &FormatOptions) -> Result<()> {
+    let fmt = viewer.output_format.as_deref().unwrap_or_default();
+    let format = DataFormat::from_str_name(fmt)?;
+    if io::stdout().is_terminal() {
+        bail!(
+            "refusing to write binary {fmt} output to a terminal.\n\
+             Hint: redirect it to a file, e.g. `jdx data.json -o {fmt} > data.{fmt}`"
+        );
+    }
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(&format_output_bytes_with(value, format, options)?)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+fn format_output_value(
+    value: &serde_json::Value,
+    viewer: &ViewerArgs,
+    multi_document: bool,
+    options: &FormatOptions,
+) -> Result<String> {
+    if viewer.raw_output {
+        if let Some(raw) = format_raw(value) {
+            return Ok(raw);
+        }
+    }
     let output_format = match &viewer.output_format {
         Some(fmt) => DataFormat::from_str_name(fmt)?,
         None => DataFormat::Json,
     };
-    format_output(value, output_format)
+    serialize_value(value, output_format, multi_document, options)
 }
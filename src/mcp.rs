@@ -6,10 +6,16 @@
 use std::io::{self, BufRead, Write};
 
 use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
 use serde_json::{json, Value};
 
 use crate::engine;
-use crate::format::{detect_format, format_output, parse_input, DataFormat};
+use crate::format::ejson_format::{self, TypeHints};
+use crate::format::{
+    bson_format, decode_text, detect_format, detect_format_bytes, format_output_bytes_with,
+    format_output_with, parse_detected_bytes_with, parse_input_bytes, DataFormat, FormatOptions,
+};
 
 /// Run the MCP server, reading JSON-RPC requests from stdin and writing
 /// responses to stdout.
@@ -107,29 +113,40 @@ fn handle_initialize(id: Value) -> Value {
 }
 
 fn handle_tools_list(id: Value) -> Value {
+    // Generated from the transform registry so that transforms registered by
+    // embedding applications are advertised too.
+    let query_description = format!(
+        "Query and transform structured data using jdx dot-notation. Supports path traversal (e.g. '.users[0].name'), filter predicates (e.g. '.items[price < 10]'), and transform commands ({}). Input format is auto-detected.",
+        engine::registry::snapshot().names().join(", ")
+    );
     json_rpc_ok(
         id,
         json!({
             "tools": [
                 {
                     "name": "convert",
-                    "description": "Convert structured data between formats. Supported formats: json, yaml, toml, csv, ndjson. Input format is auto-detected if not specified.",
+                    "description": "Convert structured data between formats. Supported formats: json, json5, yaml, toml, csv, tsv, psv, ndjson, xml, msgpack, cbor, bson, ejson, ejson-canonical, env, ini, properties, plus parquet, arrow, xlsx, ods, logfmt, access-log and syslog as input. Input format is auto-detected if not specified.",
                     "inputSchema": {
                         "type": "object",
                         "properties": {
                             "input": {
                                 "type": "string",
-                                "description": "The input data to convert (as a string)"
+                                "description": "The input data to convert (as a string). Binary formats (msgpack, cbor, bson, parquet, arrow, xlsx, ods) are passed base64-encoded."
+                            },
+                            "input_encoding": {
+                                "type": "string",
+                                "description": "Set to base64 when input is base64-encoded bytes. Implied for binary input formats.",
+                                "enum": ["utf8", "base64"]
                             },
                             "input_format": {
                                 "type": "string",
-                                "description": "Input format: json, yaml, toml, csv, ndjson. Auto-detected if omitted.",
-                                "enum": ["json", "yaml", "toml", "csv", "ndjson"]
+                                "description": "Input format: json, json5, yaml, toml, csv, tsv, psv, ndjson, xml, msgpack, cbor, bson, ejson, ejson-canonical, env, ini, properties, parquet, arrow, xlsx, ods, logfmt, access-log, syslog. Auto-detected if omitted.",
+                                "enum": ["json", "json5", "yaml", "toml", "csv", "tsv", "psv", "ndjson", "xml", "msgpack", "cbor", "bson", "ejson", "ejson-canonical", "env", "ini", "properties", "parquet", "arrow", "xlsx", "ods", "logfmt", "access-log", "syslog"]
                             },
                             "output_format": {
                                 "type": "string",
-                                "description": "Output format: json, yaml, toml, csv, ndjson",
-                                "enum": ["json", "yaml", "toml", "csv", "ndjson"]
+                                "description": "Output format: json, json5, yaml, toml, csv, tsv, psv, ndjson, xml, msgpack, cbor, bson, ejson, ejson-canonical, env, ini, properties",
+                                "enum": ["json", "json5", "yaml", "toml", "csv", "tsv", "psv", "ndjson", "xml", "msgpack", "cbor", "bson", "ejson", "ejson-canonical", "env", "ini", "properties"]
                             }
                         },
                         "required": ["input", "output_format"]
@@ -137,13 +154,18 @@ fn handle_tools_list(id: Value) -> Value {
                 },
                 {
                     "name": "query",
-                    "description": "Query and transform structured data using jdx dot-notation. Supports path traversal (e.g. '.users[0].name'), filter predicates (e.g. '.items[price < 10]'), and transform commands (:keys, :values, :count, :flatten, :pick, :omit, :sort, :uniq, :group_by, :filter, :sum, :avg, :min, :max). Input format is auto-detected.",
+                    "description": query_description,
                     "inputSchema": {
                         "type": "object",
                         "properties": {
                             "input": {
                                 "type": "string",
-                                "description": "The input data to query (as a string)"
+                                "description": "The input data to query (as a string). Binary formats (msgpack, cbor, bson, parquet, arrow, xlsx, ods) are passed base64-encoded."
+                            },
+                            "input_encoding": {
+                                "type": "string",
+                                "description": "Set to base64 when input is base64-encoded bytes. Implied for binary input formats.",
+                                "enum": ["utf8", "base64"]
                             },
                             "query": {
                                 "type": "string",
@@ -151,13 +173,13 @@ fn handle_tools_list(id: Value) -> Value {
                             },
                             "input_format": {
                                 "type": "string",
-                                "description": "Input format: json, yaml, toml, csv, ndjson. Auto-detected if omitted.",
-                                "enum": ["json", "yaml", "toml", "csv", "ndjson"]
+                                "description": "Input format: json, json5, yaml, toml, csv, tsv, psv, ndjson, xml, msgpack, cbor, bson, ejson, ejson-canonical, env, ini, properties, parquet, arrow, xlsx, ods, logfmt, access-log, syslog. Auto-detected if omitted.",
+                                "enum": ["json", "json5", "yaml", "toml", "csv", "tsv", "psv", "ndjson", "xml", "msgpack", "cbor", "bson", "ejson", "ejson-canonical", "env", "ini", "properties", "parquet", "arrow", "xlsx", "ods", "logfmt", "access-log", "syslog"]
                             },
                             "output_format": {
                                 "type": "string",
-                                "description": "Output format for the result: json, yaml, toml, csv, ndjson. Defaults to json.",
-                                "enum": ["json", "yaml", "toml", "csv", "ndjson"]
+                                "description": "Output format for the result: json, json5, yaml, toml, csv, tsv, psv, ndjson, xml, msgpack, cbor, bson, ejson, ejson-canonical, env, ini, properties. Defaults to json.",
+                                "enum": ["json", "json5", "yaml", "toml", "csv", "tsv", "psv", "ndjson", "xml", "msgpack", "cbor", "bson", "ejson", "ejson-canonical", "env", "ini", "properties"]
                             }
                         },
                         "required": ["input", "query"]
@@ -209,12 +231,33 @@ fn handle_tools_call(id: Value, params: &Value) -> Value {
 // Tool implementations
 // ---------------------------------------------------------------------------
 
-/// Resolve a DataFormat from an optional string parameter, falling back to
-/// auto-detection from the content.
-fn resolve_input_format(args: &Value, content: &str) -> Result<DataFormat> {
-    match args.get("input_format").and_then(|v| v.as_str()) {
-        Some(fmt) => DataFormat::from_str_name(fmt),
-        None => Ok(detect_format(content)),
+/// The `input_format` parameter, if given.
+fn requested_input_format(args: &Value) -> Result<Option<DataFormat>> {
+    args.get("input_format")
+        .and_then(|v| v.as_str())
+        .map(DataFormat::from_str_name)
+        .transpose()
+}
+
+/// Parse input in the requested format, or in the detected one, where JSON
+/// that fails strictly is retried as JSON5. BSON and Extended JSON also
+/// return the types of their fields, to wrap them again on output.
+fn parse_as(
+    bytes: &[u8],
+    requested: Option<DataFormat>,
+    detect: impl FnOnce(&[u8]) -> DataFormat,
+) -> Result<(Value, TypeHints)> {
+    let format = requested.unwrap_or_else(|| detect(bytes));
+    match format {
+        DataFormat::Bson => bson_format::parse_typed(bytes),
+        DataFormat::Ejson | DataFormat::EjsonCanonical => {
+            ejson_format::parse_typed(&decode_text(bytes)?)
+        }
+        _ if requested.is_some() => Ok((parse_input_bytes(bytes, format)?, TypeHints::default())),
+        _ => Ok((
+            parse_detected_bytes_with(bytes, format, &FormatOptions::default())?,
+            TypeHints::default(),
+        )),
     }
 }
 
@@ -225,36 +268,58 @@ fn resolve_output_format(args: &Value, default: DataFormat) -> Result<DataFormat
     }
 }
 
-fn tool_convert(args: &Value) -> Result<String> {
+/// Parse the `input` parameter. Binary formats travel through MCP as base64
+/// text, both ways.
+fn parse_tool_input(args: &Value) -> Result<(Value, TypeHints)> {
     let input = args["input"]
         .as_str()
         .context("missing required parameter: input")?;
-    let in_fmt = resolve_input_format(args, input)?;
-    let out_fmt = resolve_output_format(args, DataFormat::Json)?;
+    let explicit = requested_input_format(args)?;
+    let base64_input = args.get("input_encoding").and_then(|v| v.as_str()) == Some("base64")
+        || explicit.is_some_and(DataFormat::is_binary);
+    if base64_input {
+        let bytes = engine::decode::base64_bytes(input)
+            .context("input is not valid base64 (binary formats are passed base64-encoded)")?;
+        parse_as(&bytes, explicit, detect_format_bytes)
+    } else {
+        parse_as(input.as_bytes(), explicit, |_| detect_format(input))
+    }
+    .context("failed to parse input data")
+}
 
-    let data = parse_input(input, in_fmt).context("failed to parse input data")?;
-    format_output(&data, out_fmt).context("failed to format output")
+fn format_tool_output(value: &Value, format: DataFormat, type_hints: TypeHints) -> Result<String> {
+    let options = FormatOptions {
+        type_hints,
+        ..FormatOptions::default()
+    };
+    if format.is_binary() {
+        let bytes =
+            format_output_bytes_with(value, format, &options).context("failed to format output")?;
+        return Ok(STANDARD.encode(bytes));
+    }
+    format_output_with(value, format, &options).context("failed to format output")
+}
+
+fn tool_convert(args: &Value) -> Result<String> {
+    let out_fmt = resolve_output_format(args, DataFormat::Json)?;
+    let (data, hints) = parse_tool_input(args)?;
+    format_tool_output(&data, out_fmt, hints)
 }
 
 fn tool_query(args: &Value) -> Result<String> {
-    let input = args["input"]
-        .as_str()
-        .context("missing required parameter: input")?;
     let query_str = args["query"]
         .as_str()
         .context("missing required parameter: query")?;
 
-    let in_fmt = resolve_input_format(args, input)?;
     let out_fmt = resolve_output_format(args, DataFormat::Json)?;
 
-    let data = parse_input(input, in_fmt).context("failed to parse input data")?;
+    let (data, hints) = parse_tool_input(args)?;
 
-    // Split query from transform commands (separated by ` :`)
-    let (path_part, transform_part) = split_query_and_transforms(query_str);
+    let (path_part, transform_part) = engine::lexer::split_query(query_str);
 
     // Parse and traverse the path
-    let segments = engine::query::parse(path_part)
-        .map_err(|e| anyhow::anyhow!("invalid query: {e}"))?;
+    let segments =
+        engine::query::parse(path_part).map_err(|e| anyhow::anyhow!("invalid query: {e}"))?;
     let traversal = engine::json::traverse(&data, &segments);
 
     let value = traversal
@@ -263,28 +328,12 @@ fn tool_query(args: &Value) -> Result<String> {
 
     // Apply transform commands if present
     let result = if let Some(transforms) = transform_part {
-        engine::transform::apply_transform(&value, transforms)
-            .context("transform failed")?
+        engine::transform::apply_transform(&value, transforms).context("transform failed")?
     } else {
         value
     };
 
-    format_output(&result, out_fmt).context("failed to format output")
-}
-
-/// Split a full query string into the path portion and the optional transform
-/// chain. For example:
-///   `.users :pick name,age :sort age`
-/// becomes `(".users", Some(":pick name,age :sort age"))`.
-fn split_query_and_transforms(query: &str) -> (&str, Option<&str>) {
-    // Look for the first ` :` that signals the start of transforms
-    if let Some(idx) = query.find(" :") {
-        let path = query[..idx].trim();
-        let transforms = query[idx + 1..].trim(); // keep the leading ':'
-        (path, Some(transforms))
-    } else {
-        (query, None)
-    }
+    format_tool_output(&result, out_fmt, hints.select(&segments))
 }
 
 #[cfg(test)]
@@ -292,18 +341,13 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_split_query_no_transforms() {
-        let (path, transforms) = split_query_and_transforms(".users[0].name");
-        assert_eq!(path, ".users[0].name");
-        assert!(transforms.is_none());
-    }
-
-    #[test]
-    fn test_split_query_with_transforms() {
-        let (path, transforms) =
-            split_query_and_transforms(".users :pick name,age :sort age");
-        assert_eq!(path, ".users");
-        assert_eq!(transforms.unwrap(), ":pick name,age :sort age");
+    fn test_tool_query_quoted_colon_in_filter() {
+        let args = serde_json::json!({
+            "input": r#"[{"name": "x :y", "n": 1}, {"name": "z", "n": 2}]"#,
+            "query": r#".[name == "x :y"] :pick n"#
+        });
+        let parsed: serde_json::Value = serde_json::from_str(&tool_query(&args).unwrap()).unwrap();
+        assert_eq!(parsed, serde_json::json!([{"n": 1}]));
     }
 
     #[test]
@@ -328,6 +372,85 @@ mod tests {
         assert_eq!(parsed["name"], "Alice");
     }
 
+    #[test]
+    fn test_tool_convert_msgpack_base64() {
+        let args = serde_json::json!({
+            "input": "{\"id\": 7, \"tags\": [\"a\"]}",
+            "output_format": "msgpack"
+        });
+        let encoded = tool_convert(&args).unwrap();
+        assert_eq!(encoded, "gqJpZAekdGFnc5GhYQ==");
+
+        let args = serde_json::json!({
+            "input": encoded,
+            "input_format": "msgpack",
+            "output_format": "json"
+        });
+        let parsed: serde_json::Value =
+            serde_json::from_str(&tool_convert(&args).unwrap()).unwrap();
+        assert_eq!(parsed, serde_json::json!({"id": 7, "tags": ["a"]}));
+    }
+
+    #[test]
+    fn test_binary_input_detected_from_base64() {
+        // {"id": 7} as CBOR, without an input_format
+        let args = serde_json::json!({
+            "input": "oWJpZAc=",
+            "input_encoding": "base64",
+            "query": ".id"
+        });
+        assert_eq!(tool_query(&args).unwrap().trim(), "7");
+
+        let args = serde_json::json!({
+            "input": "{\"id\": 7}",
+            "query": ".",
+            "output_format": "msgpack"
+        });
+        assert_eq!(tool_query(&args).unwrap(), "gaJpZAc=");
+    }
+
+    #[test]
+    fn test_listed_formats_are_supported() {
+        let tools = handle_tools_list(Value::Null)["result"]["tools"].clone();
+        for tool in tools.as_array().unwrap() {
+            let properties = &tool["inputSchema"]["properties"];
+            for name in properties["input_format"]["enum"].as_array().unwrap() {
+                DataFormat::from_str_name(name.as_str().unwrap()).unwrap();
+            }
+            for name in properties["output_format"]["enum"].as_array().unwrap() {
+                let format = DataFormat::from_str_name(name.as_str().unwrap()).unwrap();
+                // Tabular formats want rows, key-value formats an object
+                let hints = TypeHints::default;
+                format_tool_output(&json!([{"a": "x"}]), format, hints())
+                    .or_else(|_| format_tool_output(&json!({"a": "x"}), format, hints()))
+                    .unwrap_or_else(|e| panic!("{name}: {e}"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_tool_query_xml_output() {
+        let args = serde_json::json!({
+            "input": "{\"user\": {\"name\": \"Alice\"}}",
+            "query": ".",
+            "output_format": "xml"
+        });
+        let result = tool_query(&args).unwrap();
+        assert!(result.contains("<name>Alice</name>"), "{result}");
+    }
+
+    #[test]
+    fn test_tool_convert_xml_to_json() {
+        let args = serde_json::json!({
+            "input": "<?xml version=\"1.0\"?><user id=\"7\"><name>Alice</name></user>",
+            "output_format": "json"
+        });
+        let result = tool_convert(&args).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(parsed["user"]["@id"], "7");
+        assert_eq!(parsed["user"]["name"], "Alice");
+    }
+
     #[test]
     fn test_tool_query_simple() {
         let args = serde_json::json!({
@@ -351,6 +474,152 @@ mod tests {
         assert_eq!(arr[1]["name"], "Alice");
     }
 
+    #[test]
+    fn test_tool_convert_bson_keeps_types() {
+        let args = serde_json::json!({
+            "input": "{\"_id\": {\"$oid\": \"65a1b2c3d4e5f60718293a4b\"}, \"n\": {\"$numberLong\": \"5\"}}",
+            "input_format": "ejson",
+            "output_format": "bson"
+        });
+        let bson = tool_convert(&args).unwrap();
+
+        let args = serde_json::json!({
+            "input": bson,
+            "input_format": "bson",
+            "query": ".[0]",
+            "output_format": "ejson-canonical"
+        });
+        let parsed: serde_json::Value = serde_json::from_str(&tool_query(&args).unwrap()).unwrap();
+        assert_eq!(
+            parsed,
+            serde_json::json!({
+                "_id": {"$oid": "65a1b2c3d4e5f60718293a4b"},
+                "n": {"$numberLong": "5"}
+            })
+        );
+    }
+
+    #[test]
+    fn test_tool_query_parquet_base64() {
+        use arrow_array::{ArrayRef, Int64Array, RecordBatch, StringArray};
+        use std::sync::Arc;
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef),
+            (
+                "name",
+                Arc::new(StringArray::from(vec!["a", "b"])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let mut parquet = Vec::new();
+        let mut writer =
+            parquet::arrow::ArrowWriter::try_new(&mut parquet, batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let args = serde_json::json!({
+            "input": STANDARD.encode(&parquet),
+            "input_encoding": "base64",
+            "query": ".[1].name"
+        });
+        assert_eq!(tool_query(&args).unwrap().trim(), "\"b\"");
+    }
+
+    #[test]
+    fn test_tool_query_spreadsheet_base64() {
+        let mut workbook = rust_xlsxwriter::Workbook::new();
+        let sheet = workbook.add_worksheet().set_name("Q1").unwrap();
+        sheet.write(0, 0, "name").unwrap();
+        sheet.write(1, 0, "Ada").unwrap();
+        let bytes = workbook.save_to_buffer().unwrap();
+
+        let args = serde_json::json!({
+            "input": STANDARD.encode(&bytes),
+            "input_format": "xlsx",
+            "query": ".Q1[0].name"
+        });
+        assert_eq!(tool_query(&args).unwrap().trim(), "\"Ada\"");
+    }
+
+    #[test]
+    fn test_tool_convert_csv_dialects() {
+        let args = serde_json::json!({
+            "input": "name\tzip\nAnn\t02139\n",
+            "output_format": "psv"
+        });
+        assert_eq!(tool_convert(&args).unwrap(), "name|zip\nAnn|02139\n");
+
+        let args = serde_json::json!({
+            "input": "a;b\n1;2\n",
+            "input_format": "csv",
+            "query": ".[0].b"
+        });
+        assert_eq!(tool_query(&args).unwrap().trim(), "2");
+    }
+
+    #[test]
+    fn test_tool_query_logs() {
+        let args = serde_json::json!({
+            "input": "level=info status=200\nlevel=error status=500\n",
+            "query": ".[status >= 500]"
+        });
+        let parsed: serde_json::Value = serde_json::from_str(&tool_query(&args).unwrap()).unwrap();
+        assert_eq!(
+            parsed,
+            serde_json::json!([{"level": "error", "status": 500}])
+        );
+
+        let args = serde_json::json!({
+            "input": "127.0.0.1 - frank [10/Oct/2000:13:55:36 -0700] \"GET /a.gif HTTP/1.0\" 200 2326",
+            "input_format": "access-log",
+            "query": ".[0].path"
+        });
+        assert_eq!(tool_query(&args).unwrap().trim(), "\"/a.gif\"");
+    }
+
+    #[test]
+    fn test_tool_convert_config_formats() {
+        let args = serde_json::json!({
+            "input": "# db\nDB_HOST=localhost\nexport DB_PORT=5432\n",
+            "output_format": "json"
+        });
+        let parsed: serde_json::Value =
+            serde_json::from_str(&tool_convert(&args).unwrap()).unwrap();
+        assert_eq!(
+            parsed,
+            serde_json::json!({"DB_HOST": "localhost", "DB_PORT": "5432"})
+        );
+
+        let args = serde_json::json!({
+            "input": "{\"server\": {\"port\": 8080}}",
+            "output_format": "properties"
+        });
+        assert_eq!(tool_convert(&args).unwrap(), "server.port=8080\n");
+
+        let args = serde_json::json!({
+            "input": "; app\n[server]\nhost = 0.0.0.0\n",
+            "query": ".server.host"
+        });
+        assert_eq!(tool_query(&args).unwrap().trim(), "\"0.0.0.0\"");
+    }
+
+    #[test]
+    fn test_tool_convert_json5_only_when_detected() {
+        let jsonc = "{\n  \"a\": 1, // one\n  \"b\": [2,],\n}";
+        let args = serde_json::json!({"input": jsonc, "output_format": "json"});
+        let parsed: serde_json::Value =
+            serde_json::from_str(&tool_convert(&args).unwrap()).unwrap();
+        assert_eq!(parsed, serde_json::json!({"a": 1, "b": [2]}));
+
+        let args = serde_json::json!({
+            "input": jsonc,
+            "input_format": "json",
+            "output_format": "json"
+        });
+        let err = format!("{:#}", tool_convert(&args).unwrap_err());
+        assert!(err.contains("invalid JSON at line 2"), "{err}");
+    }
+
     #[test]
     fn test_tool_convert_missing_input() {
         let args = serde_json::json!({
@@ -6,6 +6,7 @@ use ratatui::{
     widgets::{Block, Borders, Clear, Paragraph, Widget, Wrap},
 };
 
+use crate::engine::registry;
 use crate::modes::AppMode;
 
 /// Help overlay showing keybindings for the current mode.
@@ -17,7 +18,7 @@ impl Widget for HelpOverlayWidget {
     fn render(self, area: Rect, buf: &mut Buffer) {
         // Center the help popup
         let width = 60.min(area.width.saturating_sub(4));
-        let height = 28.min(area.height.saturating_sub(4));
+        let height = 40.min(area.height.saturating_sub(4));
         let x = area.x + (area.width.saturating_sub(width)) / 2;
         let y = area.y + (area.height.saturating_sub(height)) / 2;
         let popup = Rect::new(x, y, width, height);
@@ -37,7 +38,16 @@ impl Widget for HelpOverlayWidget {
             .add_modifier(Modifier::BOLD);
         let desc_style = Style::default().fg(Color::White);
 
-        let bindings = get_bindings(self.mode);
+        let mut bindings: Vec<(&str, String)> = get_bindings(self.mode)
+            .into_iter()
+            .map(|(key, desc)| (key, desc.to_string()))
+            .collect();
+        if self.mode == AppMode::Query {
+            let desc_width = usize::from(inner.width).saturating_sub(16).max(20);
+            for (i, chunk) in transform_name_lines(desc_width).into_iter().enumerate() {
+                bindings.push((if i == 0 { "Transforms" } else { "" }, chunk));
+            }
+        }
         let lines: Vec<Line> = bindings
             .into_iter()
             .map(|(key, desc)| {
@@ -53,6 +63,21 @@ impl Widget for HelpOverlayWidget {
     }
 }
 
+/// Names of all registered transforms, packed into lines of at most `width`.
+fn transform_name_lines(width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for name in registry::snapshot().names() {
+        match lines.last_mut() {
+            Some(line) if line.len() + 1 + name.len() <= width => {
+                line.push(' ');
+                line.push_str(&name);
+            }
+            _ => lines.push(name),
+        }
+    }
+    lines
+}
+
 fn get_bindings(mode: AppMode) -> Vec<(&'static str, &'static str)> {
     let mut bindings = vec![("?", "Toggle this help"), ("Ctrl+C", "Quit")];
 
@@ -79,7 +104,6 @@ fn get_bindings(mode: AppMode) -> Vec<(&'static str, &'static str)> {
                 ("/", "AI mode"),
                 ("", ""),
                 ("Filters", "[field > 5 && field < 20]"),
-                ("Transforms", ":sort :pick :filter :reverse ..."),
             ]);
         }
         AppMode::Tree => {
@@ -88,6 +112,7 @@ fn get_bindings(mode: AppMode) -> Vec<(&'static str, &'static str)> {
                 ("↑/↓", "Navigate tree"),
                 ("→/Enter", "Expand node"),
                 ("←", "Collapse node"),
+                ("d", "Decode string as… (JSON, JWT, base64, URL)"),
                 ("Ctrl+J/K", "Scroll"),
                 ("Ctrl+S", "Close split view"),
             ]);
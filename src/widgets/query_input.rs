@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
@@ -17,6 +19,8 @@ pub struct QueryInputWidget<'a> {
     pub completion: Option<&'a str>,
     /// Whether there's a validation error
     pub error: bool,
+    /// Byte range of the query to highlight as the error location
+    pub error_span: Option<Range<usize>>,
     /// Whether this widget currently has input focus
     pub focused: bool,
 }
@@ -46,10 +50,24 @@ impl<'a> Widget for QueryInputWidget<'a> {
 
         let completion_style = Style::default().fg(Color::DarkGray);
 
-        let mut spans = vec![
-            Span::styled(prompt, prompt_style),
-            Span::styled(self.query, query_style),
-        ];
+        let mut spans = vec![Span::styled(prompt, prompt_style)];
+        match self.error_span.clone().filter(|r| {
+            r.start < r.end
+                && r.end <= self.query.len()
+                && self.query.is_char_boundary(r.start)
+                && self.query.is_char_boundary(r.end)
+        }) {
+            Some(r) => {
+                let error_style = Style::default()
+                    .fg(Color::White)
+                    .bg(Color::Red)
+                    .add_modifier(Modifier::BOLD);
+                spans.push(Span::styled(&self.query[..r.start], query_style));
+                spans.push(Span::styled(&self.query[r.clone()], error_style));
+                spans.push(Span::styled(&self.query[r.end..], query_style));
+            }
+            None => spans.push(Span::styled(self.query, query_style)),
+        }
 
         if let Some(completion) = self.completion {
             spans.push(Span::styled(completion, completion_style));
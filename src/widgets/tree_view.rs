@@ -28,6 +28,33 @@ pub fn build_tree(
     nodes
 }
 
+/// Relabel the top-level nodes of a multi-document YAML stream as
+/// `--- doc N`, previewing Kubernetes-style `kind name` when present.
+pub fn label_documents(nodes: &mut [TreeNode], docs: &Value) {
+    let Value::Array(docs) = docs else {
+        return;
+    };
+    for node in nodes.iter_mut().filter(|n| n.depth == 0) {
+        let Some(i) = node
+            .path
+            .strip_prefix('[')
+            .and_then(|p| p.strip_suffix(']'))
+            .and_then(|p| p.parse::<usize>().ok())
+        else {
+            continue;
+        };
+        node.key = format!("--- doc {}", i + 1);
+        let kind = docs[i].get("kind").and_then(Value::as_str);
+        let name = docs[i].pointer("/metadata/name").and_then(Value::as_str);
+        if let Some(kind) = kind {
+            node.value_preview = match name {
+                Some(name) => format!("{kind} {name}"),
+                None => kind.to_string(),
+            };
+        }
+    }
+}
+
 fn build_tree_recursive(
     value: &Value,
     path: &str,
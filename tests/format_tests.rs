@@ -1,4 +1,8 @@
-use jdx::format::{detect_format, format_output, parse_input, DataFormat};
+use jdx::format::csv_format::CsvOptions;
+use jdx::format::{
+    decode_text, detect_format, format_output, format_output_with, format_raw,
+    parse_detected_bytes_with, parse_input, parse_input_with, DataFormat, FormatOptions,
+};
 use serde_json::json;
 
 #[test]
@@ -131,5 +135,525 @@ fn test_format_from_str_name() {
         DataFormat::from_str_name("jsonl").unwrap(),
         DataFormat::Ndjson
     );
+    assert_eq!(DataFormat::from_str_name("xml").unwrap(), DataFormat::Xml);
+    assert_eq!(
+        DataFormat::from_str_name("jsonc").unwrap(),
+        DataFormat::Json5
+    );
+    assert_eq!(
+        DataFormat::from_str_name("json5").unwrap(),
+        DataFormat::Json5
+    );
+    assert_eq!(DataFormat::from_str_name("bson").unwrap(), DataFormat::Bson);
+    assert_eq!(
+        DataFormat::from_str_name("extjson").unwrap(),
+        DataFormat::Ejson
+    );
+    assert_eq!(
+        DataFormat::from_str_name("parquet").unwrap(),
+        DataFormat::Parquet
+    );
+    assert_eq!(
+        DataFormat::from_str_name("feather").unwrap(),
+        DataFormat::Arrow
+    );
+    assert_eq!(
+        DataFormat::from_str_name("ods").unwrap(),
+        DataFormat::Spreadsheet
+    );
+    assert_eq!(DataFormat::from_str_name("tsv").unwrap(), DataFormat::Tsv);
+    assert_eq!(DataFormat::from_str_name("psv").unwrap(), DataFormat::Psv);
+    assert_eq!(
+        DataFormat::from_str_name("nginx").unwrap(),
+        DataFormat::AccessLog
+    );
+    assert_eq!(
+        DataFormat::from_str_name("rfc5424").unwrap(),
+        DataFormat::Syslog
+    );
+    assert_eq!(
+        DataFormat::from_str_name("dotenv").unwrap(),
+        DataFormat::Env
+    );
+    assert_eq!(DataFormat::from_str_name("ini").unwrap(), DataFormat::Ini);
+    assert_eq!(
+        DataFormat::from_str_name("properties").unwrap(),
+        DataFormat::Properties
+    );
     assert!(DataFormat::from_str_name("unknown").is_err());
 }
+
+#[test]
+fn test_format_raw() {
+    assert_eq!(format_raw(&json!("plain text")), Some("plain text".into()));
+    assert_eq!(
+        format_raw(&json!(["Ada — 3 orders", "Bob — 0 orders"])),
+        Some("Ada — 3 orders\nBob — 0 orders".into())
+    );
+    assert_eq!(
+        format_raw(&json!([1, true, null])),
+        Some("1\ntrue\nnull".into())
+    );
+    assert_eq!(format_raw(&json!({"a": 1})), None);
+    assert_eq!(format_raw(&json!([[1]])), None);
+}
+
+#[test]
+fn test_detect_xml() {
+    assert_eq!(
+        detect_format("<?xml version=\"1.0\"?>\n<a/>"),
+        DataFormat::Xml
+    );
+    assert_eq!(
+        detect_format("<project>\n  <version>1.0</version>\n</project>"),
+        DataFormat::Xml
+    );
+    assert_eq!(detect_format("<!-- feed -->\n<rss/>"), DataFormat::Xml);
+}
+
+#[test]
+fn test_xml_roundtrip() {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<project xmlns="http://maven.apache.org/POM/4.0.0">
+  <artifactId>demo</artifactId>
+  <dependencies>
+    <dependency scope="test"><artifactId>junit</artifactId></dependency>
+    <dependency><artifactId>guava</artifactId><optional/></dependency>
+  </dependencies>
+  <description lang="en">Tools &amp; more</description>
+</project>"#;
+    let value = parse_input(xml, DataFormat::Xml).unwrap();
+    assert_eq!(
+        value,
+        json!({"project": {
+            "@xmlns": "http://maven.apache.org/POM/4.0.0",
+            "artifactId": "demo",
+            "dependencies": {"dependency": [
+                {"@scope": "test", "artifactId": "junit"},
+                {"artifactId": "guava", "optional": null}
+            ]},
+            "description": {"@lang": "en", "#text": "Tools & more"}
+        }})
+    );
+
+    let written = format_output(&value, DataFormat::Xml).unwrap();
+    assert!(
+        written.contains(r#"<dependency scope="test">"#),
+        "{written}"
+    );
+    assert_eq!(parse_input(&written, DataFormat::Xml).unwrap(), value);
+}
+
+#[test]
+fn test_json_to_xml_roundtrip() {
+    let value = json!({"rss": {"@version": "2.0", "channel": {
+        "title": "News",
+        "item": [{"title": "One"}, {"title": "Two"}]
+    }}});
+    let xml = format_output(&value, DataFormat::Xml).unwrap();
+    assert_eq!(parse_input(&xml, DataFormat::Xml).unwrap(), value);
+
+    // Values without a single root key are wrapped in <root>
+    let xml = format_output(&json!([{"id": "1"}, {"id": "2"}]), DataFormat::Xml).unwrap();
+    assert_eq!(
+        parse_input(&xml, DataFormat::Xml).unwrap(),
+        json!({"root": {"item": [{"id": "1"}, {"id": "2"}]}})
+    );
+}
+
+#[test]
+fn test_jsonc_detected_and_parsed() {
+    let tsconfig = r#"// tsconfig
+{
+  "compilerOptions": {
+    "strict": true, // always
+    "paths": {"@/*": ["src/*"],},
+  },
+}"#;
+    assert_eq!(detect_format(tsconfig), DataFormat::Json5);
+    let value = parse_input(tsconfig, detect_format(tsconfig)).unwrap();
+    assert_eq!(value["compilerOptions"]["paths"]["@/*"], json!(["src/*"]));
+
+    // Without a leading comment it looks like JSON and falls back on failure
+    let settings = "{\n  \"a\": 1, /* one */\n  'b': [2,],\n}";
+    assert_eq!(detect_format(settings), DataFormat::Json);
+    assert_eq!(
+        parse_detected_bytes_with(
+            settings.as_bytes(),
+            DataFormat::Json,
+            &FormatOptions::default()
+        )
+        .unwrap(),
+        json!({"a": 1, "b": [2]})
+    );
+
+    // An explicit `--input json` stays strict and reports the JSON error
+    let err = parse_input(settings, DataFormat::Json)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("line 2"), "{err}");
+
+    // Broken JSON that isn't JSON5 either keeps the JSON error
+    let broken = "{\n  \"a\": [\n    1, 2\n}";
+    let err = parse_detected_bytes_with(
+        broken.as_bytes(),
+        DataFormat::Json,
+        &FormatOptions::default(),
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(err.contains("expected `,` or `]`"), "{err}");
+    assert!(err.contains("line 4"), "{err}");
+}
+
+#[test]
+fn test_json_parse_error_has_location() {
+    let err = parse_input("{\n  \"a\": 1,\n  \"b\": ?\n}", DataFormat::Json)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("line 3, column 8"), "{err}");
+    assert!(err.contains("3 |   \"b\": ?"), "{err}");
+}
+
+#[test]
+fn test_multi_document_yaml() {
+    let manifests = "---\napiVersion: v1\nkind: Service\nmetadata:\n  name: web\n---\n# Source: chart/templates/empty.yaml\n---\napiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: web\n";
+    assert_eq!(detect_format(manifests), DataFormat::Yaml);
+    let value = parse_input(manifests, DataFormat::Yaml).unwrap();
+    assert_eq!(value.as_array().unwrap().len(), 2);
+    assert_eq!(value[1]["kind"], "Deployment");
+
+    let docs = value.as_array().unwrap();
+    let written = jdx::format::yaml_format::serialize_documents(docs).unwrap();
+    assert_eq!(written.matches("---\n").count(), 2);
+    assert_eq!(parse_input(&written, DataFormat::Yaml).unwrap(), value);
+}
+
+#[test]
+fn test_detect_binary_formats() {
+    use jdx::format::{detect_format_bytes, format_output_bytes};
+    let value = json!({"id": 1, "tags": ["a", "b"]});
+    let msgpack = format_output_bytes(&value, DataFormat::MsgPack).unwrap();
+    let cbor = format_output_bytes(&value, DataFormat::Cbor).unwrap();
+    assert_eq!(detect_format_bytes(&msgpack), DataFormat::MsgPack);
+    assert_eq!(detect_format_bytes(&cbor), DataFormat::Cbor);
+    assert_eq!(
+        detect_format_bytes(&[0xd9, 0xd9, 0xf7, 0xa0]),
+        DataFormat::Cbor
+    );
+    assert_eq!(detect_format_bytes(b"{\"a\": 1}"), DataFormat::Json);
+
+    let list = format_output_bytes(&json!([1, 2]), DataFormat::MsgPack).unwrap();
+    assert_eq!(detect_format_bytes(&list), DataFormat::MsgPack);
+}
+
+#[test]
+fn test_detect_invalid_utf8_as_text() {
+    use jdx::format::{detect_format_bytes, parse_input_bytes};
+    // Latin-1 text is neither UTF-8 nor a binary document
+    let latin1 = b"name: Jos\xe9\n";
+    let format = detect_format_bytes(latin1);
+    assert!(!format.is_binary(), "{format:?}");
+    let err = format!("{:#}", parse_input_bytes(latin1, format).unwrap_err());
+    assert!(err.contains("UTF-8"), "{err}");
+
+    // A map header whose body doesn't parse is not taken for a document
+    assert!(!detect_format_bytes(&[0x82, 0xa1]).is_binary());
+}
+
+#[test]
+fn test_binary_roundtrip() {
+    use jdx::format::{format_output_bytes, parse_input_bytes};
+    let value = json!({
+        "id": 42,
+        "score": 0.5,
+        "payload": {"$binary": "3q2+7w=="},
+        "nested": [{"ok": true}, null]
+    });
+    for format in [DataFormat::MsgPack, DataFormat::Cbor] {
+        let bytes = format_output_bytes(&value, format).unwrap();
+        assert_eq!(
+            parse_input_bytes(&bytes, format).unwrap(),
+            value,
+            "{format:?}"
+        );
+    }
+    assert!(format_output(&value, DataFormat::MsgPack).is_err());
+
+    let err = parse_input_bytes(&[0xc3, 0x28], DataFormat::Json).unwrap_err();
+    assert!(err.to_string().contains("--input msgpack"), "{err}");
+}
+
+#[test]
+fn test_bson_detected_and_roundtrips() {
+    use jdx::format::{detect_format_bytes, ejson_format, format_output_bytes_with};
+    let (docs, type_hints) = ejson_format::parse_typed(
+        r#"[{"_id": {"$oid": "65a1b2c3d4e5f60718293a4b"}, "n": {"$numberLong": "7"}},
+            {"at": {"$date": "2024-01-01T00:00:00Z"}}]"#,
+    )
+    .unwrap();
+    assert_eq!(docs[0]["_id"], json!("65a1b2c3d4e5f60718293a4b"));
+
+    let options = FormatOptions {
+        type_hints,
+        ..FormatOptions::default()
+    };
+    let bytes = format_output_bytes_with(&docs, DataFormat::Bson, &options).unwrap();
+    assert_eq!(detect_format_bytes(&bytes), DataFormat::Bson);
+    let (read, hints) = jdx::format::bson_format::parse_typed(&bytes).unwrap();
+    assert_eq!(read, docs);
+    assert_eq!(hints, options.type_hints);
+    assert!(format_output_bytes_with(&json!([1]), DataFormat::Bson, &options).is_err());
+}
+
+#[test]
+fn test_ejson_preserves_types_on_output() {
+    use jdx::format::ejson_format;
+    let (value, type_hints) = ejson_format::parse_typed(
+        r#"{"_id": {"$oid": "65a1b2c3d4e5f60718293a4b"},
+            "at": {"$date": {"$numberLong": "1704067200000"}},
+            "n": {"$numberLong": "5"}}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        value,
+        json!({"_id": "65a1b2c3d4e5f60718293a4b", "at": "2024-01-01T00:00:00Z", "n": 5})
+    );
+    let options = FormatOptions {
+        type_hints,
+        ..FormatOptions::default()
+    };
+
+    let relaxed: serde_json::Value =
+        serde_json::from_str(&format_output_with(&value, DataFormat::Ejson, &options).unwrap())
+            .unwrap();
+    assert_eq!(relaxed["_id"], json!({"$oid": "65a1b2c3d4e5f60718293a4b"}));
+    assert_eq!(relaxed["at"], json!({"$date": "2024-01-01T00:00:00Z"}));
+    assert_eq!(relaxed["n"], json!(5));
+
+    let canonical = format_output_with(&value, DataFormat::EjsonCanonical, &options).unwrap();
+    let canonical: serde_json::Value = serde_json::from_str(&canonical).unwrap();
+    assert_eq!(
+        canonical["at"],
+        json!({"$date": {"$numberLong": "1704067200000"}})
+    );
+    assert_eq!(canonical["n"], json!({"$numberLong": "5"}));
+
+    // Without the hints of the input, values are written as plain JSON
+    let plain = format_output(&value, DataFormat::EjsonCanonical).unwrap();
+    assert!(!plain.contains("$oid"), "{plain}");
+}
+
+fn sample_batch() -> arrow_array::RecordBatch {
+    use arrow_array::{ArrayRef, Int64Array, StringArray};
+    use std::sync::Arc;
+    arrow_array::RecordBatch::try_from_iter([
+        ("id", Arc::new(Int64Array::from(vec![1, 2, 3])) as ArrayRef),
+        (
+            "name",
+            Arc::new(StringArray::from(vec!["a", "b", "c"])) as ArrayRef,
+        ),
+    ])
+    .unwrap()
+}
+
+#[test]
+fn test_columnar_formats_detected_and_read() {
+    use jdx::format::{detect_format_bytes, parse_input_bytes, read_table_bytes, TableOptions};
+
+    let batch = sample_batch();
+    let mut parquet = Vec::new();
+    let mut writer =
+        parquet::arrow::ArrowWriter::try_new(&mut parquet, batch.schema(), None).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+
+    let mut arrow = arrow_ipc::writer::FileWriter::try_new(Vec::new(), &batch.schema()).unwrap();
+    arrow.write(&batch).unwrap();
+    let arrow = arrow.into_inner().unwrap();
+
+    let options = TableOptions {
+        columns: Some(vec!["name".into()]),
+        limit: Some(2),
+    };
+    for (bytes, format) in [(parquet, DataFormat::Parquet), (arrow, DataFormat::Arrow)] {
+        assert_eq!(detect_format_bytes(&bytes), format);
+        assert_eq!(
+            parse_input_bytes(&bytes, format).unwrap()[2],
+            json!({"id": 3, "name": "c"})
+        );
+        let table = read_table_bytes(&bytes, format, &options).unwrap();
+        assert_eq!(
+            table.rows,
+            json!([{"name": "a"}, {"name": "b"}]),
+            "{format:?}"
+        );
+        assert!(table.schema.contains("name"), "{}", table.schema);
+        assert!(jdx::format::format_output_bytes(&table.rows, format).is_err());
+    }
+}
+
+#[test]
+fn test_spreadsheet_detected_and_parsed() {
+    use jdx::format::{detect_format_bytes, format_output_bytes, parse_input_bytes};
+
+    let mut workbook = rust_xlsxwriter::Workbook::new();
+    let sheet = workbook.add_worksheet().set_name("Q1").unwrap();
+    sheet.write(0, 0, "name").unwrap();
+    sheet.write(0, 1, "paid").unwrap();
+    sheet.write(1, 0, "Ada").unwrap();
+    sheet.write(1, 1, false).unwrap();
+    let bytes = workbook.save_to_buffer().unwrap();
+
+    assert_eq!(detect_format_bytes(&bytes), DataFormat::Spreadsheet);
+    let value = parse_input_bytes(&bytes, DataFormat::Spreadsheet).unwrap();
+    assert_eq!(value, json!({"Q1": [{"name": "Ada", "paid": false}]}));
+    assert!(format_output_bytes(&value, DataFormat::Spreadsheet).is_err());
+}
+
+#[test]
+fn test_csv_dialects_detected() {
+    let tsv = "name\tzip\nAnn\t02139\nBob\t10001\n";
+    assert_eq!(detect_format(tsv), DataFormat::Tsv);
+    let rows = parse_input(tsv, DataFormat::Tsv).unwrap();
+    assert_eq!(rows[0], json!({"name": "Ann", "zip": "02139"}));
+    assert_eq!(format_output(&rows, DataFormat::Tsv).unwrap(), tsv);
+
+    assert_eq!(detect_format("a|b\n1|2\n3|4\n"), DataFormat::Psv);
+    let semicolons = "a;b\n1;2\n3;4\n";
+    assert_eq!(detect_format(semicolons), DataFormat::Csv);
+    assert_eq!(
+        parse_input(semicolons, DataFormat::Csv).unwrap(),
+        json!([{"a": 1, "b": 2}, {"a": 3, "b": 4}])
+    );
+}
+
+#[test]
+fn test_csv_options_apply_per_call() {
+    let options = FormatOptions {
+        csv: CsvOptions {
+            quote: b'\'',
+            has_headers: false,
+            ..CsvOptions::default()
+        },
+        ..FormatOptions::default()
+    };
+    let rows = parse_input_with("'a,b';c\n'd;e';f\n", DataFormat::Csv, &options).unwrap();
+    assert_eq!(
+        rows,
+        json!([{"col0": "a,b", "col1": "c"}, {"col0": "d;e", "col1": "f"}])
+    );
+    // TSV keeps its own delimiter but takes the rest of the dialect
+    assert_eq!(
+        format_output_with(&rows, DataFormat::Tsv, &options).unwrap(),
+        "a,b\tc\nd;e\tf\n"
+    );
+    // Calls without options are unaffected
+    assert_eq!(
+        parse_input("x,y\n1,2\n", DataFormat::Csv).unwrap(),
+        json!([{"x": 1, "y": 2}])
+    );
+}
+
+#[test]
+fn test_decode_text_strips_bom() {
+    assert_eq!(
+        decode_text(b"\xef\xbb\xbfa,b\n1,2\n").unwrap(),
+        "a,b\n1,2\n"
+    );
+    let utf16: Vec<u8> = [0xff, 0xfe]
+        .into_iter()
+        .chain("a\tb\n".encode_utf16().flat_map(u16::to_le_bytes))
+        .collect();
+    assert_eq!(decode_text(&utf16).unwrap(), "a\tb\n");
+}
+
+#[test]
+fn test_log_formats_detected_and_parsed() {
+    use jdx::format::parse_line;
+
+    let logfmt = "ts=1 level=info msg=\"a: b\"\nts=2 level=warn msg=\"x = y\"\n";
+    assert_eq!(detect_format(logfmt), DataFormat::Logfmt);
+    assert_eq!(
+        parse_input(logfmt, DataFormat::Logfmt).unwrap()[1],
+        json!({"ts": 2, "level": "warn", "msg": "x = y"})
+    );
+
+    let access =
+        "::1 - - [01/Mar/2024:08:00:01 +0000] \"GET / HTTP/1.1\" 200 612 \"-\" \"curl/8\"\n";
+    assert_eq!(detect_format(access), DataFormat::AccessLog);
+    assert_eq!(
+        parse_input(access, DataFormat::AccessLog).unwrap()[0]["time"],
+        "2024-03-01T08:00:01+00:00"
+    );
+
+    let syslog = "<13>1 2024-03-01T08:00:01Z web nginx 812 - - started\n";
+    assert_eq!(detect_format(syslog), DataFormat::Syslog);
+    assert_eq!(
+        parse_line(syslog.trim(), DataFormat::Syslog).unwrap()["severity"],
+        "notice"
+    );
+
+    // Single pairs per line and TOML assignments are not logfmt
+    assert_eq!(detect_format("a = 1\nb = 2\n"), DataFormat::Toml);
+    assert!(DataFormat::Syslog.is_line_oriented());
+    assert!(format_output(&json!([]), DataFormat::Logfmt).is_err());
+    let err = parse_input("level=info ok\nmsg=\"open\n", DataFormat::Logfmt).unwrap_err();
+    assert!(err.to_string().starts_with("line 2:"), "{err}");
+}
+
+#[test]
+fn test_config_formats_detected_and_roundtrip() {
+    use jdx::format::detect_format_from_name;
+
+    let env = "# secrets\nDB_PASSWORD=hunter2\nexport PORT=5432\n";
+    assert_eq!(detect_format(env), DataFormat::Env);
+    // `.env` wins over TOML, which would also accept this
+    assert_eq!(detect_format("PORT=1\nDEBUG=true\n"), DataFormat::Env);
+    let parsed = parse_input(env, DataFormat::Env).unwrap();
+    assert_eq!(parsed, json!({"DB_PASSWORD": "hunter2", "PORT": "5432"}));
+    assert!(DataFormat::Env.holds_secrets());
+    assert!(!DataFormat::Ini.holds_secrets());
+
+    let ini = "; app\n[server]\nhost = 0.0.0.0\nport = 8080\n";
+    assert_eq!(detect_format(ini), DataFormat::Ini);
+    let parsed = parse_input(ini, DataFormat::Ini).unwrap();
+    assert_eq!(parsed["server"]["port"], "8080");
+    let written = format_output(&parsed, DataFormat::Ini).unwrap();
+    assert_eq!(parse_input(&written, DataFormat::Ini).unwrap(), parsed);
+
+    let properties = "server.url=http://localhost:8080/\nserver.name=demo\n";
+    assert_eq!(detect_format(properties), DataFormat::Properties);
+    let parsed = parse_input(properties, DataFormat::Properties).unwrap();
+    assert_eq!(parsed["server.url"], "http://localhost:8080/");
+    let written = format_output(&json!({"server": {"url": "x"}}), DataFormat::Properties);
+    assert_eq!(written.unwrap(), "server.url=x\n");
+
+    // TOML keeps its own detection
+    assert_eq!(detect_format("[server]\nport = 8080\n"), DataFormat::Toml);
+    assert_eq!(detect_format("a.b = \"x\"\n"), DataFormat::Toml);
+    // Lower-case keys with quoted values are valid TOML too, but stay `.env`
+    // so their values are redacted
+    let quoted = "api_key=\"sk-123\"\ndb_password=\"x\"\n";
+    assert_eq!(detect_format(quoted), DataFormat::Env);
+    assert!(detect_format(quoted).holds_secrets());
+    let parsed = parse_input(quoted, detect_format(quoted)).unwrap();
+    assert_eq!(parsed, json!({"api_key": "sk-123", "db_password": "x"}));
+    let redacted = jdx::ai::prompts::redact_values(&parsed).to_string();
+    assert!(!redacted.contains("sk-123"), "{redacted}");
+
+    assert_eq!(detect_format_from_name("/app/.env"), Some(DataFormat::Env));
+    assert_eq!(detect_format_from_name(".env.local"), Some(DataFormat::Env));
+    assert_eq!(detect_format_from_name("prod.env"), Some(DataFormat::Env));
+    assert_eq!(detect_format_from_name("setup.INI"), Some(DataFormat::Ini));
+    assert_eq!(
+        detect_format_from_name("conf/app.properties"),
+        Some(DataFormat::Properties)
+    );
+    assert_eq!(
+        detect_format_from_name("events.mpk"),
+        Some(DataFormat::MsgPack)
+    );
+    assert_eq!(detect_format_from_name("dump.bson"), Some(DataFormat::Bson));
+    assert_eq!(detect_format_from_name("data.json"), None);
+}
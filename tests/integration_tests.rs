@@ -251,6 +251,21 @@ fn test_mode_switch_to_schema() {
     assert!(app.schema.text.is_some());
 }
 
+#[test]
+fn test_schema_view_shows_file_schema() {
+    let data = json!([{"id": 1}]);
+    let mut app = App::new(data, false, true);
+    app.schema.source = Some(
+        "Parquet schema (1 rows in 1 row groups):\nmessage schema {\n  REQUIRED INT64 id;\n}\n"
+            .into(),
+    );
+
+    app.handle_event(key(KeyCode::Char('S')));
+    let text = app.schema.text.as_deref().unwrap();
+    assert!(text.starts_with("Parquet schema"), "{text}");
+    assert!(text.contains("Inferred from data:"), "{text}");
+}
+
 #[test]
 fn test_mode_switch_schema_back_to_query_via_esc() {
     let data = json!({"a": 1});
@@ -437,3 +452,150 @@ fn test_app_missing_path_output() {
     let output = app.get_output();
     assert!(output.is_empty());
 }
+
+#[test]
+fn test_app_secondary_document_lookup() {
+    let data = json!({"orders": [{"id": "o1", "user_id": 1}]});
+    let mut app = App::new(data, false, true);
+    let mut documents = jdx::engine::transform::Documents::new();
+    documents.insert("users".into(), json!([{"id": 1, "name": "Alice"}]));
+    app.set_documents(documents);
+
+    app.query.text = ".orders :lookup $users on user_id=id as user".into();
+    let value = app.current_value().unwrap().unwrap();
+    assert_eq!(value[0]["user"]["name"], "Alice");
+
+    app.query.text = "$users[0].name".into();
+    assert_eq!(app.current_value().unwrap(), Some(json!("Alice")));
+}
+
+#[test]
+fn test_tree_decode_selected_string() {
+    let data = json!({"payload": "{\"user\": \"alice\"}"});
+    let mut app = App::new(data, false, true);
+
+    app.handle_event(ctrl_key('s'));
+    assert_eq!(app.mode, AppMode::Tree);
+
+    app.handle_event(key(KeyCode::Char('d')));
+    assert_eq!(app.query.text, ".payload :parse_json");
+    assert_eq!(app.current_value().unwrap(), Some(json!({"user": "alice"})));
+}
+
+#[test]
+fn test_app_quoted_transform_arguments() {
+    let data = json!({"people": [
+        {"first name": "Ada", "note": "a :b", "tags": ["x", "y"]},
+        {"first name": "Bob", "note": "c", "tags": []}
+    ]});
+    let mut app = App::new(data, false, true);
+
+    app.query.text = r#".people :filter note == "a :b" :pick "first name""#.into();
+    assert_eq!(
+        app.current_value().unwrap(),
+        Some(json!([{"first name": "Ada"}]))
+    );
+
+    app.query.text = r#".people[0].tags :join ", ""#.into();
+    assert_eq!(app.current_value().unwrap(), Some(json!("x, y")));
+    assert_eq!(app.query_error_span(), None);
+}
+
+#[test]
+fn test_app_transform_error_span() {
+    let data = json!({"tags": ["a", "b"]});
+    let mut app = App::new(data, false, true);
+
+    // Unterminated string: highlighted from the opening quote to the end
+    app.query.text = r#".tags :join ", "#.into();
+    assert!(app.current_value().is_err());
+    assert_eq!(app.query_error_span(), Some(12..15));
+
+    // Bad argument: highlighted on the offending token only
+    app.query.text = ".tags :sort name order".into();
+    assert_eq!(app.query_error_span(), Some(17..22));
+
+    // Unknown command
+    app.query.text = ".tags :nope".into();
+    assert_eq!(app.query_error_span(), Some(6..11));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_app_exec_transform() {
+    use jdx::engine::exec::ExecPolicy;
+
+    let data = json!([{"ip": "10.0.0.1"}, {"ip": "10.0.0.2"}]);
+    let mut app = App::new(data, false, true);
+    app.set_exec_policy(ExecPolicy::default());
+
+    // Nothing runs until the command is allow-listed
+    app.query.text = r#". :exec sh -c "cat""#.into();
+    let err = app.current_value().unwrap_err();
+    assert!(err.contains("allow-list"), "{err}");
+
+    app.set_exec_policy(ExecPolicy {
+        allow: vec!["sh".into()],
+        ..ExecPolicy::default()
+    });
+    assert_eq!(
+        app.current_value().unwrap(),
+        Some(json!([{"ip": "10.0.0.1"}, {"ip": "10.0.0.2"}]))
+    );
+
+    // NDJSON input: one line per record in, one record per line out
+    app.ndjson_input = true;
+    app.query.text =
+        r#". :exec sh -c "while read l; do echo '{\"seen\":true}'; done" :count"#.into();
+    assert_eq!(app.current_value().unwrap(), Some(json!(2)));
+
+    app.query.text = r#". :exec sh -c "echo boom >&2; exit 2""#.into();
+    let err = app.current_value().unwrap_err();
+    assert!(err.contains("exited with status 2: boom"), "{err}");
+}
+
+#[test]
+fn test_app_aliases_and_completion() {
+    use jdx::engine::alias;
+
+    alias::configure(
+        [
+            (
+                "live".to_string(),
+                r#".items[status != "deleted"] :pick id,name :sort name"#.to_string(),
+            ),
+            ("top".to_string(), ":sort $1 desc :pick $2".to_string()),
+        ]
+        .into_iter()
+        .collect(),
+    );
+    let data = json!({"items": [
+        {"id": 1, "name": "b", "status": "ok", "score": 5},
+        {"id": 2, "name": "a", "status": "deleted", "score": 9},
+        {"id": 3, "name": "c", "status": "ok", "score": 7}
+    ]});
+    let mut app = App::new(data, false, true);
+
+    app.query.text = ". :@live :count".into();
+    assert_eq!(app.current_value().unwrap(), Some(json!(2)));
+
+    app.query.text = ".items :@top score id,score".into();
+    assert_eq!(
+        app.current_value().unwrap(),
+        Some(json!([{"id": 2, "score": 9}, {"id": 3, "score": 7}, {"id": 1, "score": 5}]))
+    );
+
+    app.query.text = ".items :@top score".into();
+    let err = app.current_value().unwrap_err();
+    assert!(err.contains("takes 2 arguments, got 1"), "{err}");
+
+    app.query.text = ".items :@nope".into();
+    assert_eq!(app.query_error_span(), Some(7..13));
+
+    // Tab completes aliases (and transforms) after ` :`
+    app.query.text = ". :@li".into();
+    app.query.cursor = app.query.text.len();
+    app.handle_event(key(KeyCode::Tab));
+    app.handle_event(key(KeyCode::Enter));
+    assert_eq!(app.query.text, ". :@live");
+}
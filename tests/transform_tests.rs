@@ -1,6 +1,7 @@
 use jdx::engine::json::traverse;
 use jdx::engine::query::parse;
-use jdx::engine::transform::apply_transform;
+use jdx::engine::transform::{apply_transform, pivot_index};
+use jdx::format::{format_output_with, DataFormat, FormatOptions};
 use serde_json::json;
 
 /// Test transforms on fixture data (query + transform combined).
@@ -188,6 +189,53 @@ fn test_transform_chained_three_steps() {
     assert_eq!(result, json!(3));
 }
 
+#[test]
+fn test_transform_sort_multi_key_on_fixture() {
+    let content = std::fs::read_to_string("fixtures/nested.json").unwrap();
+    let data: serde_json::Value = serde_json::from_str(&content).unwrap();
+
+    let segments = parse(".users").unwrap();
+    let result = traverse(&data, &segments);
+    let users = result.value.unwrap();
+
+    let sorted = apply_transform(&users, ":sort role asc, id desc :pick name").unwrap();
+    assert_eq!(
+        sorted,
+        json!([
+            {"name": "Alice"},
+            {"name": "Charlie"},
+            {"name": "Bob"}
+        ])
+    );
+}
+
+#[test]
+fn test_transform_pivot_to_csv() {
+    let data = json!([
+        {"date": "2024-01-02", "metric": "views", "value": 7},
+        {"date": "2024-01-01", "metric": "clicks", "value": 3},
+        {"date": "2024-01-01", "metric": "views", "value": 10}
+    ]);
+
+    let wide = apply_transform(
+        &data,
+        ":pivot index=date columns=metric values=value agg=sum",
+    )
+    .unwrap();
+    // Object keys are sorted; the index columns are put first on output
+    let mut options = FormatOptions::default();
+    options.csv.leading_columns =
+        pivot_index(":pivot index=date columns=metric values=value agg=sum").unwrap();
+    let csv = format_output_with(&wide, DataFormat::Csv, &options).unwrap();
+    assert_eq!(
+        csv,
+        "date,clicks,views
+2024-01-02,,7
+2024-01-01,3,10
+"
+    );
+}
+
 // --- Filter integration tests ---
 
 #[test]
@@ -585,3 +633,133 @@ fn test_compound_filter_mixed_and_or() {
     assert_eq!(arr[2]["name"], "C");
     assert_eq!(arr[3]["name"], "D");
 }
+
+#[test]
+fn test_date_filter_then_daily_buckets() {
+    let data = json!({"orders": [
+        {"id": 1, "created_at": "2023-12-31T22:00:00-05:00", "total": 10},
+        {"id": 2, "created_at": "2024-01-02T09:30:00Z", "total": 5},
+        {"id": 3, "created_at": 1_704_200_400, "total": 7}
+    ]});
+    // Order 1 is 2024-01-01T03:00:00Z once its offset is applied.
+    let segments = parse(".orders[created_at > date(\"2024-01-01\")]").unwrap();
+    let filtered = traverse(&data, &segments).value.unwrap();
+    assert_eq!(filtered.as_array().unwrap().len(), 3);
+
+    let result = apply_transform(
+        &filtered,
+        ":bucket_time 1d field=created_at :group_by created_at",
+    )
+    .unwrap();
+    let days: Vec<&String> = result.as_object().unwrap().keys().collect();
+    assert_eq!(days, ["2024-01-01T00:00:00Z", "2024-01-02T00:00:00Z"]);
+    assert_eq!(result["2024-01-02T00:00:00Z"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn test_regex_extract_then_group() {
+    let data = json!([
+        {"line": "GET /users 200"},
+        {"line": "POST /users 201"},
+        {"line": "GET /orders 500"}
+    ]);
+    let result = apply_transform(
+        &data,
+        r":extract /(?P<method>[A-Z]+) (?P<path>\S+) (?P<status>\d+)/ line",
+    )
+    .unwrap();
+    assert_eq!(
+        result[1]["line"],
+        json!({"method": "POST", "path": "/users", "status": "201"})
+    );
+    let matches = apply_transform(&data, r":match /\s5\d\d$/ line").unwrap();
+    assert_eq!(matches[2]["line"], json!(true));
+    assert_eq!(matches[0]["line"], json!(false));
+}
+
+/// An application embedding jdx registers its own transform, which then works
+/// in chains, takes lexed arguments, and shows up in the AI prompt.
+#[test]
+fn test_registered_transform_in_chain() {
+    use anyhow::Result;
+    use jdx::engine::lexer::Args;
+    use jdx::engine::registry::{self, ArgSpec, Transform, TransformContext};
+    use serde_json::Value;
+
+    struct Scale;
+
+    impl Transform for Scale {
+        fn name(&self) -> &str {
+            ":scale"
+        }
+        fn args(&self) -> ArgSpec {
+            ArgSpec::usage("factor")
+        }
+        fn help(&self) -> &str {
+            "Multiply every number by a factor"
+        }
+        fn apply(&self, value: &Value, args: &Args, _ctx: &TransformContext) -> Result<Value> {
+            let factor: f64 = match args.optional_value()? {
+                Some(f) => f.parse()?,
+                None => anyhow::bail!(":scale requires a factor"),
+            };
+            let arr = value.as_array().cloned().unwrap_or_default();
+            Ok(arr
+                .iter()
+                .map(|v| json!(v.as_f64().unwrap_or(0.0) * factor))
+                .collect())
+        }
+    }
+
+    registry::register(Scale).unwrap();
+    let result = apply_transform(&json!([1, 2, 3]), ":scale 2 :sum").unwrap();
+    assert_eq!(result, json!(12));
+    assert!(apply_transform(&json!([1]), ":scale").is_err());
+
+    let prompt = jdx::ai::prompts::build_system_prompt("{}");
+    assert!(prompt.contains(":scale factor — Multiply every number by a factor"));
+}
+
+#[test]
+fn test_script_then_builtin_transforms() {
+    let data = json!([
+        {"name": "a", "price": 10, "qty": 2},
+        {"name": "b", "price": 5, "qty": 0},
+        {"name": "c", "price": 1.5, "qty": 4}
+    ]);
+    let result = apply_transform(
+        &data,
+        r##":script "if it.qty > 0 { #{ name: it.name, total: it.price * it.qty } }" :sort total"##,
+    )
+    .unwrap();
+    assert_eq!(
+        result,
+        json!([{"name": "c", "total": 6.0}, {"name": "a", "total": 20}])
+    );
+}
+
+#[test]
+fn test_mutation_transforms_edit_whole_document() {
+    let doc = json!({
+        "metadata": {"name": "web", "managedFields": [{"manager": "kubectl"}]},
+        "spec": {"replicas": 1},
+        "users": [{"mail": "a@x.io"}, {"mail": "b@x.io", "role": "admin"}]
+    });
+    let result = apply_transform(
+        &doc,
+        ":set .spec.replicas 3 :del .metadata.managedFields :set .users[*].active true",
+    )
+    .unwrap();
+    assert_eq!(result["spec"]["replicas"], json!(3));
+    assert_eq!(result["metadata"], json!({"name": "web"}));
+    assert_eq!(result["users"][1]["active"], json!(true));
+
+    let users = apply_transform(&doc["users"], ":rename mail=email :default role=user").unwrap();
+    assert_eq!(
+        users,
+        json!([
+            {"email": "a@x.io", "role": "user"},
+            {"email": "b@x.io", "role": "admin"}
+        ])
+    );
+}
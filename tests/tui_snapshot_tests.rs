@@ -21,6 +21,7 @@ fn test_query_input_renders_prompt() {
                 cursor: 14,
                 completion: None,
                 error: false,
+                error_span: None,
                 focused: true,
             };
             frame.render_widget(widget, area);
@@ -46,6 +47,7 @@ fn test_query_input_with_completion() {
                 cursor: 3,
                 completion: Some("ers"),
                 error: false,
+                error_span: None,
                 focused: true,
             };
             frame.render_widget(widget, area);
@@ -58,6 +60,33 @@ fn test_query_input_with_completion() {
     assert!(line.contains("ers"), "should contain completion");
 }
 
+#[test]
+fn test_query_input_highlights_error_span() {
+    let backend = TestBackend::new(60, 1);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal
+        .draw(|frame| {
+            let area = frame.area();
+            let widget = QueryInputWidget {
+                query: ".tags :join \", ",
+                cursor: 15,
+                completion: None,
+                error: true,
+                error_span: Some(12..15),
+                focused: true,
+            };
+            frame.render_widget(widget, area);
+        })
+        .unwrap();
+
+    let buf = terminal.backend().buffer().clone();
+    let prompt_len = "[Filter]> ".len() as u16;
+    assert_ne!(buf[(prompt_len + 11, 0)].bg, ratatui::style::Color::Red);
+    assert_eq!(buf[(prompt_len + 12, 0)].bg, ratatui::style::Color::Red);
+    assert_eq!(buf[(prompt_len + 14, 0)].bg, ratatui::style::Color::Red);
+}
+
 #[test]
 fn test_json_view_renders_value() {
     let backend = TestBackend::new(40, 10);
@@ -218,7 +247,7 @@ use jdx::modes::AppMode;
 use jdx::widgets::ai_panel::AiPanelWidget;
 use jdx::widgets::candidate_popup::CandidatePopupWidget;
 use jdx::widgets::help_overlay::HelpOverlayWidget;
-use jdx::widgets::tree_view::{build_tree, TreeNode, TreeViewWidget};
+use jdx::widgets::tree_view::{build_tree, label_documents, TreeNode, TreeViewWidget};
 use std::collections::HashSet;
 
 #[test]
@@ -557,6 +586,47 @@ fn test_tree_view_expanded() {
     assert!(all_text.contains("zip"), "should show expanded child key");
 }
 
+#[test]
+fn test_tree_view_labels_yaml_documents() {
+    let data = json!([
+        {"kind": "Service", "metadata": {"name": "web"}},
+        {"replicas": 2}
+    ]);
+    let mut nodes = build_tree(&data, &HashSet::new());
+    label_documents(&mut nodes, &data);
+
+    let backend = TestBackend::new(50, 6);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal
+        .draw(|frame| {
+            let area = frame.area();
+            let widget = TreeViewWidget {
+                nodes: &nodes,
+                selected: 0,
+                scroll: 0,
+            };
+            frame.render_widget(widget, area);
+        })
+        .unwrap();
+
+    let buf = terminal.backend().buffer().clone();
+    let all_text = buffer_to_string(&buf);
+    assert!(
+        all_text.contains("--- doc 1"),
+        "should label first document"
+    );
+    assert!(
+        all_text.contains("Service web"),
+        "should preview kind and name"
+    );
+    assert!(
+        all_text.contains("--- doc 2"),
+        "should label second document"
+    );
+    assert_eq!(nodes[1].path, "[1]", "paths stay queryable");
+}
+
 #[test]
 fn test_tree_view_empty() {
     let nodes: Vec<TreeNode> = vec![];
@@ -613,6 +683,28 @@ fn test_help_overlay_query_mode() {
     );
 }
 
+#[test]
+fn test_help_overlay_lists_registered_transforms() {
+    let backend = TestBackend::new(90, 50);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal
+        .draw(|frame| {
+            let area = frame.area();
+            let widget = HelpOverlayWidget {
+                mode: AppMode::Query,
+            };
+            frame.render_widget(widget, area);
+        })
+        .unwrap();
+
+    let buf = terminal.backend().buffer().clone();
+    let all_text = buffer_to_string(&buf);
+    for name in jdx::engine::registry::snapshot().names() {
+        assert!(all_text.contains(&name), "help should list {name}");
+    }
+}
+
 #[test]
 fn test_help_overlay_tree_mode() {
     let backend = TestBackend::new(70, 30);